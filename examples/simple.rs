@@ -4,12 +4,13 @@ extern crate midgar;
 use std::rc::Rc;
 
 use midgar::{App, Midgar, MidgarApp, MidgarAppConfig, Surface, KeyCode};
+use midgar::graphics::camera::Camera2D;
 use midgar::graphics::sprite::{Sprite, SpriteDrawParams, SpriteRenderer};
 
 pub struct GameApp<'a> {
     sprite_renderer: SpriteRenderer,
     sprite: Sprite<'a>,
-    projection: cgmath::Matrix4<f32>,
+    camera: Camera2D,
 
     play: bool,
 }
@@ -26,12 +27,12 @@ impl<'a> App for GameApp<'a> {
         sprite.set_flip_y(true);
 
         let (screen_width, screen_height) = midgar.graphics().screen_size();
-        let projection = cgmath::ortho(0.0, screen_width as f32, 0.0, screen_height as f32, -1.0, 1.0);
+        let camera = Camera2D::new(screen_width as f32, screen_height as f32);
 
         GameApp {
-            sprite_renderer: SpriteRenderer::new(midgar.graphics().display(), projection),
+            sprite_renderer: SpriteRenderer::new(midgar.graphics().display(), camera.combined()).unwrap(),
             sprite: sprite,
-            projection: projection,
+            camera: camera,
             play: false,
         }
     }
@@ -68,8 +69,8 @@ impl<'a> App for GameApp<'a> {
 
     fn resize(&mut self, size: (u32, u32), midgar: &Midgar) {
         println!("Resize: {:?}", size);
-        self.projection = cgmath::ortho(0.0, size.0 as f32, 0.0, size.1 as f32, -1.0, 1.0);
-        self.sprite_renderer.set_projection_matrix(self.projection);
+        self.camera.set_viewport_size(size.0 as f32, size.1 as f32);
+        self.sprite_renderer.set_camera(&self.camera);
     }
 
     fn pause(&mut self, midgar: &Midgar) {
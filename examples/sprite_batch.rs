@@ -42,7 +42,7 @@ impl<'a> App for GameApp<'a> {
         }
 
         GameApp {
-            renderer: SpriteRenderer::new(midgar.graphics().display(), projection),
+            renderer: SpriteRenderer::new(midgar.graphics().display(), projection).unwrap(),
             sprite: sprite,
             sprite_positions: sprite_positions,
             projection: projection,
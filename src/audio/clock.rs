@@ -0,0 +1,338 @@
+use audio::{Audio, BusId, Sound, VoiceId};
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeSignature {
+    pub beats_per_bar: u32,
+    pub beat_value: u32,
+}
+
+impl TimeSignature {
+    pub fn new(beats_per_bar: u32, beat_value: u32) -> Self {
+        TimeSignature {
+            beats_per_bar: beats_per_bar.max(1),
+            beat_value: beat_value,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TempoSegment {
+    start_beat: f64,
+    start_seconds: f64,
+    start_bar: f64,
+    bpm: f64,
+    signature: TimeSignature,
+}
+
+/// Maps between time in seconds and musical time in beats and bars.
+///
+/// Tempo and time signature changes happen on beats. A time signature change always starts a new
+/// bar.
+#[derive(Clone, Debug)]
+pub struct TempoMap {
+    segments: Vec<TempoSegment>,
+}
+
+impl TempoMap {
+    pub fn new(bpm: f64, signature: TimeSignature) -> Self {
+        TempoMap {
+            segments: vec![TempoSegment {
+                start_beat: 0.0,
+                start_seconds: 0.0,
+                start_bar: 0.0,
+                bpm: bpm,
+                signature: signature,
+            }],
+        }
+    }
+
+    /// Change the tempo and time signature starting at `beat`.
+    pub fn add_change(&mut self, beat: f64, bpm: f64, signature: TimeSignature) {
+        let index = self.segments.iter()
+            .position(|segment| segment.start_beat >= beat)
+            .unwrap_or(self.segments.len());
+        let segment = TempoSegment {
+            start_beat: beat.max(0.0),
+            start_seconds: 0.0,
+            start_bar: 0.0,
+            bpm: bpm,
+            signature: signature,
+        };
+        if index < self.segments.len() && self.segments[index].start_beat == beat {
+            self.segments[index] = segment;
+        } else {
+            self.segments.insert(index, segment);
+        }
+
+        // Recompute where each segment starts.
+        for i in 1..self.segments.len() {
+            let prev = self.segments[i - 1];
+            let beats = self.segments[i].start_beat - prev.start_beat;
+            self.segments[i].start_seconds = prev.start_seconds + beats * 60.0 / prev.bpm;
+            let bars = beats / prev.signature.beats_per_bar as f64;
+            self.segments[i].start_bar = prev.start_bar + bars.ceil();
+        }
+    }
+
+    fn segment_for_seconds(&self, seconds: f64) -> &TempoSegment {
+        self.segments.iter()
+            .rev()
+            .find(|segment| segment.start_seconds <= seconds)
+            .unwrap_or(&self.segments[0])
+    }
+
+    fn segment_for_beat(&self, beat: f64) -> &TempoSegment {
+        self.segments.iter()
+            .rev()
+            .find(|segment| segment.start_beat <= beat)
+            .unwrap_or(&self.segments[0])
+    }
+
+    pub fn beat_at(&self, seconds: f64) -> f64 {
+        let segment = self.segment_for_seconds(seconds);
+        segment.start_beat + (seconds - segment.start_seconds) * segment.bpm / 60.0
+    }
+
+    pub fn seconds_at(&self, beat: f64) -> f64 {
+        let segment = self.segment_for_beat(beat);
+        segment.start_seconds + (beat - segment.start_beat) * 60.0 / segment.bpm
+    }
+
+    /// Get the bar a beat falls in and the (fractional) beat within that bar.
+    pub fn bar_at(&self, beat: f64) -> (i64, f64) {
+        let segment = self.segment_for_beat(beat);
+        let beats_per_bar = segment.signature.beats_per_bar as f64;
+        let beats = beat - segment.start_beat;
+        let bar = segment.start_bar + (beats / beats_per_bar).floor();
+        let beat_in_bar = beats - (beats / beats_per_bar).floor() * beats_per_bar;
+        (bar as i64, beat_in_bar)
+    }
+
+    pub fn bpm_at(&self, beat: f64) -> f64 {
+        self.segment_for_beat(beat).bpm
+    }
+
+    pub fn signature_at(&self, beat: f64) -> TimeSignature {
+        self.segment_for_beat(beat).signature
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BeatEvent {
+    pub beat: i64,
+    pub bar: i64,
+    pub beat_in_bar: u32,
+}
+
+impl BeatEvent {
+    pub fn is_downbeat(&self) -> bool {
+        self.beat_in_bar == 0
+    }
+}
+
+/// Follows a piece of music using the audio clock rather than `Time`, so beat-driven gameplay
+/// stays locked to what the player hears.
+pub struct Conductor {
+    tempo_map: TempoMap,
+    sample_rate: u32,
+    /// Seconds from the start of the sound to beat 0.
+    first_beat_offset: f64,
+
+    voice: Option<VoiceId>,
+    start_frame: Option<u64>,
+    position: f64,
+    last_beat: Option<i64>,
+
+    beat_callbacks: Vec<Box<FnMut(BeatEvent)>>,
+    bar_callbacks: Vec<Box<FnMut(BeatEvent)>>,
+}
+
+impl Conductor {
+    pub fn new(tempo_map: TempoMap) -> Self {
+        Conductor {
+            tempo_map: tempo_map,
+            sample_rate: 0,
+            first_beat_offset: 0.0,
+
+            voice: None,
+            start_frame: None,
+            position: 0.0,
+            last_beat: None,
+
+            beat_callbacks: Vec::new(),
+            bar_callbacks: Vec::new(),
+        }
+    }
+
+    pub fn with_first_beat_offset(mut self, seconds: f64) -> Self {
+        self.first_beat_offset = seconds;
+        self
+    }
+
+    pub fn tempo_map(&self) -> &TempoMap {
+        &self.tempo_map
+    }
+
+    pub fn tempo_map_mut(&mut self) -> &mut TempoMap {
+        &mut self.tempo_map
+    }
+
+    /// Start playing the music and follow it.
+    pub fn play(&mut self, audio: &Audio, music: &Sound, bus: BusId) -> VoiceId {
+        let mut mixer = audio.mixer();
+        let start_frame = mixer.frames_rendered();
+        let voice = mixer.play_at(music, bus, start_frame);
+        self.follow(voice, start_frame, mixer.sample_rate());
+        voice
+    }
+
+    /// Follow music that was started on the mixer at `start_frame`.
+    pub fn follow(&mut self, voice: VoiceId, start_frame: u64, sample_rate: u32) {
+        self.voice = Some(voice);
+        self.start_frame = Some(start_frame);
+        self.sample_rate = sample_rate;
+        self.position = -self.first_beat_offset;
+        self.last_beat = None;
+    }
+
+    pub fn stop(&mut self, audio: &Audio) {
+        if let Some(voice) = self.voice.take() {
+            audio.mixer().stop(voice);
+        }
+        self.start_frame = None;
+    }
+
+    pub fn voice(&self) -> Option<VoiceId> {
+        self.voice
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.start_frame.is_some()
+    }
+
+    pub fn on_beat<F: FnMut(BeatEvent) + 'static>(&mut self, callback: F) {
+        self.beat_callbacks.push(Box::new(callback));
+    }
+
+    pub fn on_bar<F: FnMut(BeatEvent) + 'static>(&mut self, callback: F) {
+        self.bar_callbacks.push(Box::new(callback));
+    }
+
+    /// Update the position from the audio clock and fire beat and bar callbacks. Call this once
+    /// per frame. Returns the beats that were crossed since the last update.
+    pub fn update(&mut self, audio: &Audio) -> Vec<BeatEvent> {
+        let frame = audio.playback_frame();
+        self.update_at_frame(frame)
+    }
+
+    /// Like `update`, but with an explicit playback frame. Useful when rendering offline.
+    pub fn update_at_frame(&mut self, playback_frame: f64) -> Vec<BeatEvent> {
+        let start_frame = match self.start_frame {
+            Some(start_frame) => start_frame,
+            None => return Vec::new(),
+        };
+
+        // Never run backwards, even if the latency estimate jitters.
+        let position = (playback_frame - start_frame as f64) / self.sample_rate as f64 - self.first_beat_offset;
+        self.position = self.position.max(position);
+
+        let current_beat = self.beat().floor() as i64;
+        let first_beat = match self.last_beat {
+            Some(last_beat) => last_beat + 1,
+            None => 0,
+        };
+
+        let mut events = Vec::new();
+        for beat in first_beat..(current_beat + 1) {
+            let (bar, beat_in_bar) = self.tempo_map.bar_at(beat as f64);
+            events.push(BeatEvent {
+                beat: beat,
+                bar: bar,
+                beat_in_bar: beat_in_bar.round() as u32,
+            });
+        }
+        if current_beat >= first_beat {
+            self.last_beat = Some(current_beat);
+        }
+
+        for event in &events {
+            for callback in &mut self.beat_callbacks {
+                callback(*event);
+            }
+            if event.is_downbeat() {
+                for callback in &mut self.bar_callbacks {
+                    callback(*event);
+                }
+            }
+        }
+
+        events
+    }
+
+    /// The latency-compensated song position in seconds, relative to beat 0.
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    pub fn beat(&self) -> f64 {
+        self.tempo_map.beat_at(self.position)
+    }
+
+    pub fn bar(&self) -> (i64, f64) {
+        self.tempo_map.bar_at(self.beat())
+    }
+
+    /// The mixer frame on which `beat` will be rendered.
+    pub fn frame_for_beat(&self, beat: f64) -> Option<u64> {
+        self.start_frame.map(|start_frame| {
+            let seconds = self.tempo_map.seconds_at(beat) + self.first_beat_offset;
+            start_frame + (seconds.max(0.0) * self.sample_rate as f64).round() as u64
+        })
+    }
+
+    /// Schedule `sound` to start exactly on `beat`. Returns `None` if no music is playing.
+    pub fn schedule(&self, audio: &Audio, sound: &Sound, bus: BusId, beat: f64) -> Option<VoiceId> {
+        self.frame_for_beat(beat)
+            .map(|frame| audio.mixer().play_at(sound, bus, frame))
+    }
+
+    /// Schedule `sound` on the next whole beat after the current position.
+    pub fn schedule_next_beat(&self, audio: &Audio, sound: &Sound, bus: BusId) -> Option<VoiceId> {
+        let next_beat = self.beat().floor() + 1.0;
+        self.schedule(audio, sound, bus, next_beat)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use audio::{Mixer, Sound, MASTER_BUS};
+    use super::{Conductor, TempoMap, TimeSignature};
+
+    #[test]
+    fn tempo_changes_map_beats_and_seconds() {
+        let mut map = TempoMap::new(120.0, TimeSignature::new(4, 4));
+        map.add_change(8.0, 60.0, TimeSignature::new(3, 4));
+
+        assert_eq!(map.seconds_at(8.0), 4.0);
+        assert_eq!(map.seconds_at(10.0), 6.0);
+        assert_eq!(map.beat_at(6.0), 10.0);
+        assert_eq!(map.bar_at(7.0), (1, 3.0));
+        assert_eq!(map.bar_at(11.0), (3, 0.0));
+    }
+
+    #[test]
+    fn conductor_reports_crossed_beats() {
+        let mut conductor = Conductor::new(TempoMap::new(60.0, TimeSignature::new(4, 4)));
+        let voice = Mixer::new(1000).play(&Sound::from_mono(&[0.0], 1000), MASTER_BUS);
+        conductor.follow(voice, 100, 1000);
+
+        let events = conductor.update_at_frame(100.0);
+        assert_eq!(events.len(), 1);
+        assert!(events[0].is_downbeat());
+
+        let events = conductor.update_at_frame(2600.0);
+        assert_eq!(events.iter().map(|event| event.beat).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(conductor.frame_for_beat(4.0), Some(4100));
+    }
+}
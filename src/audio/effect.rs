@@ -0,0 +1,438 @@
+use std::f32::consts::PI;
+
+use audio::param::{Param, db_to_gain, gain_to_db};
+
+
+/// An audio effect that processes a bus's interleaved stereo buffer in place.
+pub trait Effect: Send {
+    fn process(&mut self, buffer: &mut [f32], sample_rate: u32);
+
+    /// Look up an animatable parameter by name.
+    fn param_mut(&mut self, name: &str) -> Option<&mut Param>;
+
+    /// Clear any internal state, e.g. delay lines and filter history.
+    fn reset(&mut self) {}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterKind {
+    LowPass,
+    HighPass,
+}
+
+/// A resonant biquad filter. See the Audio EQ Cookbook by Robert Bristow-Johnson.
+///
+/// Parameters: `cutoff` (Hz) and `q`.
+pub struct Filter {
+    kind: FilterKind,
+    cutoff: Param,
+    q: Param,
+
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    // Per-channel history: x[n-1], x[n-2], y[n-1], y[n-2].
+    history: [[f32; 4]; 2],
+}
+
+impl Filter {
+    pub fn new(kind: FilterKind, cutoff: f32, q: f32) -> Self {
+        Filter {
+            kind: kind,
+            cutoff: Param::new(cutoff),
+            q: Param::new(q),
+
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+            history: [[0.0; 4]; 2],
+        }
+    }
+
+    pub fn low_pass(cutoff: f32) -> Self {
+        Filter::new(FilterKind::LowPass, cutoff, 0.707)
+    }
+
+    pub fn high_pass(cutoff: f32) -> Self {
+        Filter::new(FilterKind::HighPass, cutoff, 0.707)
+    }
+
+    pub fn kind(&self) -> FilterKind {
+        self.kind
+    }
+
+    fn update_coefficients(&mut self, sample_rate: u32) {
+        let nyquist = sample_rate as f32 * 0.5;
+        let cutoff = self.cutoff.value().max(10.0).min(nyquist * 0.99);
+        let q = self.q.value().max(0.01);
+
+        let w0 = 2.0 * PI * cutoff / sample_rate as f32;
+        let (sin_w0, cos_w0) = w0.sin_cos();
+        let alpha = sin_w0 / (2.0 * q);
+
+        let (b0, b1, b2) = match self.kind {
+            FilterKind::LowPass => ((1.0 - cos_w0) / 2.0, 1.0 - cos_w0, (1.0 - cos_w0) / 2.0),
+            FilterKind::HighPass => ((1.0 + cos_w0) / 2.0, -(1.0 + cos_w0), (1.0 + cos_w0) / 2.0),
+        };
+        let a0 = 1.0 + alpha;
+        let a1 = -2.0 * cos_w0;
+        let a2 = 1.0 - alpha;
+
+        self.b0 = b0 / a0;
+        self.b1 = b1 / a0;
+        self.b2 = b2 / a0;
+        self.a1 = a1 / a0;
+        self.a2 = a2 / a0;
+    }
+}
+
+impl Effect for Filter {
+    fn process(&mut self, buffer: &mut [f32], sample_rate: u32) {
+        let frames = (buffer.len() / 2) as u32;
+        self.cutoff.advance(frames, sample_rate);
+        self.q.advance(frames, sample_rate);
+        self.update_coefficients(sample_rate);
+
+        for frame in buffer.chunks_mut(2) {
+            for (sample, h) in frame.iter_mut().zip(self.history.iter_mut()) {
+                let x = *sample;
+                let y = self.b0 * x + self.b1 * h[0] + self.b2 * h[1] - self.a1 * h[2] - self.a2 * h[3];
+                h[1] = h[0];
+                h[0] = x;
+                h[3] = h[2];
+                h[2] = y;
+                *sample = y;
+            }
+        }
+    }
+
+    fn param_mut(&mut self, name: &str) -> Option<&mut Param> {
+        match name {
+            "cutoff" => Some(&mut self.cutoff),
+            "q" => Some(&mut self.q),
+            _ => None,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.history = [[0.0; 4]; 2];
+    }
+}
+
+const MAX_DELAY_SECONDS: f32 = 2.0;
+
+/// A feedback delay line.
+///
+/// Parameters: `time` (seconds, up to 2), `feedback` (0..1) and `mix` (0..1).
+pub struct Delay {
+    time: Param,
+    feedback: Param,
+    mix: Param,
+
+    line: Vec<f32>,
+    write_pos: usize,
+}
+
+impl Delay {
+    pub fn new(time: f32, feedback: f32, mix: f32) -> Self {
+        Delay {
+            time: Param::new(time),
+            feedback: Param::new(feedback),
+            mix: Param::new(mix),
+
+            line: Vec::new(),
+            write_pos: 0,
+        }
+    }
+}
+
+impl Effect for Delay {
+    fn process(&mut self, buffer: &mut [f32], sample_rate: u32) {
+        // Lazily allocate the delay line once the sample rate is known.
+        let line_frames = (MAX_DELAY_SECONDS * sample_rate as f32) as usize + 1;
+        if self.line.len() != line_frames * 2 {
+            self.line = vec![0.0; line_frames * 2];
+            self.write_pos = 0;
+        }
+
+        for frame in buffer.chunks_mut(2) {
+            let time = self.time.next(sample_rate).max(0.0).min(MAX_DELAY_SECONDS);
+            let feedback = self.feedback.next(sample_rate).max(0.0).min(0.99);
+            let mix = self.mix.next(sample_rate).max(0.0).min(1.0);
+
+            let delay_frames = ((time * sample_rate as f32) as usize).max(1).min(line_frames - 1);
+            let read_pos = (self.write_pos + line_frames - delay_frames) % line_frames;
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                let delayed = self.line[read_pos * 2 + channel];
+                self.line[self.write_pos * 2 + channel] = *sample + delayed * feedback;
+                *sample = *sample * (1.0 - mix) + delayed * mix;
+            }
+            self.write_pos = (self.write_pos + 1) % line_frames;
+        }
+    }
+
+    fn param_mut(&mut self, name: &str) -> Option<&mut Param> {
+        match name {
+            "time" => Some(&mut self.time),
+            "feedback" => Some(&mut self.feedback),
+            "mix" => Some(&mut self.mix),
+            _ => None,
+        }
+    }
+
+    fn reset(&mut self) {
+        for sample in &mut self.line {
+            *sample = 0.0;
+        }
+    }
+}
+
+// Freeverb tunings, in samples at 44.1kHz.
+const COMB_TUNINGS: [usize; 4] = [1116, 1188, 1277, 1356];
+const ALLPASS_TUNINGS: [usize; 2] = [556, 441];
+const STEREO_SPREAD: usize = 23;
+
+struct Comb {
+    buffer: Vec<f32>,
+    pos: usize,
+    filter_store: f32,
+}
+
+impl Comb {
+    fn new(size: usize) -> Self {
+        Comb {
+            buffer: vec![0.0; size.max(1)],
+            pos: 0,
+            filter_store: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let output = self.buffer[self.pos];
+        self.filter_store = output * (1.0 - damping) + self.filter_store * damping;
+        self.buffer[self.pos] = input + self.filter_store * feedback;
+        self.pos = (self.pos + 1) % self.buffer.len();
+        output
+    }
+}
+
+struct AllPass {
+    buffer: Vec<f32>,
+    pos: usize,
+}
+
+impl AllPass {
+    fn new(size: usize) -> Self {
+        AllPass {
+            buffer: vec![0.0; size.max(1)],
+            pos: 0,
+        }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let buffered = self.buffer[self.pos];
+        self.buffer[self.pos] = input + buffered * 0.5;
+        self.pos = (self.pos + 1) % self.buffer.len();
+        buffered - input
+    }
+}
+
+/// A small Freeverb-style reverb.
+///
+/// Parameters: `room_size` (0..1), `damping` (0..1) and `mix` (0..1).
+pub struct Reverb {
+    room_size: Param,
+    damping: Param,
+    mix: Param,
+
+    sample_rate: u32,
+    combs: [Vec<Comb>; 2],
+    allpasses: [Vec<AllPass>; 2],
+}
+
+impl Reverb {
+    pub fn new(room_size: f32, damping: f32, mix: f32) -> Self {
+        Reverb {
+            room_size: Param::new(room_size),
+            damping: Param::new(damping),
+            mix: Param::new(mix),
+
+            sample_rate: 0,
+            combs: [Vec::new(), Vec::new()],
+            allpasses: [Vec::new(), Vec::new()],
+        }
+    }
+
+    fn allocate(&mut self, sample_rate: u32) {
+        let scale = sample_rate as f32 / 44100.0;
+        let scaled = |size: usize| (size as f32 * scale) as usize;
+        for channel in 0..2 {
+            let spread = channel * STEREO_SPREAD;
+            self.combs[channel] = COMB_TUNINGS.iter()
+                .map(|&size| Comb::new(scaled(size + spread)))
+                .collect();
+            self.allpasses[channel] = ALLPASS_TUNINGS.iter()
+                .map(|&size| AllPass::new(scaled(size + spread)))
+                .collect();
+        }
+        self.sample_rate = sample_rate;
+    }
+}
+
+impl Effect for Reverb {
+    fn process(&mut self, buffer: &mut [f32], sample_rate: u32) {
+        if self.sample_rate != sample_rate {
+            self.allocate(sample_rate);
+        }
+
+        let frames = (buffer.len() / 2) as u32;
+        let feedback = 0.7 + 0.28 * self.room_size.advance(frames, sample_rate).max(0.0).min(1.0);
+        let damping = 0.4 * self.damping.advance(frames, sample_rate).max(0.0).min(1.0);
+        let mix = self.mix.advance(frames, sample_rate).max(0.0).min(1.0);
+
+        for frame in buffer.chunks_mut(2) {
+            // Feed both channels the same mono input, as Freeverb does.
+            let input = (frame[0] + frame[1]) * 0.015;
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                let mut wet = 0.0;
+                for comb in &mut self.combs[channel] {
+                    wet += comb.process(input, feedback, damping);
+                }
+                for allpass in &mut self.allpasses[channel] {
+                    wet = allpass.process(wet);
+                }
+                *sample = *sample * (1.0 - mix) + wet * mix;
+            }
+        }
+    }
+
+    fn param_mut(&mut self, name: &str) -> Option<&mut Param> {
+        match name {
+            "room_size" => Some(&mut self.room_size),
+            "damping" => Some(&mut self.damping),
+            "mix" => Some(&mut self.mix),
+            _ => None,
+        }
+    }
+
+    fn reset(&mut self) {
+        // Force the delay lines to be reallocated on the next block.
+        self.sample_rate = 0;
+    }
+}
+
+/// Tracks the level of a signal with separate attack and release times.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EnvelopeFollower {
+    level: f32,
+}
+
+impl EnvelopeFollower {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    pub fn process(&mut self, input: f32, attack: f32, release: f32, sample_rate: u32) -> f32 {
+        let input = input.abs();
+        let time = if input > self.level { attack } else { release };
+        let coefficient = time_coefficient(time, sample_rate);
+        self.level = input + coefficient * (self.level - input);
+        self.level
+    }
+}
+
+/// The one-pole smoothing coefficient for a time constant in seconds.
+pub fn time_coefficient(seconds: f32, sample_rate: u32) -> f32 {
+    if seconds <= 0.0 {
+        0.0
+    } else {
+        (-1.0 / (seconds * sample_rate as f32)).exp()
+    }
+}
+
+/// A feed-forward peak compressor. With a very high ratio and a fast attack it acts as a limiter.
+///
+/// Parameters: `threshold` (dB), `ratio`, `attack` (seconds), `release` (seconds) and
+/// `makeup` (dB).
+pub struct Compressor {
+    threshold: Param,
+    ratio: Param,
+    attack: Param,
+    release: Param,
+    makeup: Param,
+
+    envelope: EnvelopeFollower,
+}
+
+impl Compressor {
+    pub fn new(threshold_db: f32, ratio: f32, attack: f32, release: f32) -> Self {
+        Compressor {
+            threshold: Param::new(threshold_db),
+            ratio: Param::new(ratio),
+            attack: Param::new(attack),
+            release: Param::new(release),
+            makeup: Param::new(0.0),
+
+            envelope: EnvelopeFollower::new(),
+        }
+    }
+
+    pub fn limiter(threshold_db: f32) -> Self {
+        Compressor::new(threshold_db, 1000.0, 0.0005, 0.05)
+    }
+
+    /// The gain reduction applied to the last processed frame, in dB.
+    pub fn gain_reduction(&self) -> f32 {
+        let level_db = gain_to_db(self.envelope.level());
+        let threshold = self.threshold.value();
+        if level_db <= threshold {
+            0.0
+        } else {
+            let ratio = self.ratio.value().max(1.0);
+            (level_db - threshold) * (1.0 - 1.0 / ratio)
+        }
+    }
+}
+
+impl Effect for Compressor {
+    fn process(&mut self, buffer: &mut [f32], sample_rate: u32) {
+        let frames = (buffer.len() / 2) as u32;
+        self.threshold.advance(frames, sample_rate);
+        self.ratio.advance(frames, sample_rate);
+        let attack = self.attack.advance(frames, sample_rate);
+        let release = self.release.advance(frames, sample_rate);
+        let makeup = db_to_gain(self.makeup.advance(frames, sample_rate));
+
+        for frame in buffer.chunks_mut(2) {
+            let peak = frame[0].abs().max(frame[1].abs());
+            self.envelope.process(peak, attack, release, sample_rate);
+            let gain = db_to_gain(-self.gain_reduction()) * makeup;
+            for sample in frame.iter_mut() {
+                *sample *= gain;
+            }
+        }
+    }
+
+    fn param_mut(&mut self, name: &str) -> Option<&mut Param> {
+        match name {
+            "threshold" => Some(&mut self.threshold),
+            "ratio" => Some(&mut self.ratio),
+            "attack" => Some(&mut self.attack),
+            "release" => Some(&mut self.release),
+            "makeup" => Some(&mut self.makeup),
+            _ => None,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.envelope = EnvelopeFollower::new();
+    }
+}
@@ -0,0 +1,497 @@
+use audio::effect::{Effect, time_coefficient};
+use audio::param::{Param, db_to_gain};
+use audio::sound::Sound;
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BusId(usize);
+
+/// The bus every other bus eventually routes into. Its output is what gets played.
+pub const MASTER_BUS: BusId = BusId(0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VoiceId(u64);
+
+/// Reduces a bus's gain while another bus is active, e.g. to duck music under dialogue.
+#[derive(Clone, Copy, Debug)]
+pub struct Ducking {
+    pub sidechain: BusId,
+    /// How far to reduce the gain, in dB.
+    pub amount: f32,
+    /// The sidechain level above which ducking kicks in, in dB.
+    pub threshold: f32,
+    pub attack: f32,
+    pub release: f32,
+}
+
+impl Ducking {
+    pub fn new(sidechain: BusId, amount: f32) -> Self {
+        Ducking {
+            sidechain: sidechain,
+            amount: amount,
+            threshold: -40.0,
+            attack: 0.1,
+            release: 0.5,
+        }
+    }
+}
+
+pub struct Bus {
+    name: String,
+    output: Option<BusId>,
+    gain: Param,
+    muted: bool,
+    effects: Vec<Box<Effect>>,
+    ducking: Option<Ducking>,
+
+    buffer: Vec<f32>,
+    duck_gain: f32,
+    peak: f32,
+}
+
+impl Bus {
+    fn new(name: &str, output: Option<BusId>) -> Self {
+        Bus {
+            name: name.into(),
+            output: output,
+            gain: Param::new(1.0),
+            muted: false,
+            effects: Vec::new(),
+            ducking: None,
+
+            buffer: Vec::new(),
+            duck_gain: 1.0,
+            peak: 0.0,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn output(&self) -> Option<BusId> {
+        self.output
+    }
+
+    pub fn gain(&self) -> &Param {
+        &self.gain
+    }
+
+    pub fn gain_mut(&mut self) -> &mut Param {
+        &mut self.gain
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    pub fn muted(&self) -> bool {
+        self.muted
+    }
+
+    /// Append an effect to the end of this bus's chain and return its index.
+    pub fn add_effect<E: Effect + 'static>(&mut self, effect: E) -> usize {
+        self.effects.push(Box::new(effect));
+        self.effects.len() - 1
+    }
+
+    pub fn remove_effect(&mut self, index: usize) -> Box<Effect> {
+        self.effects.remove(index)
+    }
+
+    pub fn effect_mut(&mut self, index: usize) -> Option<&mut Box<Effect>> {
+        self.effects.get_mut(index)
+    }
+
+    /// Shortcut for looking up an effect's parameter, e.g. to animate a filter cutoff.
+    pub fn effect_param_mut(&mut self, index: usize, name: &str) -> Option<&mut Param> {
+        self.effects.get_mut(index).and_then(|effect| effect.param_mut(name))
+    }
+
+    pub fn num_effects(&self) -> usize {
+        self.effects.len()
+    }
+
+    pub fn set_ducking(&mut self, ducking: Option<Ducking>) {
+        self.ducking = ducking;
+    }
+
+    pub fn ducking(&self) -> Option<Ducking> {
+        self.ducking
+    }
+
+    /// The peak sample value output by this bus during the last rendered block.
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+}
+
+pub struct Voice {
+    id: VoiceId,
+    sound: Sound,
+    bus: BusId,
+    gain: Param,
+    pitch: f32,
+    looping: bool,
+
+    position: f64,
+    start_frame: u64,
+    finished: bool,
+}
+
+impl Voice {
+    pub fn id(&self) -> VoiceId {
+        self.id
+    }
+
+    pub fn sound(&self) -> &Sound {
+        &self.sound
+    }
+
+    pub fn bus(&self) -> BusId {
+        self.bus
+    }
+
+    pub fn gain_mut(&mut self) -> &mut Param {
+        &mut self.gain
+    }
+
+    pub fn set_pitch(&mut self, pitch: f32) {
+        self.pitch = pitch.max(0.0);
+    }
+
+    pub fn pitch(&self) -> f32 {
+        self.pitch
+    }
+
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    pub fn looping(&self) -> bool {
+        self.looping
+    }
+
+    /// The current playback position within the sound, in seconds.
+    pub fn position(&self) -> f64 {
+        self.position / self.sound.sample_rate() as f64
+    }
+
+    pub fn stop(&mut self) {
+        self.finished = true;
+    }
+}
+
+/// A software mixer that routes voices through buses and their effect chains.
+///
+/// The mixer does not depend on an audio device, so it can be rendered offline into any buffer.
+pub struct Mixer {
+    sample_rate: u32,
+    buses: Vec<Bus>,
+    voices: Vec<Voice>,
+    next_voice_id: u64,
+    frames_rendered: u64,
+}
+
+impl Mixer {
+    pub fn new(sample_rate: u32) -> Self {
+        Mixer {
+            sample_rate: sample_rate,
+            buses: vec![Bus::new("master", None)],
+            voices: Vec::new(),
+            next_voice_id: 0,
+            frames_rendered: 0,
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        for bus in &mut self.buses {
+            for effect in &mut bus.effects {
+                effect.reset();
+            }
+        }
+    }
+
+    /// The total number of frames rendered since the mixer was created.
+    pub fn frames_rendered(&self) -> u64 {
+        self.frames_rendered
+    }
+
+    /// Create a new bus that routes into `output`.
+    pub fn add_bus(&mut self, name: &str, output: BusId) -> BusId {
+        assert!(output.0 < self.buses.len(), "Output bus does not exist!");
+
+        // NOTE: A bus's output always has a lower index than the bus itself, so rendering buses
+        // in reverse order guarantees children are mixed before their parents.
+        self.buses.push(Bus::new(name, Some(output)));
+        BusId(self.buses.len() - 1)
+    }
+
+    pub fn find_bus(&self, name: &str) -> Option<BusId> {
+        self.buses.iter()
+            .position(|bus| bus.name == name)
+            .map(BusId)
+    }
+
+    pub fn bus(&self, id: BusId) -> &Bus {
+        &self.buses[id.0]
+    }
+
+    pub fn bus_mut(&mut self, id: BusId) -> &mut Bus {
+        &mut self.buses[id.0]
+    }
+
+    pub fn play(&mut self, sound: &Sound, bus: BusId) -> VoiceId {
+        let start_frame = self.frames_rendered;
+        self.play_at(sound, bus, start_frame)
+    }
+
+    /// Start playing `sound` once the mixer has rendered `start_frame` frames. Use this to
+    /// schedule sounds with sample accuracy.
+    pub fn play_at(&mut self, sound: &Sound, bus: BusId, start_frame: u64) -> VoiceId {
+        assert!(bus.0 < self.buses.len(), "Bus does not exist!");
+
+        let id = VoiceId(self.next_voice_id);
+        self.next_voice_id += 1;
+
+        self.voices.push(Voice {
+            id: id,
+            sound: sound.clone(),
+            bus: bus,
+            gain: Param::new(1.0),
+            pitch: 1.0,
+            looping: false,
+
+            position: 0.0,
+            start_frame: start_frame,
+            finished: false,
+        });
+
+        id
+    }
+
+    pub fn voice(&self, id: VoiceId) -> Option<&Voice> {
+        self.voices.iter().find(|voice| voice.id == id)
+    }
+
+    pub fn voice_mut(&mut self, id: VoiceId) -> Option<&mut Voice> {
+        self.voices.iter_mut().find(|voice| voice.id == id)
+    }
+
+    pub fn is_playing(&self, id: VoiceId) -> bool {
+        self.voice(id).map(|voice| !voice.finished).unwrap_or(false)
+    }
+
+    pub fn stop(&mut self, id: VoiceId) {
+        if let Some(voice) = self.voice_mut(id) {
+            voice.stop();
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        self.voices.clear();
+    }
+
+    /// Render interleaved stereo samples into `out`, overwriting its contents. If `out` has an odd
+    /// length, the trailing sample is silent.
+    pub fn render(&mut self, out: &mut [f32]) {
+        let frames = out.len() / 2;
+        let sample_rate = self.sample_rate;
+        let block_start = self.frames_rendered;
+
+        for bus in &mut self.buses {
+            bus.buffer.clear();
+            bus.buffer.resize(frames * 2, 0.0);
+        }
+
+        // Mix voices into the buses they play on.
+        for voice in &mut self.voices {
+            if voice.finished {
+                continue;
+            }
+
+            let buffer = &mut self.buses[voice.bus.0].buffer;
+            let step = voice.pitch as f64 * voice.sound.sample_rate() as f64 / sample_rate as f64;
+            let first_frame = voice.start_frame.saturating_sub(block_start) as usize;
+            for frame in first_frame..frames {
+                if voice.position >= voice.sound.frames() as f64 {
+                    if voice.looping && voice.sound.frames() > 0 {
+                        voice.position -= voice.sound.frames() as f64;
+                    } else {
+                        voice.finished = true;
+                        break;
+                    }
+                }
+
+                let gain = voice.gain.next(sample_rate);
+                let (left, right) = voice.sound.frame_at(voice.position);
+                buffer[frame * 2] += left * gain;
+                buffer[frame * 2 + 1] += right * gain;
+                voice.position += step;
+            }
+        }
+        self.voices.retain(|voice| !voice.finished);
+
+        // Process buses from the leaves up to the master bus.
+        for index in (0..self.buses.len()).rev() {
+            // NOTE: Sidechain levels come from the previous block, since the sidechain bus may
+            // not have been processed yet.
+            let duck_target = match self.buses[index].ducking {
+                Some(ducking) => match self.buses.get(ducking.sidechain.0) {
+                    Some(sidechain) if sidechain.peak > db_to_gain(ducking.threshold) =>
+                        Some((db_to_gain(-ducking.amount.abs()), ducking)),
+                    Some(_) => Some((1.0, ducking)),
+                    // The sidechain is a bus from another mixer, so there's nothing to duck to.
+                    None => None,
+                },
+                None => None,
+            };
+
+            let output = {
+                let bus = &mut self.buses[index];
+                for effect in &mut bus.effects {
+                    effect.process(&mut bus.buffer, sample_rate);
+                }
+
+                let mut peak = 0.0f32;
+                for frame in bus.buffer.chunks_mut(2) {
+                    // Keep the gain ramp moving while muted, so unmuting picks up where it would be.
+                    let mut gain = bus.gain.next(sample_rate);
+                    if bus.muted {
+                        gain = 0.0;
+                    }
+                    if let Some((target, ducking)) = duck_target {
+                        let time = if target < bus.duck_gain { ducking.attack } else { ducking.release };
+                        let coefficient = time_coefficient(time, sample_rate);
+                        bus.duck_gain = target + coefficient * (bus.duck_gain - target);
+                        gain *= bus.duck_gain;
+                    }
+                    for sample in frame.iter_mut() {
+                        *sample *= gain;
+                        peak = peak.max(sample.abs());
+                    }
+                }
+                bus.peak = peak;
+
+                bus.output
+            };
+
+            if let Some(output) = output {
+                let (parents, children) = self.buses.split_at_mut(index);
+                let source = &children[0].buffer;
+                for (dest, sample) in parents[output.0].buffer.iter_mut().zip(source) {
+                    *dest += *sample;
+                }
+            }
+        }
+
+        out[..frames * 2].copy_from_slice(&self.buses[MASTER_BUS.0].buffer[..frames * 2]);
+        for sample in &mut out[frames * 2..] {
+            *sample = 0.0;
+        }
+        self.frames_rendered += frames as u64;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use audio::effect::{Compressor, Filter};
+    use audio::sound::Sound;
+    use super::{Ducking, Mixer, MASTER_BUS};
+
+    const RATE: u32 = 1000;
+
+    fn constant(value: f32, frames: usize) -> Sound {
+        Sound::from_mono(&vec![value; frames], RATE)
+    }
+
+    #[test]
+    fn renders_voices_through_buses() {
+        let mut mixer = Mixer::new(RATE);
+        let sfx = mixer.add_bus("sfx", MASTER_BUS);
+        mixer.bus_mut(sfx).gain_mut().set(0.5);
+        mixer.play(&constant(1.0, 4), sfx);
+
+        let mut out = [0.0; 12];
+        mixer.render(&mut out);
+        assert_eq!(&out[..8], &[0.5; 8]);
+        assert_eq!(&out[8..], &[0.0; 4]);
+    }
+
+    #[test]
+    fn odd_length_output_ends_in_silence() {
+        let mut mixer = Mixer::new(RATE);
+        mixer.play(&constant(1.0, 4), MASTER_BUS);
+
+        let mut out = [0.5; 5];
+        mixer.render(&mut out);
+        assert_eq!(out, [1.0, 1.0, 1.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn scheduled_voices_start_on_their_frame() {
+        let mut mixer = Mixer::new(RATE);
+        mixer.play_at(&constant(1.0, 10), MASTER_BUS, 3);
+
+        let mut out = [0.0; 8];
+        mixer.render(&mut out);
+        assert_eq!(out, [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn gain_ramps_over_time() {
+        let mut mixer = Mixer::new(RATE);
+        mixer.play(&constant(1.0, 1000), MASTER_BUS);
+        mixer.bus_mut(MASTER_BUS).gain_mut().ramp_to(0.0, 0.1);
+
+        let mut out = vec![0.0; 400];
+        mixer.render(&mut out);
+        assert!(out[0] > 0.9);
+        assert_eq!(out[398], 0.0);
+    }
+
+    #[test]
+    fn low_pass_removes_high_frequencies() {
+        let mut mixer = Mixer::new(RATE);
+        mixer.bus_mut(MASTER_BUS).add_effect(Filter::low_pass(10.0));
+        let alternating: Vec<f32> = (0..1000).map(|i| if i % 2 == 0 { 1.0 } else { -1.0 }).collect();
+        mixer.play(&Sound::from_mono(&alternating, RATE), MASTER_BUS);
+
+        let mut out = vec![0.0; 2000];
+        mixer.render(&mut out);
+        assert!(out[1000..].iter().all(|sample| sample.abs() < 0.05));
+    }
+
+    #[test]
+    fn limiter_keeps_peaks_under_threshold() {
+        let mut mixer = Mixer::new(RATE);
+        mixer.bus_mut(MASTER_BUS).add_effect(Compressor::limiter(-6.0));
+        mixer.play(&constant(1.0, 1000), MASTER_BUS);
+
+        let mut out = vec![0.0; 2000];
+        mixer.render(&mut out);
+        assert!(out[200..].iter().all(|sample| *sample < 0.55));
+    }
+
+    #[test]
+    fn music_ducks_under_dialogue() {
+        let mut mixer = Mixer::new(RATE);
+        let music = mixer.add_bus("music", MASTER_BUS);
+        let dialogue = mixer.add_bus("dialogue", MASTER_BUS);
+        mixer.bus_mut(music).set_ducking(Some(Ducking::new(dialogue, 12.0)));
+        mixer.play(&constant(1.0, 3000), music);
+        mixer.play(&constant(1.0, 3000), dialogue);
+
+        // Ducking reacts to the previous block, then takes a block to settle.
+        let mut out = vec![0.0; 2000];
+        for _ in 0..3 {
+            mixer.render(&mut out);
+        }
+        assert!(mixer.bus(music).peak() < 0.5);
+    }
+}
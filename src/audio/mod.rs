@@ -0,0 +1,153 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
+
+use sdl2;
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+
+use time::Time;
+
+pub use self::clock::{BeatEvent, Conductor, TempoMap};
+pub use self::mixer::{BusId, MASTER_BUS, Mixer, VoiceId};
+pub use self::sound::Sound;
+
+pub mod clock;
+pub mod effect;
+pub mod mixer;
+pub mod param;
+pub mod sfxr;
+mod sound;
+
+
+const DEFAULT_SAMPLE_RATE: u32 = 44100;
+const DEFAULT_BUFFER_FRAMES: u16 = 1024;
+
+/// When the audio device last asked for samples, and how many frames had been rendered then.
+#[derive(Clone, Copy)]
+struct ClockSnapshot {
+    frames_rendered: u64,
+    instant: Instant,
+}
+
+struct MixerCallback {
+    mixer: Arc<Mutex<Mixer>>,
+    clock: Arc<Mutex<Option<ClockSnapshot>>>,
+}
+
+impl AudioCallback for MixerCallback {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        let frames_rendered = {
+            let mut mixer = self.mixer.lock().unwrap();
+            mixer.render(out);
+            mixer.frames_rendered()
+        };
+        *self.clock.lock().unwrap() = Some(ClockSnapshot {
+            frames_rendered: frames_rendered,
+            instant: Instant::now(),
+        });
+    }
+}
+
+pub struct Audio {
+    mixer: Arc<Mutex<Mixer>>,
+    clock: Arc<Mutex<Option<ClockSnapshot>>>,
+    device: Option<AudioDevice<MixerCallback>>,
+    latency_offset: f64,
+}
+
+impl Audio {
+    // FIXME: This shouldn't be accessible outside the crate.
+    pub fn new(sdl_context: &sdl2::Sdl) -> Self {
+        let mixer = Arc::new(Mutex::new(Mixer::new(DEFAULT_SAMPLE_RATE)));
+        let clock = Arc::new(Mutex::new(None));
+
+        // A missing audio device shouldn't stop the game from running, so just keep mixing
+        // without output.
+        let device = sdl_context.audio().ok().and_then(|audio_subsystem| {
+            let desired_spec = AudioSpecDesired {
+                freq: Some(DEFAULT_SAMPLE_RATE as i32),
+                channels: Some(2),
+                samples: Some(DEFAULT_BUFFER_FRAMES),
+            };
+            audio_subsystem.open_playback(None, &desired_spec, |spec| {
+                mixer.lock().unwrap().set_sample_rate(spec.freq as u32);
+                MixerCallback {
+                    mixer: mixer.clone(),
+                    clock: clock.clone(),
+                }
+            }).ok()
+        });
+
+        if let Some(ref device) = device {
+            device.resume();
+        }
+
+        Audio {
+            mixer: mixer,
+            clock: clock,
+            device: device,
+            latency_offset: 0.0,
+        }
+    }
+
+    /// Lock the mixer to create buses, play sounds or change parameters.
+    ///
+    /// The audio thread can't render while the mixer is locked, so don't hold on to it.
+    pub fn mixer(&self) -> MutexGuard<Mixer> {
+        self.mixer.lock().unwrap()
+    }
+
+    pub fn play(&self, sound: &Sound) -> VoiceId {
+        self.mixer().play(sound, MASTER_BUS)
+    }
+
+    pub fn play_on(&self, sound: &Sound, bus: BusId) -> VoiceId {
+        self.mixer().play(sound, bus)
+    }
+
+    /// Estimate which mixer frame is currently coming out of the speakers.
+    ///
+    /// This accounts for the device's buffer and any calibrated latency offset, and interpolates
+    /// between audio callbacks with the wall clock so it advances smoothly every game frame.
+    pub fn playback_frame(&self) -> f64 {
+        let sample_rate = self.mixer().sample_rate() as f64;
+        let snapshot = *self.clock.lock().unwrap();
+        let (snapshot, buffer_frames) = match (snapshot, self.device.as_ref()) {
+            (Some(snapshot), Some(device)) => (snapshot, device.spec().samples as f64),
+            // Without a device, the mixer only advances when rendered offline.
+            _ => return self.mixer().frames_rendered() as f64,
+        };
+
+        // The block that was just rendered starts playing once the previous one finishes.
+        let rendered = snapshot.frames_rendered as f64;
+        let elapsed = Time::duration_as_f64(snapshot.instant.elapsed());
+        let frame = (rendered - buffer_frames + elapsed * sample_rate).min(rendered);
+        (frame - self.latency_offset * sample_rate).max(0.0)
+    }
+
+    /// Additional output latency, in seconds, e.g. from a player-calibrated offset.
+    pub fn set_latency_offset(&mut self, seconds: f64) {
+        self.latency_offset = seconds;
+    }
+
+    pub fn latency_offset(&self) -> f64 {
+        self.latency_offset
+    }
+
+    pub fn has_device(&self) -> bool {
+        self.device.is_some()
+    }
+
+    pub fn pause(&self) {
+        if let Some(ref device) = self.device {
+            device.pause();
+        }
+    }
+
+    pub fn resume(&self) {
+        if let Some(ref device) = self.device {
+            device.resume();
+        }
+    }
+}
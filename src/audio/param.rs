@@ -0,0 +1,86 @@
+/// An effect or mixer parameter that can be animated over time.
+///
+/// Ramps are linear and specified in seconds. They are advanced by the mixer as it renders, so
+/// they stay in sync with what is actually heard.
+#[derive(Clone, Copy, Debug)]
+pub struct Param {
+    value: f32,
+    target: f32,
+    // Units per second while ramping.
+    velocity: f32,
+}
+
+impl Param {
+    pub fn new(value: f32) -> Self {
+        Param {
+            value: value,
+            target: value,
+            velocity: 0.0,
+        }
+    }
+
+    /// Jump to `value` immediately, cancelling any ramp in progress.
+    pub fn set(&mut self, value: f32) {
+        self.value = value;
+        self.target = value;
+        self.velocity = 0.0;
+    }
+
+    /// Linearly ramp to `value` over `seconds`.
+    pub fn ramp_to(&mut self, value: f32, seconds: f32) {
+        if seconds <= 0.0 {
+            self.set(value);
+            return;
+        }
+
+        self.target = value;
+        self.velocity = (value - self.value) / seconds;
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+
+    pub fn is_ramping(&self) -> bool {
+        self.value != self.target
+    }
+
+    /// Advance the ramp by a single frame and return the new value.
+    pub fn next(&mut self, sample_rate: u32) -> f32 {
+        self.advance(1, sample_rate)
+    }
+
+    /// Advance the ramp by `frames` frames at once and return the new value. Used by effects that
+    /// only update their coefficients once per block.
+    pub fn advance(&mut self, frames: u32, sample_rate: u32) -> f32 {
+        if self.value != self.target {
+            let delta = self.velocity * frames as f32 / sample_rate as f32;
+            let remaining = self.target - self.value;
+            if delta.abs() >= remaining.abs() {
+                self.value = self.target;
+                self.velocity = 0.0;
+            } else {
+                self.value += delta;
+            }
+        }
+        self.value
+    }
+}
+
+impl Default for Param {
+    fn default() -> Self {
+        Param::new(0.0)
+    }
+}
+
+pub fn db_to_gain(db: f32) -> f32 {
+    10.0f32.powf(db / 20.0)
+}
+
+pub fn gain_to_db(gain: f32) -> f32 {
+    20.0 * gain.max(1e-6).log10()
+}
@@ -0,0 +1,790 @@
+//! A port of DrPetter's sfxr sound effect synthesizer.
+//!
+//! Parameter sets are compatible with sfxr's `.sfs` settings files, so sounds designed in sfxr
+//! (or one of its many ports) can be loaded and regenerated here.
+
+use std::f32::consts::PI;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use rand::{self, Rng};
+
+use audio::sound::Sound;
+
+
+/// sfxr always synthesizes at 44.1kHz.
+pub const SAMPLE_RATE: u32 = 44100;
+
+const MASTER_VOLUME: f32 = 0.05;
+const SUPERSAMPLES: usize = 8;
+const PHASER_SIZE: usize = 1024;
+const NOISE_SIZE: usize = 32;
+const SFS_VERSION: i32 = 102;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaveType {
+    Square,
+    Sawtooth,
+    Sine,
+    Noise,
+}
+
+impl WaveType {
+    fn from_i32(value: i32) -> Option<WaveType> {
+        match value {
+            0 => Some(WaveType::Square),
+            1 => Some(WaveType::Sawtooth),
+            2 => Some(WaveType::Sine),
+            3 => Some(WaveType::Noise),
+            _ => None,
+        }
+    }
+
+    fn to_i32(self) -> i32 {
+        match self {
+            WaveType::Square => 0,
+            WaveType::Sawtooth => 1,
+            WaveType::Sine => 2,
+            WaveType::Noise => 3,
+        }
+    }
+}
+
+/// The full set of sfxr parameters. Field names and ranges match sfxr's own.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SfxrParams {
+    pub wave_type: WaveType,
+    pub sound_volume: f32,
+
+    pub base_freq: f32,
+    pub freq_limit: f32,
+    pub freq_ramp: f32,
+    pub freq_delta_ramp: f32,
+    pub duty: f32,
+    pub duty_ramp: f32,
+
+    pub vibrato_strength: f32,
+    pub vibrato_speed: f32,
+    pub vibrato_delay: f32,
+
+    pub env_attack: f32,
+    pub env_sustain: f32,
+    pub env_decay: f32,
+    pub env_punch: f32,
+
+    pub lpf_resonance: f32,
+    pub lpf_freq: f32,
+    pub lpf_ramp: f32,
+    pub hpf_freq: f32,
+    pub hpf_ramp: f32,
+
+    pub phaser_offset: f32,
+    pub phaser_ramp: f32,
+
+    pub repeat_speed: f32,
+
+    pub arp_speed: f32,
+    pub arp_mod: f32,
+}
+
+impl Default for SfxrParams {
+    fn default() -> Self {
+        SfxrParams {
+            wave_type: WaveType::Square,
+            sound_volume: 0.5,
+
+            base_freq: 0.3,
+            freq_limit: 0.0,
+            freq_ramp: 0.0,
+            freq_delta_ramp: 0.0,
+            duty: 0.0,
+            duty_ramp: 0.0,
+
+            vibrato_strength: 0.0,
+            vibrato_speed: 0.0,
+            vibrato_delay: 0.0,
+
+            env_attack: 0.0,
+            env_sustain: 0.3,
+            env_decay: 0.4,
+            env_punch: 0.0,
+
+            lpf_resonance: 0.0,
+            lpf_freq: 1.0,
+            lpf_ramp: 0.0,
+            hpf_freq: 0.0,
+            hpf_ramp: 0.0,
+
+            phaser_offset: 0.0,
+            phaser_ramp: 0.0,
+
+            repeat_speed: 0.0,
+
+            arp_speed: 0.0,
+            arp_mod: 0.0,
+        }
+    }
+}
+
+// sfxr's random helpers: `rnd(n)` is an integer in 0..=n, `frnd(range)` a float in 0..range.
+fn rnd<R: Rng>(rng: &mut R, n: u32) -> u32 {
+    rng.gen_range(0, n + 1)
+}
+
+fn frnd<R: Rng>(rng: &mut R, range: f32) -> f32 {
+    rng.gen::<f32>() * range
+}
+
+impl SfxrParams {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn pickup<R: Rng>(rng: &mut R) -> Self {
+        let mut p = SfxrParams::new();
+        p.base_freq = 0.4 + frnd(rng, 0.5);
+        p.env_attack = 0.0;
+        p.env_sustain = frnd(rng, 0.1);
+        p.env_decay = 0.1 + frnd(rng, 0.4);
+        p.env_punch = 0.3 + frnd(rng, 0.3);
+        if rnd(rng, 1) == 1 {
+            p.arp_speed = 0.5 + frnd(rng, 0.2);
+            p.arp_mod = 0.2 + frnd(rng, 0.4);
+        }
+        p
+    }
+
+    pub fn laser<R: Rng>(rng: &mut R) -> Self {
+        let mut p = SfxrParams::new();
+        let mut wave_type = rnd(rng, 2);
+        if wave_type == 2 && rnd(rng, 1) == 1 {
+            wave_type = rnd(rng, 1);
+        }
+        p.wave_type = WaveType::from_i32(wave_type as i32).unwrap();
+        p.base_freq = 0.5 + frnd(rng, 0.5);
+        p.freq_limit = (p.base_freq - 0.2 - frnd(rng, 0.6)).max(0.2);
+        p.freq_ramp = -0.15 - frnd(rng, 0.2);
+        if rnd(rng, 2) == 0 {
+            p.base_freq = 0.3 + frnd(rng, 0.6);
+            p.freq_limit = frnd(rng, 0.1);
+            p.freq_ramp = -0.35 - frnd(rng, 0.3);
+        }
+        if rnd(rng, 1) == 1 {
+            p.duty = frnd(rng, 0.5);
+            p.duty_ramp = frnd(rng, 0.2);
+        } else {
+            p.duty = 0.4 + frnd(rng, 0.5);
+            p.duty_ramp = -frnd(rng, 0.7);
+        }
+        p.env_attack = 0.0;
+        p.env_sustain = 0.1 + frnd(rng, 0.2);
+        p.env_decay = frnd(rng, 0.4);
+        if rnd(rng, 1) == 1 {
+            p.env_punch = frnd(rng, 0.3);
+        }
+        if rnd(rng, 2) == 0 {
+            p.phaser_offset = frnd(rng, 0.2);
+            p.phaser_ramp = -frnd(rng, 0.2);
+        }
+        if rnd(rng, 1) == 1 {
+            p.hpf_freq = frnd(rng, 0.3);
+        }
+        p
+    }
+
+    pub fn explosion<R: Rng>(rng: &mut R) -> Self {
+        let mut p = SfxrParams::new();
+        p.wave_type = WaveType::Noise;
+        if rnd(rng, 1) == 1 {
+            p.base_freq = 0.1 + frnd(rng, 0.4);
+            p.freq_ramp = -0.1 + frnd(rng, 0.4);
+        } else {
+            p.base_freq = 0.2 + frnd(rng, 0.7);
+            p.freq_ramp = -0.2 - frnd(rng, 0.2);
+        }
+        p.base_freq *= p.base_freq;
+        if rnd(rng, 4) == 0 {
+            p.freq_ramp = 0.0;
+        }
+        if rnd(rng, 2) == 0 {
+            p.repeat_speed = 0.3 + frnd(rng, 0.5);
+        }
+        p.env_attack = 0.0;
+        p.env_sustain = 0.1 + frnd(rng, 0.3);
+        p.env_decay = frnd(rng, 0.5);
+        if rnd(rng, 1) == 0 {
+            p.phaser_offset = -0.3 + frnd(rng, 0.9);
+            p.phaser_ramp = -frnd(rng, 0.3);
+        }
+        p.env_punch = 0.2 + frnd(rng, 0.6);
+        if rnd(rng, 1) == 1 {
+            p.vibrato_strength = frnd(rng, 0.7);
+            p.vibrato_speed = frnd(rng, 0.6);
+        }
+        if rnd(rng, 2) == 0 {
+            p.arp_speed = 0.6 + frnd(rng, 0.3);
+            p.arp_mod = 0.8 - frnd(rng, 1.6);
+        }
+        p
+    }
+
+    pub fn powerup<R: Rng>(rng: &mut R) -> Self {
+        let mut p = SfxrParams::new();
+        if rnd(rng, 1) == 1 {
+            p.wave_type = WaveType::Sawtooth;
+        } else {
+            p.duty = frnd(rng, 0.6);
+        }
+        p.base_freq = 0.2 + frnd(rng, 0.3);
+        if rnd(rng, 1) == 1 {
+            p.freq_ramp = 0.1 + frnd(rng, 0.4);
+            p.repeat_speed = 0.4 + frnd(rng, 0.4);
+        } else {
+            p.freq_ramp = 0.05 + frnd(rng, 0.2);
+            if rnd(rng, 1) == 1 {
+                p.vibrato_strength = frnd(rng, 0.7);
+                p.vibrato_speed = frnd(rng, 0.6);
+            }
+        }
+        p.env_attack = 0.0;
+        p.env_sustain = frnd(rng, 0.4);
+        p.env_decay = 0.1 + frnd(rng, 0.4);
+        p
+    }
+
+    pub fn hit<R: Rng>(rng: &mut R) -> Self {
+        let mut p = SfxrParams::new();
+        p.wave_type = match rnd(rng, 2) {
+            0 => WaveType::Square,
+            1 => WaveType::Sawtooth,
+            _ => WaveType::Noise,
+        };
+        if p.wave_type == WaveType::Square {
+            p.duty = frnd(rng, 0.6);
+        }
+        p.base_freq = 0.2 + frnd(rng, 0.6);
+        p.freq_ramp = -0.3 - frnd(rng, 0.4);
+        p.env_attack = 0.0;
+        p.env_sustain = frnd(rng, 0.1);
+        p.env_decay = 0.1 + frnd(rng, 0.2);
+        if rnd(rng, 1) == 1 {
+            p.hpf_freq = frnd(rng, 0.3);
+        }
+        p
+    }
+
+    pub fn jump<R: Rng>(rng: &mut R) -> Self {
+        let mut p = SfxrParams::new();
+        p.wave_type = WaveType::Square;
+        p.duty = frnd(rng, 0.6);
+        p.base_freq = 0.3 + frnd(rng, 0.3);
+        p.freq_ramp = 0.1 + frnd(rng, 0.2);
+        p.env_attack = 0.0;
+        p.env_sustain = 0.1 + frnd(rng, 0.3);
+        p.env_decay = 0.1 + frnd(rng, 0.2);
+        if rnd(rng, 1) == 1 {
+            p.hpf_freq = frnd(rng, 0.3);
+        }
+        if rnd(rng, 1) == 1 {
+            p.lpf_freq = 1.0 - frnd(rng, 0.6);
+        }
+        p
+    }
+
+    pub fn blip<R: Rng>(rng: &mut R) -> Self {
+        let mut p = SfxrParams::new();
+        if rnd(rng, 1) == 1 {
+            p.wave_type = WaveType::Sawtooth;
+        } else {
+            p.duty = frnd(rng, 0.6);
+        }
+        p.base_freq = 0.2 + frnd(rng, 0.4);
+        p.env_attack = 0.0;
+        p.env_sustain = 0.1 + frnd(rng, 0.1);
+        p.env_decay = frnd(rng, 0.2);
+        p.hpf_freq = 0.1;
+        p
+    }
+
+    /// Randomly nudge most parameters, like sfxr's "mutate" button.
+    pub fn mutate<R: Rng>(&mut self, rng: &mut R) {
+        {
+            let mut unsigned: [&mut f32; 13] = [
+                &mut self.base_freq, &mut self.duty, &mut self.vibrato_strength,
+                &mut self.vibrato_speed, &mut self.vibrato_delay, &mut self.env_attack,
+                &mut self.env_sustain, &mut self.env_decay, &mut self.env_punch,
+                &mut self.lpf_resonance, &mut self.lpf_freq, &mut self.hpf_freq,
+                &mut self.repeat_speed,
+            ];
+            for param in unsigned.iter_mut() {
+                if rnd(rng, 1) == 1 {
+                    **param = (**param + frnd(rng, 0.1) - 0.05).max(0.0).min(1.0);
+                }
+            }
+        }
+
+        let mut signed: [&mut f32; 9] = [
+            &mut self.freq_ramp, &mut self.freq_delta_ramp, &mut self.duty_ramp,
+            &mut self.lpf_ramp, &mut self.hpf_ramp, &mut self.phaser_offset,
+            &mut self.phaser_ramp, &mut self.arp_speed, &mut self.arp_mod,
+        ];
+        for param in signed.iter_mut() {
+            if rnd(rng, 1) == 1 {
+                **param = (**param + frnd(rng, 0.1) - 0.05).max(-1.0).min(1.0);
+            }
+        }
+    }
+
+    /// Return a mutated copy of these parameters.
+    pub fn mutated<R: Rng>(&self, rng: &mut R) -> Self {
+        let mut params = *self;
+        params.mutate(rng);
+        params
+    }
+
+    /// Synthesize the sound as mono samples at `SAMPLE_RATE`.
+    pub fn synthesize(&self) -> Vec<f32> {
+        Synth::new(self).run()
+    }
+
+    /// Synthesize a `Sound` that can be played through `Audio`.
+    pub fn generate(&self) -> Sound {
+        Sound::from_mono(&self.synthesize(), SAMPLE_RATE)
+    }
+
+    /// Load an sfxr `.sfs` settings file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        SfxrParams::read_from(&mut file)
+    }
+
+    /// Save the parameters as an sfxr `.sfs` settings file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        self.write_to(&mut file)
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let version = read_i32(reader)?;
+        if version < 100 || version > SFS_VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      format!("Unsupported sfxr settings version {}", version)));
+        }
+
+        let mut p = SfxrParams::new();
+        p.wave_type = WaveType::from_i32(read_i32(reader)?)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid sfxr wave type"))?;
+        if version == 102 {
+            p.sound_volume = read_f32(reader)?;
+        }
+        p.base_freq = read_f32(reader)?;
+        p.freq_limit = read_f32(reader)?;
+        p.freq_ramp = read_f32(reader)?;
+        if version >= 101 {
+            p.freq_delta_ramp = read_f32(reader)?;
+        }
+        p.duty = read_f32(reader)?;
+        p.duty_ramp = read_f32(reader)?;
+        p.vibrato_strength = read_f32(reader)?;
+        p.vibrato_speed = read_f32(reader)?;
+        p.vibrato_delay = read_f32(reader)?;
+        p.env_attack = read_f32(reader)?;
+        p.env_sustain = read_f32(reader)?;
+        p.env_decay = read_f32(reader)?;
+        p.env_punch = read_f32(reader)?;
+        // sfxr stores an unused "filter on" flag here.
+        let mut filter_on = [0u8; 1];
+        reader.read_exact(&mut filter_on)?;
+        p.lpf_resonance = read_f32(reader)?;
+        p.lpf_freq = read_f32(reader)?;
+        p.lpf_ramp = read_f32(reader)?;
+        p.hpf_freq = read_f32(reader)?;
+        p.hpf_ramp = read_f32(reader)?;
+        p.phaser_offset = read_f32(reader)?;
+        p.phaser_ramp = read_f32(reader)?;
+        p.repeat_speed = read_f32(reader)?;
+        if version >= 101 {
+            p.arp_speed = read_f32(reader)?;
+            p.arp_mod = read_f32(reader)?;
+        }
+
+        Ok(p)
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_i32(writer, SFS_VERSION)?;
+        write_i32(writer, self.wave_type.to_i32())?;
+        let values = [
+            self.sound_volume,
+            self.base_freq, self.freq_limit, self.freq_ramp, self.freq_delta_ramp,
+            self.duty, self.duty_ramp,
+            self.vibrato_strength, self.vibrato_speed, self.vibrato_delay,
+            self.env_attack, self.env_sustain, self.env_decay, self.env_punch,
+        ];
+        for &value in &values {
+            write_f32(writer, value)?;
+        }
+        writer.write_all(&[0])?;
+        let values = [
+            self.lpf_resonance, self.lpf_freq, self.lpf_ramp, self.hpf_freq, self.hpf_ramp,
+            self.phaser_offset, self.phaser_ramp,
+            self.repeat_speed,
+            self.arp_speed, self.arp_mod,
+        ];
+        for &value in &values {
+            write_f32(writer, value)?;
+        }
+        Ok(())
+    }
+}
+
+fn read_i32<R: Read>(reader: &mut R) -> io::Result<i32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok((bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16 | (bytes[3] as u32) << 24) as i32)
+}
+
+fn read_f32<R: Read>(reader: &mut R) -> io::Result<f32> {
+    read_i32(reader).map(|bits| f32::from_bits(bits as u32))
+}
+
+fn write_i32<W: Write>(writer: &mut W, value: i32) -> io::Result<()> {
+    let value = value as u32;
+    writer.write_all(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8])
+}
+
+fn write_f32<W: Write>(writer: &mut W, value: f32) -> io::Result<()> {
+    write_i32(writer, value.to_bits() as i32)
+}
+
+/// The synthesizer state. This follows sfxr's `ResetSample` and `SynthSample` closely so that
+/// parameter sets sound the same as they do in sfxr.
+struct Synth<'a> {
+    params: &'a SfxrParams,
+    rng: rand::XorShiftRng,
+    playing: bool,
+
+    phase: usize,
+    fperiod: f64,
+    fmaxperiod: f64,
+    fslide: f64,
+    fdslide: f64,
+    period: usize,
+    square_duty: f32,
+    square_slide: f32,
+
+    env_stage: usize,
+    env_time: usize,
+    env_length: [usize; 3],
+    env_vol: f32,
+
+    fphase: f32,
+    fdphase: f32,
+    iphase: usize,
+    phaser_buffer: [f32; PHASER_SIZE],
+    ipp: usize,
+
+    noise_buffer: [f32; NOISE_SIZE],
+
+    fltp: f32,
+    fltdp: f32,
+    fltw: f32,
+    fltw_d: f32,
+    fltdmp: f32,
+    fltphp: f32,
+    flthp: f32,
+    flthp_d: f32,
+
+    vib_phase: f32,
+    vib_speed: f32,
+    vib_amp: f32,
+
+    rep_time: usize,
+    rep_limit: usize,
+    arp_time: usize,
+    arp_limit: usize,
+    arp_mod: f64,
+}
+
+impl<'a> Synth<'a> {
+    fn new(params: &'a SfxrParams) -> Self {
+        let mut synth = Synth {
+            params: params,
+            rng: rand::weak_rng(),
+            playing: true,
+
+            phase: 0,
+            fperiod: 0.0,
+            fmaxperiod: 0.0,
+            fslide: 0.0,
+            fdslide: 0.0,
+            period: 0,
+            square_duty: 0.0,
+            square_slide: 0.0,
+
+            env_stage: 0,
+            env_time: 0,
+            env_length: [0; 3],
+            env_vol: 0.0,
+
+            fphase: 0.0,
+            fdphase: 0.0,
+            iphase: 0,
+            phaser_buffer: [0.0; PHASER_SIZE],
+            ipp: 0,
+
+            noise_buffer: [0.0; NOISE_SIZE],
+
+            fltp: 0.0,
+            fltdp: 0.0,
+            fltw: 0.0,
+            fltw_d: 0.0,
+            fltdmp: 0.0,
+            fltphp: 0.0,
+            flthp: 0.0,
+            flthp_d: 0.0,
+
+            vib_phase: 0.0,
+            vib_speed: 0.0,
+            vib_amp: 0.0,
+
+            rep_time: 0,
+            rep_limit: 0,
+            arp_time: 0,
+            arp_limit: 0,
+            arp_mod: 0.0,
+        };
+        synth.reset(false);
+        synth
+    }
+
+    fn reset(&mut self, restart: bool) {
+        let p = self.params;
+
+        if !restart {
+            self.phase = 0;
+        }
+        self.fperiod = 100.0 / (p.base_freq as f64 * p.base_freq as f64 + 0.001);
+        self.period = self.fperiod as usize;
+        self.fmaxperiod = 100.0 / (p.freq_limit as f64 * p.freq_limit as f64 + 0.001);
+        self.fslide = 1.0 - (p.freq_ramp as f64).powi(3) * 0.01;
+        self.fdslide = -(p.freq_delta_ramp as f64).powi(3) * 0.000001;
+        self.square_duty = 0.5 - p.duty * 0.5;
+        self.square_slide = -p.duty_ramp * 0.00005;
+        self.arp_mod = if p.arp_mod >= 0.0 {
+            1.0 - (p.arp_mod as f64).powi(2) * 0.9
+        } else {
+            1.0 + (p.arp_mod as f64).powi(2) * 10.0
+        };
+        self.arp_time = 0;
+        self.arp_limit = if p.arp_speed == 1.0 {
+            0
+        } else {
+            ((1.0 - p.arp_speed).powi(2) * 20000.0 + 32.0) as usize
+        };
+
+        if restart {
+            return;
+        }
+
+        // Reset the filters.
+        self.fltp = 0.0;
+        self.fltdp = 0.0;
+        self.fltw = p.lpf_freq.powi(3) * 0.1;
+        self.fltw_d = 1.0 + p.lpf_ramp * 0.0001;
+        self.fltdmp = (5.0 / (1.0 + p.lpf_resonance.powi(2) * 20.0) * (0.01 + self.fltw)).min(0.8);
+        self.fltphp = 0.0;
+        self.flthp = p.hpf_freq.powi(2) * 0.1;
+        self.flthp_d = 1.0 + p.hpf_ramp * 0.0003;
+
+        // Reset the vibrato.
+        self.vib_phase = 0.0;
+        self.vib_speed = p.vibrato_speed.powi(2) * 0.01;
+        self.vib_amp = p.vibrato_strength * 0.5;
+
+        // Reset the envelope.
+        self.env_vol = 0.0;
+        self.env_stage = 0;
+        self.env_time = 0;
+        self.env_length = [
+            (p.env_attack * p.env_attack * 100000.0) as usize,
+            (p.env_sustain * p.env_sustain * 100000.0) as usize,
+            (p.env_decay * p.env_decay * 100000.0) as usize,
+        ];
+
+        self.fphase = p.phaser_offset.powi(2) * 1020.0;
+        if p.phaser_offset < 0.0 {
+            self.fphase = -self.fphase;
+        }
+        self.fdphase = p.phaser_ramp.powi(2);
+        if p.phaser_ramp < 0.0 {
+            self.fdphase = -self.fdphase;
+        }
+        self.iphase = (self.fphase as i32).abs() as usize;
+        self.ipp = 0;
+        self.phaser_buffer = [0.0; PHASER_SIZE];
+
+        for sample in self.noise_buffer.iter_mut() {
+            *sample = self.rng.gen::<f32>() * 2.0 - 1.0;
+        }
+
+        self.rep_time = 0;
+        self.rep_limit = if p.repeat_speed == 0.0 {
+            0
+        } else {
+            ((1.0 - p.repeat_speed).powi(2) * 20000.0 + 32.0) as usize
+        };
+    }
+
+    fn run(mut self) -> Vec<f32> {
+        let mut samples = Vec::new();
+        while let Some(sample) = self.next_sample() {
+            samples.push(sample);
+        }
+        samples
+    }
+
+    fn next_sample(&mut self) -> Option<f32> {
+        if !self.playing {
+            return None;
+        }
+        let p = self.params;
+
+        self.rep_time += 1;
+        if self.rep_limit != 0 && self.rep_time >= self.rep_limit {
+            self.rep_time = 0;
+            self.reset(true);
+        }
+
+        // Frequency envelopes and arpeggios.
+        self.arp_time += 1;
+        if self.arp_limit != 0 && self.arp_time >= self.arp_limit {
+            self.arp_limit = 0;
+            self.fperiod *= self.arp_mod;
+        }
+        self.fslide += self.fdslide;
+        self.fperiod *= self.fslide;
+        if self.fperiod > self.fmaxperiod {
+            self.fperiod = self.fmaxperiod;
+            if p.freq_limit > 0.0 {
+                self.playing = false;
+            }
+        }
+        let mut rfperiod = self.fperiod;
+        if self.vib_amp > 0.0 {
+            self.vib_phase += self.vib_speed;
+            rfperiod = self.fperiod * (1.0 + self.vib_phase.sin() as f64 * self.vib_amp as f64);
+        }
+        self.period = (rfperiod as usize).max(8);
+        self.square_duty = (self.square_duty + self.square_slide).max(0.0).min(0.5);
+
+        // Volume envelope.
+        self.env_time += 1;
+        if self.env_time > self.env_length[self.env_stage] {
+            self.env_time = 0;
+            self.env_stage += 1;
+            if self.env_stage == 3 {
+                self.playing = false;
+                return None;
+            }
+        }
+        let stage_progress = self.env_time as f32 / self.env_length[self.env_stage].max(1) as f32;
+        self.env_vol = match self.env_stage {
+            0 => stage_progress,
+            1 => 1.0 + (1.0 - stage_progress) * 2.0 * p.env_punch,
+            _ => 1.0 - stage_progress,
+        };
+
+        // Phaser step.
+        self.fphase += self.fdphase;
+        self.iphase = ((self.fphase as i32).abs() as usize).min(PHASER_SIZE - 1);
+        if self.flthp_d != 0.0 {
+            self.flthp = (self.flthp * self.flthp_d).max(0.00001).min(0.1);
+        }
+
+        let mut super_sample = 0.0;
+        for _ in 0..SUPERSAMPLES {
+            self.phase += 1;
+            if self.phase >= self.period {
+                self.phase %= self.period;
+                if p.wave_type == WaveType::Noise {
+                    for sample in self.noise_buffer.iter_mut() {
+                        *sample = self.rng.gen::<f32>() * 2.0 - 1.0;
+                    }
+                }
+            }
+
+            // Base waveform.
+            let fp = self.phase as f32 / self.period as f32;
+            let mut sample = match p.wave_type {
+                WaveType::Square => if fp < self.square_duty { 0.5 } else { -0.5 },
+                WaveType::Sawtooth => 1.0 - fp * 2.0,
+                WaveType::Sine => (fp * 2.0 * PI).sin(),
+                WaveType::Noise => self.noise_buffer[self.phase * NOISE_SIZE / self.period],
+            };
+
+            // Low-pass filter.
+            let pp = self.fltp;
+            self.fltw = (self.fltw * self.fltw_d).max(0.0).min(0.1);
+            if p.lpf_freq != 1.0 {
+                self.fltdp += (sample - self.fltp) * self.fltw;
+                self.fltdp -= self.fltdp * self.fltdmp;
+            } else {
+                self.fltp = sample;
+                self.fltdp = 0.0;
+            }
+            self.fltp += self.fltdp;
+
+            // High-pass filter.
+            self.fltphp += self.fltp - pp;
+            self.fltphp -= self.fltphp * self.flthp;
+            sample = self.fltphp;
+
+            // Phaser.
+            self.phaser_buffer[self.ipp & (PHASER_SIZE - 1)] = sample;
+            sample += self.phaser_buffer[(self.ipp + PHASER_SIZE - self.iphase) & (PHASER_SIZE - 1)];
+            self.ipp = (self.ipp + 1) & (PHASER_SIZE - 1);
+
+            super_sample += sample * self.env_vol;
+        }
+
+        let sample = super_sample / SUPERSAMPLES as f32 * MASTER_VOLUME * 2.0 * p.sound_volume;
+        Some(sample.max(-1.0).min(1.0))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{SeedableRng, XorShiftRng};
+
+    use super::SfxrParams;
+
+    #[test]
+    fn presets_produce_bounded_sounds() {
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let presets = [
+            SfxrParams::pickup(&mut rng),
+            SfxrParams::laser(&mut rng),
+            SfxrParams::explosion(&mut rng),
+            SfxrParams::jump(&mut rng),
+            SfxrParams::hit(&mut rng),
+        ];
+        for params in &presets {
+            let samples = params.synthesize();
+            assert!(!samples.is_empty());
+            assert!(samples.iter().all(|sample| sample.abs() <= 1.0));
+        }
+    }
+
+    #[test]
+    fn settings_round_trip() {
+        let mut rng = XorShiftRng::from_seed([5, 6, 7, 8]);
+        let params = SfxrParams::laser(&mut rng).mutated(&mut rng);
+
+        let mut bytes = Vec::new();
+        params.write_to(&mut bytes).unwrap();
+        let loaded = SfxrParams::read_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(params, loaded);
+    }
+}
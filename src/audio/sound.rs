@@ -0,0 +1,130 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use sdl2::audio::{AudioCVT, AudioFormat, AudioSpecWAV};
+
+
+/// A fully decoded sound, stored as interleaved stereo `f32` samples.
+///
+/// Cloning a `Sound` is cheap, since the sample data is shared.
+#[derive(Clone, Debug)]
+pub struct Sound {
+    samples: Arc<Vec<f32>>,
+    sample_rate: u32,
+}
+
+impl Sound {
+    pub fn from_stereo(samples: Vec<f32>, sample_rate: u32) -> Self {
+        assert!(samples.len() % 2 == 0, "Stereo samples must come in pairs!");
+
+        Sound {
+            samples: Arc::new(samples),
+            sample_rate: sample_rate,
+        }
+    }
+
+    pub fn from_mono(samples: &[f32], sample_rate: u32) -> Self {
+        let mut stereo = Vec::with_capacity(samples.len() * 2);
+        for &sample in samples {
+            stereo.push(sample);
+            stereo.push(sample);
+        }
+        Sound::from_stereo(stereo, sample_rate)
+    }
+
+    pub fn load_wav<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let wav = AudioSpecWAV::load_wav(path)?;
+        let cvt = AudioCVT::new(wav.format, wav.channels, wav.freq,
+                                AudioFormat::F32LSB, 2, wav.freq)?;
+        let data = cvt.convert(wav.buffer().to_vec());
+
+        let samples = data.chunks(4)
+            .filter(|bytes| bytes.len() == 4)
+            .map(|bytes| {
+                let bits = bytes[0] as u32 | (bytes[1] as u32) << 8 |
+                    (bytes[2] as u32) << 16 | (bytes[3] as u32) << 24;
+                f32::from_bits(bits)
+            })
+            .collect();
+
+        Ok(Sound::from_stereo(samples, wav.freq as u32))
+    }
+
+    /// Save the sound as a 16-bit stereo PCM WAV file.
+    pub fn save_wav<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_wav(&mut writer)
+    }
+
+    pub fn write_wav<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        const CHANNELS: u32 = 2;
+        const BYTES_PER_SAMPLE: u32 = 2;
+
+        let data_size = self.samples.len() as u32 * BYTES_PER_SAMPLE;
+        let byte_rate = self.sample_rate * CHANNELS * BYTES_PER_SAMPLE;
+
+        writer.write_all(b"RIFF")?;
+        write_u32(writer, 36 + data_size)?;
+        writer.write_all(b"WAVE")?;
+
+        writer.write_all(b"fmt ")?;
+        write_u32(writer, 16)?;
+        // PCM format.
+        write_u16(writer, 1)?;
+        write_u16(writer, CHANNELS as u16)?;
+        write_u32(writer, self.sample_rate)?;
+        write_u32(writer, byte_rate)?;
+        write_u16(writer, (CHANNELS * BYTES_PER_SAMPLE) as u16)?;
+        write_u16(writer, (BYTES_PER_SAMPLE * 8) as u16)?;
+
+        writer.write_all(b"data")?;
+        write_u32(writer, data_size)?;
+        for &sample in self.samples.iter() {
+            let sample = (sample.max(-1.0).min(1.0) * i16::max_value() as f32) as i16;
+            write_u16(writer, sample as u16)?;
+        }
+
+        writer.flush()
+    }
+
+    pub fn samples(&self) -> &[f32] {
+        self.samples.as_slice()
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// The number of stereo frames in the sound.
+    pub fn frames(&self) -> usize {
+        self.samples.len() / 2
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.frames() as f64 / self.sample_rate as f64
+    }
+
+    /// Get a linearly interpolated stereo frame at a fractional frame position.
+    pub fn frame_at(&self, position: f64) -> (f32, f32) {
+        let index = position as usize;
+        if index >= self.frames() {
+            return (0.0, 0.0);
+        }
+
+        let fraction = (position - index as f64) as f32;
+        let next = if index + 1 < self.frames() { index + 1 } else { index };
+        let (l0, r0) = (self.samples[index * 2], self.samples[index * 2 + 1]);
+        let (l1, r1) = (self.samples[next * 2], self.samples[next * 2 + 1]);
+        (l0 + (l1 - l0) * fraction, r0 + (r1 - r0) * fraction)
+    }
+}
+
+fn write_u16<W: Write>(writer: &mut W, value: u16) -> io::Result<()> {
+    writer.write_all(&[value as u8, (value >> 8) as u8])
+}
+
+fn write_u32<W: Write>(writer: &mut W, value: u32) -> io::Result<()> {
+    writer.write_all(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8])
+}
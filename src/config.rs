@@ -1,4 +1,8 @@
+use std::path::{Path, PathBuf};
+
+
 pub struct MidgarAppConfig {
+    controller_mappings: Vec<PathBuf>,
     fps: u8,
     resizable: bool,
     screen_size: (u32, u32),
@@ -9,6 +13,7 @@ pub struct MidgarAppConfig {
 impl MidgarAppConfig {
     pub fn new() -> Self {
         MidgarAppConfig {
+            controller_mappings: Vec::new(),
             fps: 60,
             screen_size: (800, 600),
             resizable: false,
@@ -17,6 +22,17 @@ impl MidgarAppConfig {
         }
     }
 
+    /// Load a `gamecontrollerdb.txt` style mapping file at startup. May be called multiple
+    /// times. Files that fail to load are listed in `Input::controller_mapping_errors`.
+    pub fn with_controller_mappings<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.controller_mappings.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn controller_mappings(&self) -> &[PathBuf] {
+        &self.controller_mappings
+    }
+
     pub fn with_fps(mut self, fps: u8) -> Self {
         self.fps = fps;
         self
@@ -0,0 +1,190 @@
+use cgmath::{self, Matrix4, Vector2};
+
+
+/// An axis-aligned rectangle in world space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub min: Vector2<f32>,
+    pub max: Vector2<f32>,
+}
+
+impl Bounds {
+    pub fn new(min: Vector2<f32>, max: Vector2<f32>) -> Self {
+        Bounds {
+            min: min,
+            max: max,
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max.y - self.min.y
+    }
+
+    pub fn center(&self) -> Vector2<f32> {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn contains(&self, point: Vector2<f32>) -> bool {
+        point.x >= self.min.x && point.x <= self.max.x && point.y >= self.min.y && point.y <= self.max.y
+    }
+
+    pub fn overlaps(&self, other: &Bounds) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x &&
+            self.min.y <= other.max.y && self.max.y >= other.min.y
+    }
+}
+
+/// An orthographic camera for 2D scenes, with Y pointing up.
+///
+/// `position` is the world point at the center of the screen. A `zoom` of 2 shows everything
+/// twice as big. `rotation` is in degrees, counter-clockwise, like `Sprite`'s.
+///
+/// Pass `combined()` to `SpriteRenderer` and `ShapeRenderer`, or use their `set_camera`.
+#[derive(Clone, Debug)]
+pub struct Camera2D {
+    position: Vector2<f32>,
+    zoom: f32,
+    rotation: f32,
+    viewport_size: Vector2<f32>,
+}
+
+impl Camera2D {
+    /// Create a camera showing a `width` by `height` area, with the world origin at the
+    /// bottom-left corner of the screen. This matches `cgmath::ortho(0.0, width, 0.0, height, ..)`.
+    pub fn new(width: f32, height: f32) -> Self {
+        Camera2D {
+            position: cgmath::vec2(width / 2.0, height / 2.0),
+            zoom: 1.0,
+            rotation: 0.0,
+            viewport_size: cgmath::vec2(width, height),
+        }
+    }
+
+    pub fn position(&self) -> Vector2<f32> {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: Vector2<f32>) {
+        self.position = position;
+    }
+
+    pub fn translate(&mut self, offset: Vector2<f32>) {
+        self.position += offset;
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom;
+    }
+
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    pub fn set_rotation(&mut self, rotation: f32) {
+        self.rotation = rotation;
+    }
+
+    /// The size of the area the camera shows at a zoom of 1, usually the screen size in pixels.
+    pub fn viewport_size(&self) -> Vector2<f32> {
+        self.viewport_size
+    }
+
+    /// Call this when the window is resized. The camera keeps looking at the same position.
+    pub fn set_viewport_size(&mut self, width: f32, height: f32) {
+        self.viewport_size = cgmath::vec2(width, height);
+    }
+
+    pub fn projection(&self) -> Matrix4<f32> {
+        let half_width = self.viewport_size.x / 2.0;
+        let half_height = self.viewport_size.y / 2.0;
+        cgmath::ortho(-half_width, half_width, -half_height, half_height, -1.0, 1.0)
+    }
+
+    pub fn view(&self) -> Matrix4<f32> {
+        Matrix4::from_scale(self.zoom) *
+            Matrix4::from_angle_z(cgmath::Deg(-self.rotation)) *
+            Matrix4::from_translation(-self.position.extend(0.0))
+    }
+
+    /// The combined projection-view matrix.
+    pub fn combined(&self) -> Matrix4<f32> {
+        self.projection() * self.view()
+    }
+
+    /// Convert a point in screen pixels, with Y pointing down like `Input::mouse_pos`, to world
+    /// coordinates.
+    pub fn screen_to_world(&self, screen: Vector2<f32>) -> Vector2<f32> {
+        let offset = cgmath::vec2(screen.x - self.viewport_size.x / 2.0,
+                                  self.viewport_size.y / 2.0 - screen.y);
+        self.position + rotate(offset / self.zoom, self.rotation)
+    }
+
+    /// Convert a world point to screen pixels, with Y pointing down.
+    pub fn world_to_screen(&self, world: Vector2<f32>) -> Vector2<f32> {
+        let offset = rotate(world - self.position, -self.rotation) * self.zoom;
+        cgmath::vec2(offset.x + self.viewport_size.x / 2.0, self.viewport_size.y / 2.0 - offset.y)
+    }
+
+    /// The world area the camera can see. When rotated, this is the box around the rotated view.
+    pub fn visible_bounds(&self) -> Bounds {
+        let corners = [
+            self.screen_to_world(cgmath::vec2(0.0, 0.0)),
+            self.screen_to_world(cgmath::vec2(self.viewport_size.x, 0.0)),
+            self.screen_to_world(cgmath::vec2(0.0, self.viewport_size.y)),
+            self.screen_to_world(self.viewport_size),
+        ];
+        let mut bounds = Bounds::new(corners[0], corners[0]);
+        for corner in &corners[1..] {
+            bounds.min.x = bounds.min.x.min(corner.x);
+            bounds.min.y = bounds.min.y.min(corner.y);
+            bounds.max.x = bounds.max.x.max(corner.x);
+            bounds.max.y = bounds.max.y.max(corner.y);
+        }
+        bounds
+    }
+}
+
+/// Rotate `v` counter-clockwise by `degrees`.
+fn rotate(v: Vector2<f32>, degrees: f32) -> Vector2<f32> {
+    if degrees == 0.0 {
+        return v;
+    }
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    cgmath::vec2(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath;
+    use super::Camera2D;
+
+    fn assert_near(a: cgmath::Vector2<f32>, b: cgmath::Vector2<f32>) {
+        assert!((a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn screen_and_world_round_trip() {
+        let mut camera = Camera2D::new(800.0, 600.0);
+        // The bottom-left corner of the screen is the world origin.
+        assert_near(camera.screen_to_world(cgmath::vec2(0.0, 600.0)), cgmath::vec2(0.0, 0.0));
+
+        camera.set_position(cgmath::vec2(100.0, 50.0));
+        camera.set_zoom(2.0);
+        camera.set_rotation(30.0);
+        let world = cgmath::vec2(120.0, 80.0);
+        assert_near(camera.screen_to_world(camera.world_to_screen(world)), world);
+        assert_near(camera.screen_to_world(cgmath::vec2(400.0, 300.0)), camera.position());
+
+        let projected = camera.combined() * world.extend(0.0).extend(1.0);
+        let screen = camera.world_to_screen(world);
+        assert_near(cgmath::vec2((projected.x + 1.0) * 400.0, (1.0 - projected.y) * 300.0), screen);
+    }
+}
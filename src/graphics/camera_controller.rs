@@ -0,0 +1,397 @@
+use cgmath::{self, Matrix4, Vector2};
+
+use graphics::camera::{Bounds, Camera2D};
+use time::Time;
+
+
+/// Trauma-based screen shake. Shake strength is the square of the trauma, so small hits barely
+/// shake and big ones shake hard. Trauma decays linearly over time.
+///
+/// Offsets come from Perlin noise rather than random jumps, so the shake moves smoothly.
+#[derive(Clone, Debug)]
+pub struct ScreenShake {
+    trauma: f32,
+    /// How much trauma is lost per second.
+    pub decay: f32,
+    /// The offset in world units at full trauma.
+    pub max_offset: Vector2<f32>,
+    /// The rotation in degrees at full trauma.
+    pub max_angle: f32,
+    /// How fast the shake moves, in noise samples per second.
+    pub frequency: f32,
+    time: f32,
+    seed: u32,
+}
+
+impl ScreenShake {
+    pub fn new() -> Self {
+        ScreenShake {
+            trauma: 0.0,
+            decay: 1.0,
+            max_offset: cgmath::vec2(16.0, 16.0),
+            max_angle: 5.0,
+            frequency: 15.0,
+            time: 0.0,
+            seed: 0,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    /// Add trauma, up to a maximum of 1.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).max(0.0).min(1.0);
+    }
+
+    pub fn set_trauma(&mut self, trauma: f32) {
+        self.trauma = trauma.max(0.0).min(1.0);
+    }
+
+    pub fn is_shaking(&self) -> bool {
+        self.trauma > 0.0
+    }
+
+    pub fn advance(&mut self, dt: f64) {
+        self.time += dt as f32;
+        self.trauma = (self.trauma - self.decay * dt as f32).max(0.0);
+    }
+
+    /// The current offset and rotation to add to the camera.
+    pub fn offset(&self) -> (Vector2<f32>, f32) {
+        let shake = self.trauma * self.trauma;
+        if shake == 0.0 {
+            return (cgmath::vec2(0.0, 0.0), 0.0);
+        }
+        let t = self.time * self.frequency;
+        let x = perlin(t, self.seed) * self.max_offset.x * shake;
+        let y = perlin(t, self.seed.wrapping_add(1)) * self.max_offset.y * shake;
+        let angle = perlin(t, self.seed.wrapping_add(2)) * self.max_angle * shake;
+        (cgmath::vec2(x, y), angle)
+    }
+}
+
+impl Default for ScreenShake {
+    fn default() -> Self {
+        ScreenShake::new()
+    }
+}
+
+/// Zoom the camera so every target stays on screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ZoomToFit {
+    /// Space to keep around the targets, in world units.
+    pub padding: f32,
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+}
+
+impl Default for ZoomToFit {
+    fn default() -> Self {
+        ZoomToFit {
+            padding: 32.0,
+            min_zoom: 0.25,
+            max_zoom: 1.0,
+        }
+    }
+}
+
+/// Moves a `Camera2D` to follow one or more targets.
+///
+/// Set the targets each frame with `follow` or `follow_all`, call `update`, then draw with
+/// `combined`. The controller keeps its own position, zoom and rotation and writes them to the
+/// camera with shake on top, so shake never builds up in them. Change them through the
+/// controller rather than the camera.
+pub struct CameraController {
+    camera: Camera2D,
+    position: Vector2<f32>,
+    zoom: f32,
+    rotation: f32,
+
+    targets: Vec<Vector2<f32>>,
+    previous_focus: Option<Vector2<f32>>,
+    look_ahead_offset: Vector2<f32>,
+
+    /// How quickly the camera catches up with its goal. 0 snaps straight to it.
+    pub smoothing: f32,
+    /// The size of the area around the camera center that the target can move in without the
+    /// camera following.
+    pub dead_zone: Vector2<f32>,
+    /// How many seconds ahead of a moving target to look.
+    pub look_ahead: f32,
+    /// Keep the view inside these world bounds.
+    pub bounds: Option<Bounds>,
+    pub zoom_to_fit: Option<ZoomToFit>,
+    pub shake: ScreenShake,
+}
+
+impl CameraController {
+    pub fn new(camera: Camera2D) -> Self {
+        let position = camera.position();
+        let zoom = camera.zoom();
+        let rotation = camera.rotation();
+        CameraController {
+            camera: camera,
+            position: position,
+            zoom: zoom,
+            rotation: rotation,
+
+            targets: Vec::new(),
+            previous_focus: None,
+            look_ahead_offset: cgmath::vec2(0.0, 0.0),
+
+            smoothing: 5.0,
+            dead_zone: cgmath::vec2(0.0, 0.0),
+            look_ahead: 0.0,
+            bounds: None,
+            zoom_to_fit: None,
+            shake: ScreenShake::new(),
+        }
+    }
+
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    pub fn with_dead_zone(mut self, width: f32, height: f32) -> Self {
+        self.dead_zone = cgmath::vec2(width, height);
+        self
+    }
+
+    pub fn with_look_ahead(mut self, seconds: f32) -> Self {
+        self.look_ahead = seconds;
+        self
+    }
+
+    pub fn with_bounds(mut self, bounds: Bounds) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    pub fn with_zoom_to_fit(mut self, zoom_to_fit: ZoomToFit) -> Self {
+        self.zoom_to_fit = Some(zoom_to_fit);
+        self
+    }
+
+    pub fn camera(&self) -> &Camera2D {
+        &self.camera
+    }
+
+    /// The camera position without shake applied.
+    pub fn position(&self) -> Vector2<f32> {
+        self.position
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Set the zoom. With `zoom_to_fit` set, the controller keeps moving it towards the zoom
+    /// that fits the targets.
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom;
+        self.apply();
+    }
+
+    /// The camera rotation in degrees, without shake applied.
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    pub fn set_rotation(&mut self, rotation: f32) {
+        self.rotation = rotation;
+        self.apply();
+    }
+
+    /// Call when the screen or viewport is resized.
+    pub fn set_viewport_size(&mut self, width: f32, height: f32) {
+        self.camera.set_viewport_size(width, height);
+    }
+
+    /// Jump straight to a position, e.g. when loading a level.
+    pub fn snap_to(&mut self, position: Vector2<f32>) {
+        self.position = position;
+        self.previous_focus = None;
+        self.look_ahead_offset = cgmath::vec2(0.0, 0.0);
+        self.apply();
+    }
+
+    pub fn follow(&mut self, target: Vector2<f32>) {
+        self.targets.clear();
+        self.targets.push(target);
+    }
+
+    /// Follow the center of several targets, zooming to fit them if `zoom_to_fit` is set.
+    pub fn follow_all(&mut self, targets: &[Vector2<f32>]) {
+        self.targets.clear();
+        self.targets.extend_from_slice(targets);
+    }
+
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.shake.add_trauma(amount);
+    }
+
+    /// The projection-view matrix to pass to `SpriteRenderer` or `ShapeRenderer`.
+    pub fn combined(&self) -> Matrix4<f32> {
+        self.camera.combined()
+    }
+
+    pub fn update(&mut self, time: &Time) {
+        self.advance(time.delta_time());
+    }
+
+    pub fn advance(&mut self, dt: f64) {
+        let dt = dt as f32;
+        let blend = if self.smoothing > 0.0 { 1.0 - (-self.smoothing * dt).exp() } else { 1.0 };
+
+        if let Some(target_bounds) = self.target_bounds() {
+            let focus = target_bounds.center();
+
+            if let Some(zoom_to_fit) = self.zoom_to_fit {
+                let viewport_size = self.camera.viewport_size();
+                let fit = (viewport_size.x / (target_bounds.width() + zoom_to_fit.padding * 2.0))
+                    .min(viewport_size.y / (target_bounds.height() + zoom_to_fit.padding * 2.0));
+                let goal_zoom = fit.max(zoom_to_fit.min_zoom).min(zoom_to_fit.max_zoom);
+                self.zoom += (goal_zoom - self.zoom) * blend;
+            }
+
+            if self.look_ahead > 0.0 && dt > 0.0 {
+                let velocity = match self.previous_focus {
+                    Some(previous) => (focus - previous) / dt,
+                    None => cgmath::vec2(0.0, 0.0),
+                };
+                let goal_offset = velocity * self.look_ahead;
+                self.look_ahead_offset += (goal_offset - self.look_ahead_offset) * blend;
+            }
+            self.previous_focus = Some(focus);
+
+            let goal = self.dead_zone_goal(focus + self.look_ahead_offset);
+            self.position += (goal - self.position) * blend;
+        }
+
+        self.position = self.clamp_to_bounds(self.position);
+        self.shake.advance(dt as f64);
+        self.apply();
+    }
+
+    fn target_bounds(&self) -> Option<Bounds> {
+        let first = match self.targets.first() {
+            Some(&first) => first,
+            None => return None,
+        };
+        let mut bounds = Bounds::new(first, first);
+        for target in &self.targets[1..] {
+            bounds.min.x = bounds.min.x.min(target.x);
+            bounds.min.y = bounds.min.y.min(target.y);
+            bounds.max.x = bounds.max.x.max(target.x);
+            bounds.max.y = bounds.max.y.max(target.y);
+        }
+        Some(bounds)
+    }
+
+    /// Move the camera only as far as needed to put `focus` back inside the dead zone.
+    fn dead_zone_goal(&self, focus: Vector2<f32>) -> Vector2<f32> {
+        let half = self.dead_zone * 0.5;
+        let offset = focus - self.position;
+        let push = |offset: f32, half: f32| {
+            if offset > half {
+                offset - half
+            } else if offset < -half {
+                offset + half
+            } else {
+                0.0
+            }
+        };
+        self.position + cgmath::vec2(push(offset.x, half.x), push(offset.y, half.y))
+    }
+
+    fn clamp_to_bounds(&self, position: Vector2<f32>) -> Vector2<f32> {
+        let bounds = match self.bounds {
+            Some(bounds) => bounds,
+            None => return position,
+        };
+        let half_view = self.camera.viewport_size() / self.zoom * 0.5;
+        let clamp = |value: f32, min: f32, max: f32, half: f32| {
+            if max - min <= half * 2.0 {
+                (min + max) / 2.0
+            } else {
+                value.max(min + half).min(max - half)
+            }
+        };
+        cgmath::vec2(clamp(position.x, bounds.min.x, bounds.max.x, half_view.x),
+                     clamp(position.y, bounds.min.y, bounds.max.y, half_view.y))
+    }
+
+    fn apply(&mut self) {
+        let (offset, angle) = self.shake.offset();
+        self.camera.set_position(self.position + offset);
+        self.camera.set_zoom(self.zoom);
+        self.camera.set_rotation(self.rotation + angle);
+    }
+}
+
+/// One-dimensional Perlin noise, roughly in the range -1..1.
+fn perlin(x: f32, seed: u32) -> f32 {
+    let gradient = |i: i32| {
+        let mut hash = (i as u32).wrapping_mul(0x27d4_eb2d) ^ seed.wrapping_mul(0x1656_67b1);
+        hash ^= hash >> 15;
+        hash = hash.wrapping_mul(0x85eb_ca6b);
+        hash ^= hash >> 13;
+        (hash & 0xffff) as f32 / 32767.5 - 1.0
+    };
+    let fade = |t: f32| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+
+    let i = x.floor();
+    let t = x - i;
+    let a = gradient(i as i32) * t;
+    let b = gradient(i as i32 + 1) * (t - 1.0);
+    // The gradient noise peaks at 0.5, so scale it up to fill the range.
+    (a + (b - a) * fade(t)) * 2.0
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath;
+    use graphics::camera::{Bounds, Camera2D};
+    use super::CameraController;
+
+    #[test]
+    fn follows_within_dead_zone_and_bounds() {
+        let mut controller = CameraController::new(Camera2D::new(100.0, 100.0))
+            .with_smoothing(0.0)
+            .with_dead_zone(20.0, 20.0)
+            .with_bounds(Bounds::new(cgmath::vec2(0.0, 0.0), cgmath::vec2(200.0, 200.0)));
+
+        controller.follow(cgmath::vec2(55.0, 50.0));
+        controller.advance(0.016);
+        assert_eq!(controller.position(), cgmath::vec2(50.0, 50.0));
+
+        controller.follow(cgmath::vec2(100.0, 50.0));
+        controller.advance(0.016);
+        assert_eq!(controller.position(), cgmath::vec2(90.0, 50.0));
+
+        controller.follow(cgmath::vec2(500.0, 50.0));
+        controller.advance(0.016);
+        assert_eq!(controller.position(), cgmath::vec2(150.0, 50.0));
+    }
+
+    #[test]
+    fn shake_decays() {
+        let mut controller = CameraController::new(Camera2D::new(100.0, 100.0));
+        controller.set_rotation(10.0);
+        controller.add_trauma(0.5);
+        controller.advance(0.25);
+        assert!(controller.shake.is_shaking());
+        controller.advance(0.5);
+        assert!(!controller.shake.is_shaking());
+        assert_eq!(controller.camera().position(), controller.position());
+        assert_eq!(controller.camera().rotation(), 10.0);
+    }
+}
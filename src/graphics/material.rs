@@ -0,0 +1,136 @@
+use std::rc::Rc;
+
+use glium;
+use glium::backend::Facade;
+use glium::uniforms::SamplerBehavior;
+
+use graphics::shader::{ShaderError, ShaderPreprocessor, ShaderSource};
+use graphics::uniforms::{Uniform, UniformSet};
+
+
+const VERTEX_SHADER_SRC: &'static str = include_str!("shaders/sprite.vs.glsl");
+const FLASH_SHADER_SRC: &'static str = include_str!("shaders/sprite_flash.fs.glsl");
+const OUTLINE_SHADER_SRC: &'static str = include_str!("shaders/sprite_outline.fs.glsl");
+const DISSOLVE_SHADER_SRC: &'static str = include_str!("shaders/sprite_dissolve.fs.glsl");
+const GRAYSCALE_SHADER_SRC: &'static str = include_str!("shaders/sprite_grayscale.fs.glsl");
+
+/// A shader and its settings for drawing sprites, e.g. a hit flash or an outline.
+///
+/// Materials are shared with `Rc`, and `SpriteBatch` draws consecutive sprites with the same
+/// material and texture together. Use `Rc::make_mut` to change one material's uniforms without
+/// affecting sprites that share the original.
+#[derive(Clone)]
+pub struct Material {
+    name: String,
+    shader: Rc<glium::Program>,
+    uniforms: UniformSet,
+    blend: Option<glium::Blend>,
+    sampler: Option<SamplerBehavior>,
+}
+
+impl Material {
+    /// Create a material with a shader that takes the same inputs as the default sprite shader.
+    pub fn new(name: &str, shader: Rc<glium::Program>) -> Self {
+        Material {
+            name: name.into(),
+            shader: shader,
+            uniforms: UniformSet::new(),
+            blend: None,
+            sampler: None,
+        }
+    }
+
+    /// Create a material from a fragment shader, using the default sprite vertex shader. It
+    /// receives `in vec2 texCoords` and `in vec3 spriteColor`, and errors are reported under the
+    /// material's name.
+    pub fn from_fragment_shader<F: Facade>(facade: &F, preprocessor: &ShaderPreprocessor, name: &str,
+                                           fragment_shader: &str) -> Result<Self, ShaderError> {
+        Material::from_source(facade, preprocessor, name, ShaderSource::new(name, fragment_shader))
+    }
+
+    fn from_source<F: Facade>(facade: &F, preprocessor: &ShaderPreprocessor, name: &str,
+                              fragment_shader: ShaderSource) -> Result<Self, ShaderError> {
+        let shader = preprocessor
+            .program(facade, ShaderSource::new("sprite.vs.glsl", VERTEX_SHADER_SRC), fragment_shader)?;
+        Ok(Material::new(name, Rc::new(shader)))
+    }
+
+    /// Blends the sprite towards `flash_color` by `flash_amount`.
+    pub fn hit_flash<F: Facade>(facade: &F, preprocessor: &ShaderPreprocessor) -> Result<Self, ShaderError> {
+        let source = ShaderSource::new("sprite_flash.fs.glsl", FLASH_SHADER_SRC);
+        Ok(Material::from_source(facade, preprocessor, "hit_flash", source)?
+            .with_uniform("flash_color", [1.0f32, 1.0, 1.0])
+            .with_uniform("flash_amount", 1.0f32))
+    }
+
+    /// Draws `outline_color` around the sprite's opaque pixels, `outline_width` texels wide. The
+    /// texture needs transparent space around the sprite for the outline to show.
+    pub fn outline<F: Facade>(facade: &F, preprocessor: &ShaderPreprocessor) -> Result<Self, ShaderError> {
+        let source = ShaderSource::new("sprite_outline.fs.glsl", OUTLINE_SHADER_SRC);
+        Ok(Material::from_source(facade, preprocessor, "outline", source)?
+            .with_uniform("outline_color", [1.0f32, 1.0, 1.0, 1.0])
+            .with_uniform("outline_width", 1.0f32))
+    }
+
+    /// Eats away at the sprite as `dissolve` goes from 0 to 1, with an `edge_color` border.
+    pub fn dissolve<F: Facade>(facade: &F, preprocessor: &ShaderPreprocessor) -> Result<Self, ShaderError> {
+        let source = ShaderSource::new("sprite_dissolve.fs.glsl", DISSOLVE_SHADER_SRC);
+        Ok(Material::from_source(facade, preprocessor, "dissolve", source)?
+            .with_uniform("dissolve", 0.0f32)
+            .with_uniform("edge_width", 0.05f32)
+            .with_uniform("edge_color", [1.0f32, 0.5, 0.0]))
+    }
+
+    /// Desaturates the sprite by `amount`.
+    pub fn grayscale<F: Facade>(facade: &F, preprocessor: &ShaderPreprocessor) -> Result<Self, ShaderError> {
+        let source = ShaderSource::new("sprite_grayscale.fs.glsl", GRAYSCALE_SHADER_SRC);
+        Ok(Material::from_source(facade, preprocessor, "grayscale", source)?
+            .with_uniform("amount", 1.0f32))
+    }
+
+    pub fn with_uniform<U: Into<Uniform>>(mut self, name: &str, value: U) -> Self {
+        self.uniforms.set(name, value);
+        self
+    }
+
+    /// Override the batch's blending for sprites with this material.
+    pub fn with_blend(mut self, blend: glium::Blend) -> Self {
+        self.blend = Some(blend);
+        self
+    }
+
+    /// Override the batch's sampler for sprites with this material.
+    pub fn with_sampler(mut self, sampler: SamplerBehavior) -> Self {
+        self.sampler = Some(sampler);
+        self
+    }
+
+    /// The name draw calls are reported under.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn shader(&self) -> &glium::Program {
+        &self.shader
+    }
+
+    pub fn uniforms(&self) -> &UniformSet {
+        &self.uniforms
+    }
+
+    pub fn uniforms_mut(&mut self) -> &mut UniformSet {
+        &mut self.uniforms
+    }
+
+    pub fn set_uniform<U: Into<Uniform>>(&mut self, name: &str, value: U) {
+        self.uniforms.set(name, value);
+    }
+
+    pub fn blend(&self) -> Option<glium::Blend> {
+        self.blend
+    }
+
+    pub fn sampler(&self) -> Option<SamplerBehavior> {
+        self.sampler
+    }
+}
@@ -6,15 +6,25 @@ use image;
 use sdl2;
 
 use config::MidgarAppConfig;
+use self::viewport::Viewport;
 
 pub mod animation;
+pub mod camera;
+pub mod camera_controller;
+pub mod material;
+pub mod post_process;
+pub mod render_target;
+pub mod shader;
 pub mod shape;
 pub mod sprite;
 pub mod texture;
+pub mod uniforms;
+pub mod viewport;
 
 
 pub struct Graphics {
     display: SDL2Facade,
+    viewport: Option<Viewport>,
 }
 
 impl Graphics {
@@ -43,6 +53,7 @@ impl Graphics {
 
         Graphics {
             display: display,
+            viewport: None,
         }
     }
 
@@ -59,6 +70,34 @@ impl Graphics {
         self.display.get_framebuffer_dimensions()
     }
 
+    /// The viewport that is kept up to date with the screen size.
+    pub fn viewport(&self) -> Option<&Viewport> {
+        self.viewport.as_ref()
+    }
+
+    pub fn viewport_mut(&mut self) -> Option<&mut Viewport> {
+        self.viewport.as_mut()
+    }
+
+    /// Set the viewport to update whenever the window is resized. It's updated to the current
+    /// screen size right away.
+    pub fn set_viewport(&mut self, mut viewport: Viewport) {
+        let (width, height) = self.screen_size();
+        viewport.update(width, height);
+        self.viewport = Some(viewport);
+    }
+
+    pub fn clear_viewport(&mut self) -> Option<Viewport> {
+        self.viewport.take()
+    }
+
+    // FIXME: This shouldn't be accessible outside the crate.
+    pub fn update_viewport(&mut self, width: u32, height: u32) {
+        if let Some(ref mut viewport) = self.viewport {
+            viewport.update(width, height);
+        }
+    }
+
     // FIXME: Return a Result.
     pub fn load_texture<P: AsRef<Path>>(&self, path: P, reversed: bool) -> glium::Texture2d {
         let image = image::open(path).unwrap().to_rgba();
@@ -0,0 +1,508 @@
+use std::cell::RefCell;
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+
+use cgmath::Vector2;
+use glium::{self, Surface};
+use glium::backend::Facade;
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::uniforms::{Sampler, Uniforms};
+
+use graphics::render_target::RenderTarget;
+use graphics::shader::{ShaderError, ShaderPreprocessor, ShaderSource};
+use graphics::uniforms::{clamped_sampler, Uniform, UniformSet};
+use time::Time;
+
+
+const VERTEX_SHADER_SRC: &'static str = include_str!("shaders/post.vs.glsl");
+const COPY_SHADER_SRC: &'static str = include_str!("shaders/post_copy.fs.glsl");
+const BLUR_SHADER_SRC: &'static str = include_str!("shaders/post_blur.fs.glsl");
+const BLOOM_THRESHOLD_SHADER_SRC: &'static str = include_str!("shaders/post_bloom_threshold.fs.glsl");
+const BLOOM_COMBINE_SHADER_SRC: &'static str = include_str!("shaders/post_bloom_combine.fs.glsl");
+const VIGNETTE_SHADER_SRC: &'static str = include_str!("shaders/post_vignette.fs.glsl");
+const CRT_SHADER_SRC: &'static str = include_str!("shaders/post_crt.fs.glsl");
+const CHROMATIC_ABERRATION_SHADER_SRC: &'static str = include_str!("shaders/post_chromatic_aberration.fs.glsl");
+const COLOR_GRADING_SHADER_SRC: &'static str = include_str!("shaders/post_color_grading.fs.glsl");
+
+#[derive(Clone, Copy)]
+struct QuadVertex {
+    pos: [f32; 2],
+    tex_coords: [f32; 2],
+}
+
+implement_vertex!(QuadVertex, pos, tex_coords);
+
+/// A fullscreen effect made of one or more passes.
+///
+/// Each pass is a GLSL 3.3 fragment shader that receives `in vec2 texCoords` and these uniforms,
+/// on top of the ones the effect sets itself:
+///
+/// - `sampler2D image`: the output of the previous pass.
+/// - `sampler2D original`: the input to the effect's first pass.
+/// - `vec2 resolution`: the size of the target in pixels.
+/// - `float time`: seconds the `PostProcessor` has been advanced with `update` or `advance`.
+pub trait PostEffect {
+    fn passes(&self) -> usize {
+        1
+    }
+
+    /// The fragment shader source for `pass`. Only called when the effect is added. It can
+    /// `#include` the snippets in the `PostProcessor`'s preprocessor, like `color.glsl`.
+    fn fragment_shader(&self, pass: usize) -> String;
+
+    /// Set the effect's own uniforms for `pass`. Called every frame.
+    fn uniforms(&self, _pass: usize, _uniforms: &mut UniformSet) {}
+}
+
+/// Lets the game keep a handle to an effect and tweak it after adding it.
+impl<E: PostEffect + ?Sized> PostEffect for Rc<RefCell<E>> {
+    fn passes(&self) -> usize {
+        self.borrow().passes()
+    }
+
+    fn fragment_shader(&self, pass: usize) -> String {
+        self.borrow().fragment_shader(pass)
+    }
+
+    fn uniforms(&self, pass: usize, uniforms: &mut UniformSet) {
+        self.borrow().uniforms(pass, uniforms)
+    }
+}
+
+/// A two-pass Gaussian blur.
+#[derive(Clone, Debug)]
+pub struct Blur {
+    /// How far apart the samples are, in pixels. Bigger values blur more.
+    pub radius: f32,
+}
+
+impl Default for Blur {
+    fn default() -> Self {
+        Blur {
+            radius: 1.0,
+        }
+    }
+}
+
+impl PostEffect for Blur {
+    fn passes(&self) -> usize {
+        2
+    }
+
+    fn fragment_shader(&self, _pass: usize) -> String {
+        BLUR_SHADER_SRC.into()
+    }
+
+    fn uniforms(&self, pass: usize, uniforms: &mut UniformSet) {
+        uniforms.set("direction", if pass == 0 { [1.0f32, 0.0] } else { [0.0, 1.0] });
+        uniforms.set("radius", self.radius);
+    }
+}
+
+/// Makes bright pixels glow by blurring them and adding them back on top.
+#[derive(Clone, Debug)]
+pub struct Bloom {
+    /// How bright a pixel has to be to bloom, from 0 to 1.
+    pub threshold: f32,
+    pub intensity: f32,
+    pub blur_radius: f32,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Bloom {
+            threshold: 0.7,
+            intensity: 1.0,
+            blur_radius: 2.0,
+        }
+    }
+}
+
+impl PostEffect for Bloom {
+    fn passes(&self) -> usize {
+        4
+    }
+
+    fn fragment_shader(&self, pass: usize) -> String {
+        match pass {
+            0 => BLOOM_THRESHOLD_SHADER_SRC,
+            1 | 2 => BLUR_SHADER_SRC,
+            _ => BLOOM_COMBINE_SHADER_SRC,
+        }.into()
+    }
+
+    fn uniforms(&self, pass: usize, uniforms: &mut UniformSet) {
+        match pass {
+            0 => uniforms.set("threshold", self.threshold),
+            1 | 2 => {
+                uniforms.set("direction", if pass == 1 { [1.0f32, 0.0] } else { [0.0, 1.0] });
+                uniforms.set("radius", self.blur_radius);
+            },
+            _ => uniforms.set("intensity", self.intensity),
+        }
+    }
+}
+
+/// Darkens the corners of the screen.
+#[derive(Clone, Debug)]
+pub struct Vignette {
+    /// Where the darkening ends, as a fraction of the distance from the center to the corners.
+    pub radius: f32,
+    /// How far before `radius` the darkening starts.
+    pub softness: f32,
+    pub intensity: f32,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Vignette {
+            radius: 1.0,
+            softness: 0.6,
+            intensity: 1.0,
+        }
+    }
+}
+
+impl PostEffect for Vignette {
+    fn fragment_shader(&self, _pass: usize) -> String {
+        VIGNETTE_SHADER_SRC.into()
+    }
+
+    fn uniforms(&self, _pass: usize, uniforms: &mut UniformSet) {
+        uniforms.set("radius", self.radius);
+        uniforms.set("softness", self.softness);
+        uniforms.set("intensity", self.intensity);
+    }
+}
+
+/// Scanlines and a curved screen, like an old CRT monitor.
+#[derive(Clone, Debug)]
+pub struct Crt {
+    pub curvature: f32,
+    pub scanline_intensity: f32,
+    /// Scanlines per pixel of screen height. 0.5 gives one scanline every two pixels.
+    pub scanline_density: f32,
+}
+
+impl Default for Crt {
+    fn default() -> Self {
+        Crt {
+            curvature: 0.1,
+            scanline_intensity: 0.3,
+            scanline_density: 0.5,
+        }
+    }
+}
+
+impl PostEffect for Crt {
+    fn fragment_shader(&self, _pass: usize) -> String {
+        CRT_SHADER_SRC.into()
+    }
+
+    fn uniforms(&self, _pass: usize, uniforms: &mut UniformSet) {
+        uniforms.set("curvature", self.curvature);
+        uniforms.set("scanline_intensity", self.scanline_intensity);
+        uniforms.set("scanline_density", self.scanline_density);
+    }
+}
+
+/// Splits the red and blue channels apart towards the edges of the screen.
+#[derive(Clone, Debug)]
+pub struct ChromaticAberration {
+    /// How far the channels are pulled apart at the edges, in pixels.
+    pub offset: f32,
+}
+
+impl Default for ChromaticAberration {
+    fn default() -> Self {
+        ChromaticAberration {
+            offset: 2.0,
+        }
+    }
+}
+
+impl PostEffect for ChromaticAberration {
+    fn fragment_shader(&self, _pass: usize) -> String {
+        CHROMATIC_ABERRATION_SHADER_SRC.into()
+    }
+
+    fn uniforms(&self, _pass: usize, uniforms: &mut UniformSet) {
+        uniforms.set("offset", self.offset);
+    }
+}
+
+/// Remaps colors through a lookup table.
+///
+/// The table is a strip of `N` slices of `N` by `N` pixels, e.g. 256x16 for `N` = 16. Red goes
+/// left to right within a slice, green bottom to top, and blue increases from slice to slice.
+#[derive(Clone)]
+pub struct ColorGrading {
+    pub lut: Rc<glium::Texture2d>,
+    /// How much of the graded color to mix in, from 0 to 1.
+    pub intensity: f32,
+}
+
+impl ColorGrading {
+    pub fn new(lut: Rc<glium::Texture2d>) -> Self {
+        ColorGrading {
+            lut: lut,
+            intensity: 1.0,
+        }
+    }
+}
+
+impl PostEffect for ColorGrading {
+    fn fragment_shader(&self, _pass: usize) -> String {
+        COLOR_GRADING_SHADER_SRC.into()
+    }
+
+    fn uniforms(&self, _pass: usize, uniforms: &mut UniformSet) {
+        uniforms.set("lut", Uniform::Texture(self.lut.clone(), clamped_sampler()));
+        uniforms.set("lut_size", self.lut.dimensions().1 as f32);
+        uniforms.set("intensity", self.intensity);
+    }
+}
+
+/// An error creating a `PostProcessor` or adding an effect to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PostProcessError {
+    /// A shader failed to preprocess, compile or link.
+    Shader(ShaderError),
+    /// Creating the processor's buffers failed.
+    Buffer(String),
+}
+
+impl fmt::Display for PostProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PostProcessError::Shader(ref error) => write!(f, "{}", error),
+            PostProcessError::Buffer(ref error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for PostProcessError {
+    fn description(&self) -> &str {
+        match *self {
+            PostProcessError::Shader(_) => "post-processing shader error",
+            PostProcessError::Buffer(ref error) => error,
+        }
+    }
+}
+
+impl From<ShaderError> for PostProcessError {
+    fn from(error: ShaderError) -> Self {
+        PostProcessError::Shader(error)
+    }
+}
+
+impl From<String> for PostProcessError {
+    fn from(error: String) -> Self {
+        PostProcessError::Buffer(error)
+    }
+}
+
+struct EffectEntry {
+    effect: Box<PostEffect>,
+    programs: Vec<glium::Program>,
+    enabled: bool,
+}
+
+/// Renders the scene into an offscreen target, then runs it through a chain of `PostEffect`s
+/// on the way to the screen.
+///
+/// Draw the scene into the surface from `begin`, then call `finish` with the real target.
+/// Intermediate results ping-pong between buffers the processor owns.
+pub struct PostProcessor {
+    scene: RenderTarget,
+    // Effects alternate between these for their output, so an effect's input survives all of
+    // its passes.
+    effect_buffers: [RenderTarget; 2],
+    // Passes within a multi-pass effect alternate between these.
+    pass_buffers: [RenderTarget; 2],
+    effects: Vec<EffectEntry>,
+    copy_program: glium::Program,
+    vertex_buffer: glium::VertexBuffer<QuadVertex>,
+    preprocessor: ShaderPreprocessor,
+    time: f32,
+}
+
+impl PostProcessor {
+    pub fn new<F: Facade>(facade: &F, width: u32, height: u32) -> Result<Self, PostProcessError> {
+        PostProcessor::with_preprocessor(facade, width, height, ShaderPreprocessor::new())
+    }
+
+    /// Create a processor whose shaders, including every effect's, are built with `preprocessor`.
+    pub fn with_preprocessor<F: Facade>(facade: &F, width: u32, height: u32,
+                                        preprocessor: ShaderPreprocessor) -> Result<Self, PostProcessError> {
+        let vertices = [
+            QuadVertex { pos: [-1.0, -1.0], tex_coords: [0.0, 0.0] },
+            QuadVertex { pos: [1.0, -1.0], tex_coords: [1.0, 0.0] },
+            QuadVertex { pos: [-1.0, 1.0], tex_coords: [0.0, 1.0] },
+            QuadVertex { pos: [1.0, 1.0], tex_coords: [1.0, 1.0] },
+        ];
+        let vertex_buffer = glium::VertexBuffer::new(facade, &vertices)
+            .map_err(|error| error.to_string())?;
+
+        Ok(PostProcessor {
+            scene: RenderTarget::new(facade, width, height)?,
+            effect_buffers: [RenderTarget::new(facade, width, height)?, RenderTarget::new(facade, width, height)?],
+            pass_buffers: [RenderTarget::new(facade, width, height)?, RenderTarget::new(facade, width, height)?],
+            effects: Vec::new(),
+            copy_program: compile_program(facade, &preprocessor,
+                                          ShaderSource::new("post_copy.fs.glsl", COPY_SHADER_SRC))?,
+            vertex_buffer: vertex_buffer,
+            preprocessor: preprocessor,
+            time: 0.0,
+        })
+    }
+
+    pub fn size(&self) -> Vector2<u32> {
+        self.scene.size()
+    }
+
+    /// The preprocessor effects added from now on are built with.
+    pub fn preprocessor(&self) -> &ShaderPreprocessor {
+        &self.preprocessor
+    }
+
+    pub fn preprocessor_mut(&mut self) -> &mut ShaderPreprocessor {
+        &mut self.preprocessor
+    }
+
+    /// Reallocate the buffers, e.g. when the window is resized.
+    pub fn resize<F: Facade>(&mut self, facade: &F, width: u32, height: u32) -> Result<(), String> {
+        self.scene.resize(facade, width, height)?;
+        for buffer in self.effect_buffers.iter_mut().chain(self.pass_buffers.iter_mut()) {
+            buffer.resize(facade, width, height)?;
+        }
+        Ok(())
+    }
+
+    /// Add an effect at the end of the chain and compile its shaders. Returns its index.
+    pub fn add_effect<F: Facade, E: PostEffect + 'static>(&mut self, facade: &F,
+                                                          effect: E) -> Result<usize, ShaderError> {
+        let programs = (0..effect.passes())
+            .map(|pass| {
+                let name = format!("effect {} pass {}", self.effects.len(), pass);
+                let source = effect.fragment_shader(pass);
+                compile_program(facade, &self.preprocessor, ShaderSource::new(&name, &source))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.effects.push(EffectEntry {
+            effect: Box::new(effect),
+            programs: programs,
+            enabled: true,
+        });
+        Ok(self.effects.len() - 1)
+    }
+
+    pub fn remove_effect(&mut self, index: usize) -> Box<PostEffect> {
+        self.effects.remove(index).effect
+    }
+
+    pub fn clear_effects(&mut self) {
+        self.effects.clear();
+    }
+
+    pub fn num_effects(&self) -> usize {
+        self.effects.len()
+    }
+
+    pub fn is_effect_enabled(&self, index: usize) -> bool {
+        self.effects.get(index).map(|entry| entry.enabled).unwrap_or(false)
+    }
+
+    /// Skip an effect without removing it.
+    pub fn set_effect_enabled(&mut self, index: usize, enabled: bool) {
+        if let Some(entry) = self.effects.get_mut(index) {
+            entry.enabled = enabled;
+        }
+    }
+
+    /// The target the scene is drawn into.
+    pub fn scene(&self) -> &RenderTarget {
+        &self.scene
+    }
+
+    /// A surface to draw the scene into.
+    pub fn begin<'a, F: Facade>(&'a self, facade: &F) -> Result<SimpleFrameBuffer<'a>, String> {
+        self.scene.framebuffer(facade)
+    }
+
+    /// Advance the `time` uniform by this frame's delta time. Skip it while paused to freeze
+    /// time-based effects.
+    pub fn update(&mut self, time: &Time) {
+        self.advance(time.delta_time());
+    }
+
+    pub fn advance(&mut self, dt: f64) {
+        self.time += dt as f32;
+    }
+
+    /// Run the scene through every enabled effect and draw the result into `target`.
+    pub fn finish<F: Facade, S: Surface>(&self, facade: &F, target: &mut S) -> Result<(), String> {
+        let size = self.size();
+        let resolution = [size.x as f32, size.y as f32];
+        let effects: Vec<&EffectEntry> = self.effects.iter().filter(|entry| entry.enabled).collect();
+
+        if effects.is_empty() {
+            let uniforms = uniform! {
+                image: Sampler(self.scene.region().texture(), clamped_sampler()),
+            };
+            return self.draw_quad(target, &self.copy_program, &uniforms);
+        }
+
+        for (index, entry) in effects.iter().enumerate() {
+            let effect_input = if index == 0 {
+                self.scene.region().texture()
+            } else {
+                self.effect_buffers[(index - 1) % 2].region().texture()
+            };
+            let passes = entry.programs.len();
+
+            for (pass, program) in entry.programs.iter().enumerate() {
+                let input = if pass == 0 {
+                    effect_input
+                } else {
+                    self.pass_buffers[(pass - 1) % 2].region().texture()
+                };
+
+                let mut effect_uniforms = UniformSet::new();
+                entry.effect.uniforms(pass, &mut effect_uniforms);
+                let engine_uniforms = uniform! {
+                    image: Sampler(input, clamped_sampler()),
+                    original: Sampler(effect_input, clamped_sampler()),
+                    resolution: resolution,
+                    time: self.time,
+                };
+                let uniforms = effect_uniforms.over(engine_uniforms);
+
+                if pass + 1 < passes {
+                    let mut framebuffer = self.pass_buffers[pass % 2].framebuffer(facade)?;
+                    self.draw_quad(&mut framebuffer, program, &uniforms)?;
+                } else if index + 1 < effects.len() {
+                    let mut framebuffer = self.effect_buffers[index % 2].framebuffer(facade)?;
+                    self.draw_quad(&mut framebuffer, program, &uniforms)?;
+                } else {
+                    self.draw_quad(target, program, &uniforms)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw_quad<S: Surface, U: Uniforms>(&self, target: &mut S, program: &glium::Program,
+                                          uniforms: &U) -> Result<(), String> {
+        let indices = glium::index::NoIndices(glium::index::PrimitiveType::TriangleStrip);
+        target.draw(&self.vertex_buffer, &indices, program, uniforms, &Default::default())
+            .map_err(|error| error.to_string())
+    }
+}
+
+fn compile_program<F: Facade>(facade: &F, preprocessor: &ShaderPreprocessor,
+                              fragment_shader: ShaderSource) -> Result<glium::Program, ShaderError> {
+    preprocessor.program(facade, ShaderSource::new("post.vs.glsl", VERTEX_SHADER_SRC), fragment_shader)
+}
@@ -0,0 +1,127 @@
+use std::rc::Rc;
+
+use cgmath::{self, Vector2};
+use glium;
+use glium::backend::Facade;
+use glium::framebuffer::{DepthRenderBuffer, DepthStencilRenderBuffer, SimpleFrameBuffer, StencilRenderBuffer};
+use glium::texture::{DepthFormat, DepthStencilFormat, MipmapsOption, StencilFormat, UncompressedFloatFormat};
+
+use graphics::sprite::Sprite;
+use graphics::texture::{TextureRegion, TextureRegionHolder};
+
+
+/// The extra buffers a `RenderTarget` is created with, besides its color texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Attachment {
+    None,
+    Depth,
+    Stencil,
+    DepthStencil,
+}
+
+enum AttachmentBuffer {
+    Depth(DepthRenderBuffer),
+    Stencil(StencilRenderBuffer),
+    DepthStencil(DepthStencilRenderBuffer),
+}
+
+/// An offscreen texture to draw into, e.g. for minimaps, upscaled pixel art or cached layers.
+///
+/// Get a `Surface` to draw into with `framebuffer`, then draw the result back with `sprite` or
+/// `texture_region`. Like the screen, the bottom of the texture is at Y = 0, so sprites drawn
+/// from it come out the right way up.
+pub struct RenderTarget {
+    region: TextureRegion,
+    attachment: Attachment,
+    attachment_buffer: Option<AttachmentBuffer>,
+}
+
+impl RenderTarget {
+    pub fn new<F: Facade>(facade: &F, width: u32, height: u32) -> Result<Self, String> {
+        RenderTarget::with_attachment(facade, width, height, Attachment::None)
+    }
+
+    pub fn with_attachment<F: Facade>(facade: &F, width: u32, height: u32,
+                                      attachment: Attachment) -> Result<Self, String> {
+        let (texture, attachment_buffer) = create_buffers(facade, width, height, attachment)?;
+        Ok(RenderTarget {
+            region: TextureRegion::new(Rc::new(texture)),
+            attachment: attachment,
+            attachment_buffer: attachment_buffer,
+        })
+    }
+
+    pub fn attachment(&self) -> Attachment {
+        self.attachment
+    }
+
+    pub fn size(&self) -> Vector2<u32> {
+        self.region.texture_size()
+    }
+
+    /// The whole color texture as a region.
+    pub fn region(&self) -> &TextureRegion {
+        &self.region
+    }
+
+    /// A sprite showing the whole color texture.
+    pub fn sprite(&self) -> Sprite {
+        Sprite::from_texture_region(&self.region)
+    }
+
+    /// A surface that draws into this target, for `SpriteRenderer::begin_batch` and friends.
+    pub fn framebuffer<'a, F: Facade>(&'a self, facade: &F) -> Result<SimpleFrameBuffer<'a>, String> {
+        let texture = self.region.texture();
+        let framebuffer = match self.attachment_buffer {
+            None => SimpleFrameBuffer::new(facade, texture),
+            Some(AttachmentBuffer::Depth(ref depth)) =>
+                SimpleFrameBuffer::with_depth_buffer(facade, texture, depth),
+            Some(AttachmentBuffer::Stencil(ref stencil)) =>
+                SimpleFrameBuffer::with_stencil_buffer(facade, texture, stencil),
+            Some(AttachmentBuffer::DepthStencil(ref depth_stencil)) =>
+                SimpleFrameBuffer::with_depth_stencil_buffer(facade, texture, depth_stencil),
+        };
+        framebuffer.map_err(|error| error.to_string())
+    }
+
+    /// Reallocate the target's buffers at a new size. Its contents are lost.
+    ///
+    /// Regions and sprites taken from the target before resizing keep showing the old texture.
+    pub fn resize<F: Facade>(&mut self, facade: &F, width: u32, height: u32) -> Result<(), String> {
+        if self.size() == cgmath::vec2(width, height) {
+            return Ok(());
+        }
+        let (texture, attachment_buffer) = create_buffers(facade, width, height, self.attachment)?;
+        self.region = TextureRegion::new(Rc::new(texture));
+        self.attachment_buffer = attachment_buffer;
+        Ok(())
+    }
+}
+
+impl TextureRegionHolder for RenderTarget {
+    fn texture_region(&self) -> &TextureRegion {
+        &self.region
+    }
+}
+
+fn create_buffers<F: Facade>(facade: &F, width: u32, height: u32, attachment: Attachment)
+                             -> Result<(glium::Texture2d, Option<AttachmentBuffer>), String> {
+    let texture = glium::Texture2d::empty_with_format(facade, UncompressedFloatFormat::U8U8U8U8,
+                                                      MipmapsOption::NoMipmap, width, height)
+        .map_err(|error| error.to_string())?;
+
+    let attachment_buffer = match attachment {
+        Attachment::None => None,
+        Attachment::Depth => Some(AttachmentBuffer::Depth(
+            DepthRenderBuffer::new(facade, DepthFormat::I24, width, height)
+                .map_err(|error| error.to_string())?)),
+        Attachment::Stencil => Some(AttachmentBuffer::Stencil(
+            StencilRenderBuffer::new(facade, StencilFormat::I8, width, height)
+                .map_err(|error| error.to_string())?)),
+        Attachment::DepthStencil => Some(AttachmentBuffer::DepthStencil(
+            DepthStencilRenderBuffer::new(facade, DepthStencilFormat::I24I8, width, height)
+                .map_err(|error| error.to_string())?)),
+    };
+
+    Ok((texture, attachment_buffer))
+}
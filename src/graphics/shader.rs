@@ -0,0 +1,422 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use glium;
+use glium::backend::Facade;
+use glium::program::ProgramCreationError;
+
+
+/// The GLSL version shaders are compiled as, matching the OpenGL 3.3 core context.
+pub const DEFAULT_VERSION: &'static str = "330 core";
+
+const COLOR_INCLUDE_SRC: &'static str = include_str!("shaders/color.glsl");
+
+/// Shader source code and the name to report errors in it under, usually its file name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShaderSource<'a> {
+    pub name: &'a str,
+    pub source: &'a str,
+}
+
+impl<'a> ShaderSource<'a> {
+    pub fn new(name: &'a str, source: &'a str) -> Self {
+        ShaderSource {
+            name: name,
+            source: source,
+        }
+    }
+}
+
+/// One line of a compile or link log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShaderMessage {
+    /// The original file the message points at, if the driver gave a location.
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl fmt::Display for ShaderMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.file, self.line) {
+            (&Some(ref file), Some(line)) => write!(f, "{}:{}: {}", file, line, self.message),
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShaderError {
+    /// An `#include` names a file that hasn't been added to the preprocessor.
+    MissingInclude { file: String, line: u32, include: String },
+    /// A file includes itself, directly or through other includes.
+    RecursiveInclude { file: String, line: u32, include: String },
+    /// An `#include` without a quoted file name.
+    MalformedInclude { file: String, line: u32 },
+    Compile(Vec<ShaderMessage>),
+    Link(Vec<ShaderMessage>),
+    /// Any other error creating the program, e.g. the driver not supporting a shader type.
+    Other(String),
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ShaderError::MissingInclude { ref file, line, ref include } =>
+                write!(f, "{}:{}: could not find include \"{}\"", file, line, include),
+            ShaderError::RecursiveInclude { ref file, line, ref include } =>
+                write!(f, "{}:{}: \"{}\" includes itself", file, line, include),
+            ShaderError::MalformedInclude { ref file, line } =>
+                write!(f, "{}:{}: expected #include \"file\"", file, line),
+            ShaderError::Compile(ref messages) | ShaderError::Link(ref messages) => {
+                match *self {
+                    ShaderError::Compile(_) => write!(f, "shader compilation failed")?,
+                    _ => write!(f, "shader program linking failed")?,
+                }
+                for message in messages {
+                    write!(f, "\n{}", message)?;
+                }
+                Ok(())
+            },
+            ShaderError::Other(ref error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for ShaderError {
+    fn description(&self) -> &str {
+        match *self {
+            ShaderError::MissingInclude { .. } => "missing shader include",
+            ShaderError::RecursiveInclude { .. } => "recursive shader include",
+            ShaderError::MalformedInclude { .. } => "malformed shader include",
+            ShaderError::Compile(_) => "shader compilation failed",
+            ShaderError::Link(_) => "shader program linking failed",
+            ShaderError::Other(ref error) => error,
+        }
+    }
+}
+
+/// A shader after preprocessing, ready to hand to the driver.
+#[derive(Clone, Debug)]
+pub struct PreprocessedShader {
+    source: String,
+    files: Vec<String>,
+}
+
+impl PreprocessedShader {
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The files that went into the shader, indexed by their GLSL source string number.
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
+
+    /// Point the messages in a compile log for this shader back at the original files.
+    pub fn map_log(&self, log: &str) -> Vec<ShaderMessage> {
+        map_log(log, &self.files)
+    }
+}
+
+/// Prepares GLSL source before it's compiled.
+///
+/// - `#include "name"` pastes in a snippet added with `add_include`. Each snippet is included at
+///   most once per shader. `color.glsl` is available by default.
+/// - `#version` lines are replaced with the preprocessor's version.
+/// - Defines are inserted at the top of every shader, e.g. to select a variant with `#ifdef`.
+///
+/// Includes are expanded even inside `#if` blocks, since that's left to the driver.
+///
+/// Each file is given its own source string number with `#line`, so driver errors can be mapped
+/// back to the original file and line.
+#[derive(Clone, Debug)]
+pub struct ShaderPreprocessor {
+    version: String,
+    defines: Vec<(String, String)>,
+    includes: HashMap<String, String>,
+}
+
+impl ShaderPreprocessor {
+    pub fn new() -> Self {
+        let mut includes = HashMap::new();
+        includes.insert("color.glsl".into(), COLOR_INCLUDE_SRC.into());
+
+        ShaderPreprocessor {
+            version: DEFAULT_VERSION.into(),
+            defines: Vec::new(),
+            includes: includes,
+        }
+    }
+
+    pub fn with_version(mut self, version: &str) -> Self {
+        self.set_version(version);
+        self
+    }
+
+    pub fn with_define(mut self, name: &str, value: &str) -> Self {
+        self.define(name, value);
+        self
+    }
+
+    pub fn with_include(mut self, name: &str, source: &str) -> Self {
+        self.add_include(name, source);
+        self
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Set the `#version` header, e.g. `"330 core"` or `"300 es"`.
+    pub fn set_version(&mut self, version: &str) {
+        self.version = version.into();
+    }
+
+    /// Add `#define name value`, replacing any existing define with the same name.
+    pub fn define(&mut self, name: &str, value: &str) {
+        match self.defines.iter_mut().find(|define| define.0 == name) {
+            Some(define) => define.1 = value.into(),
+            None => self.defines.push((name.into(), value.into())),
+        }
+    }
+
+    pub fn undefine(&mut self, name: &str) {
+        self.defines.retain(|define| define.0 != name);
+    }
+
+    /// Make a snippet available to `#include`, replacing any existing one with the same name.
+    pub fn add_include(&mut self, name: &str, source: &str) {
+        self.includes.insert(name.into(), source.into());
+    }
+
+    pub fn preprocess(&self, shader: ShaderSource) -> Result<PreprocessedShader, ShaderError> {
+        let mut files = Vec::new();
+        let source = self.expand_shader(shader, &mut files)?;
+        Ok(PreprocessedShader {
+            source: source,
+            files: files,
+        })
+    }
+
+    /// Preprocess and build a program, pointing any compile or link errors at the original files.
+    pub fn program<F: Facade>(&self, facade: &F, vertex_shader: ShaderSource,
+                              fragment_shader: ShaderSource) -> Result<glium::Program, ShaderError> {
+        // Both stages share source string numbers, since glium doesn't say which one failed.
+        let mut files = Vec::new();
+        let vertex_source = self.expand_shader(vertex_shader, &mut files)?;
+        let fragment_source = self.expand_shader(fragment_shader, &mut files)?;
+
+        // NOTE: By default, assume shaders output sRGB colors.
+        let program_creation_input = glium::program::ProgramCreationInput::SourceCode {
+            vertex_shader: &vertex_source,
+            fragment_shader: &fragment_source,
+            geometry_shader: None,
+            tessellation_control_shader: None,
+            tessellation_evaluation_shader: None,
+            transform_feedback_varyings: None,
+            outputs_srgb: true,
+            uses_point_size: false,
+        };
+        glium::Program::new(facade, program_creation_input).map_err(|error| match error {
+            ProgramCreationError::CompilationError(log) => ShaderError::Compile(map_log(&log, &files)),
+            ProgramCreationError::LinkingError(log) => ShaderError::Link(map_log(&log, &files)),
+            error => ShaderError::Other(error.to_string()),
+        })
+    }
+
+    fn expand_shader(&self, shader: ShaderSource, files: &mut Vec<String>) -> Result<String, ShaderError> {
+        // The version has to come first, then the defines so the whole shader sees them.
+        let mut output = format!("#version {}\n", self.version);
+        for &(ref name, ref value) in &self.defines {
+            output.push_str(&format!("#define {} {}\n", name, value));
+        }
+
+        let mut stack = Vec::new();
+        let mut included = Vec::new();
+        self.expand(shader, files, &mut stack, &mut included, &mut output)?;
+        Ok(output)
+    }
+
+    fn expand(&self, shader: ShaderSource, files: &mut Vec<String>, stack: &mut Vec<String>,
+              included: &mut Vec<String>, output: &mut String) -> Result<(), ShaderError> {
+        let index = match files.iter().position(|file| file == shader.name) {
+            Some(index) => index,
+            None => {
+                files.push(shader.name.into());
+                files.len() - 1
+            },
+        };
+        stack.push(shader.name.into());
+
+        // NOTE: Since GLSL 3.30, `#line` sets the number of the line after it.
+        output.push_str(&format!("#line 1 {}\n", index));
+        for (i, line) in shader.source.lines().enumerate() {
+            let line_number = i as u32 + 1;
+            let directive = line.trim();
+
+            if directive.starts_with("#version") {
+                output.push('\n');
+            } else if directive.starts_with("#include") {
+                let include = match parse_include(directive) {
+                    Some(include) => include,
+                    None => return Err(ShaderError::MalformedInclude {
+                        file: shader.name.into(),
+                        line: line_number,
+                    }),
+                };
+                if stack.iter().any(|name| name == include) {
+                    return Err(ShaderError::RecursiveInclude {
+                        file: shader.name.into(),
+                        line: line_number,
+                        include: include.into(),
+                    });
+                }
+                if !included.iter().any(|name| name == include) {
+                    let source = match self.includes.get(include) {
+                        Some(source) => source,
+                        None => return Err(ShaderError::MissingInclude {
+                            file: shader.name.into(),
+                            line: line_number,
+                            include: include.into(),
+                        }),
+                    };
+                    included.push(include.into());
+                    self.expand(ShaderSource::new(include, source), files, stack, included, output)?;
+                }
+                output.push_str(&format!("#line {} {}\n", line_number + 1, index));
+            } else {
+                output.push_str(line);
+                output.push('\n');
+            }
+        }
+
+        stack.pop();
+        Ok(())
+    }
+}
+
+impl Default for ShaderPreprocessor {
+    fn default() -> Self {
+        ShaderPreprocessor::new()
+    }
+}
+
+/// The file name in `#include "name"` or `#include <name>`.
+fn parse_include(directive: &str) -> Option<&str> {
+    let rest = directive["#include".len()..].trim();
+    let close = match rest.chars().next() {
+        Some('"') => '"',
+        Some('<') => '>',
+        _ => return None,
+    };
+    match rest[1..].find(close) {
+        Some(end) if end > 0 => Some(&rest[1..end + 1]),
+        _ => None,
+    }
+}
+
+fn map_log(log: &str, files: &[String]) -> Vec<ShaderMessage> {
+    log.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| match parse_location(line) {
+            Some((index, line_number, message)) if index < files.len() => ShaderMessage {
+                file: Some(files[index].clone()),
+                line: Some(line_number),
+                message: message,
+            },
+            _ => ShaderMessage {
+                file: None,
+                line: None,
+                message: line.into(),
+            },
+        })
+        .collect()
+}
+
+/// Split a log line into its source string number, line number and message. Drivers write the
+/// location differently, e.g. `0:12(5): error` (Mesa), `0(12) : error` (NVIDIA) or
+/// `ERROR: 0:12: message` (AMD and Apple).
+fn parse_location(line: &str) -> Option<(usize, u32, String)> {
+    let bytes = line.as_bytes();
+    let is_digit = |byte: u8| byte >= b'0' && byte <= b'9';
+    let digits_end = |start: usize| {
+        bytes[start..].iter().position(|&byte| !is_digit(byte))
+            .map_or(bytes.len(), |length| start + length)
+    };
+
+    let mut start = 0;
+    while start < bytes.len() {
+        let at_word_start = start == 0 || !(bytes[start - 1] as char).is_alphanumeric();
+        if !is_digit(bytes[start]) || !at_word_start {
+            start += 1;
+            continue;
+        }
+
+        let index_end = digits_end(start);
+        if index_end < bytes.len() && (bytes[index_end] == b':' || bytes[index_end] == b'(') {
+            let line_end = digits_end(index_end + 1);
+            if line_end > index_end + 1 {
+                let index = line[start..index_end].parse().ok();
+                let line_number = line[index_end + 1..line_end].parse().ok();
+                if let (Some(index), Some(line_number)) = (index, line_number) {
+                    // Skip a column number, then the separators before the message.
+                    let mut rest = &line[line_end..];
+                    if rest.starts_with('(') {
+                        if let Some(close) = rest.find(')') {
+                            rest = &rest[close + 1..];
+                        }
+                    }
+                    let message_start = rest.find(|c| c != ')' && c != ':' && c != ' ').unwrap_or(rest.len());
+                    let rest = &rest[message_start..];
+
+                    let prefix = line[..start].trim();
+                    let message = if prefix.is_empty() {
+                        rest.into()
+                    } else {
+                        format!("{} {}", prefix, rest)
+                    };
+                    return Some((index, line_number, message));
+                }
+            }
+        }
+        start = index_end;
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ShaderError, ShaderMessage, ShaderPreprocessor, ShaderSource};
+
+    #[test]
+    fn expands_includes_and_maps_errors_back() {
+        let preprocessor = ShaderPreprocessor::new()
+            .with_define("OUTLINE", "1")
+            .with_include("light.glsl", "#include \"color.glsl\"\nfloat light() { return 1.0; }");
+        let shader = preprocessor.preprocess(ShaderSource::new(
+            "main.fs.glsl",
+            "#version 330 core\n#include \"light.glsl\"\n#include \"color.glsl\"\nvoid main() {}",
+        )).unwrap();
+
+        let source = shader.source();
+        assert!(source.starts_with("#version 330 core\n#define OUTLINE 1\n#line 1 0\n\n#line 1 1\n#line 1 2\n"));
+        assert_eq!(source.matches("float luminance").count(), 1);
+        assert!(source.ends_with("#line 4 0\nvoid main() {}\n"));
+        assert_eq!(shader.files(), &["main.fs.glsl", "light.glsl", "color.glsl"]);
+
+        let messages = shader.map_log("0:4(10): error: syntax error\n1(2) : error C0000: oops\nlinker noise");
+        assert_eq!(messages[0].to_string(), "main.fs.glsl:4: error: syntax error");
+        assert_eq!(messages[1].to_string(), "light.glsl:2: error C0000: oops");
+        assert_eq!(messages[2], ShaderMessage { file: None, line: None, message: "linker noise".into() });
+
+        let missing = preprocessor.preprocess(ShaderSource::new("a.glsl", "\n#include \"nope.glsl\""));
+        assert_eq!(missing.unwrap_err(), ShaderError::MissingInclude {
+            file: "a.glsl".into(),
+            line: 2,
+            include: "nope.glsl".into(),
+        });
+    }
+}
@@ -0,0 +1,6 @@
+// Shared color helpers. Include with `#include "color.glsl"`.
+
+// The relative luminance of a linear RGB color, using Rec. 709 weights.
+float luminance(vec3 rgb) {
+    return dot(rgb, vec3(0.2126, 0.7152, 0.0722));
+}
@@ -0,0 +1,11 @@
+#version 330 core
+
+layout (location = 0) in vec2 pos;
+layout (location = 1) in vec2 tex_coords;
+
+out vec2 texCoords;
+
+void main() {
+    texCoords = tex_coords;
+    gl_Position = vec4(pos, 0.0, 1.0);
+}
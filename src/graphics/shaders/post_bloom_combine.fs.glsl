@@ -0,0 +1,16 @@
+#version 330 core
+
+in vec2 texCoords;
+
+out vec4 color;
+
+// The blurred bright pixels.
+uniform sampler2D image;
+// The effect's input, before thresholding.
+uniform sampler2D original;
+uniform float intensity;
+
+void main() {
+    vec4 scene = texture(original, texCoords);
+    color = vec4(scene.rgb + texture(image, texCoords).rgb * intensity, scene.a);
+}
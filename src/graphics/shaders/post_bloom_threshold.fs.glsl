@@ -0,0 +1,16 @@
+#version 330 core
+
+in vec2 texCoords;
+
+out vec4 color;
+
+uniform sampler2D image;
+// How bright a pixel has to be to bloom.
+uniform float threshold;
+
+#include "color.glsl"
+
+void main() {
+    vec4 texel = texture(image, texCoords);
+    color = luminance(texel.rgb) > threshold ? texel : vec4(0.0, 0.0, 0.0, texel.a);
+}
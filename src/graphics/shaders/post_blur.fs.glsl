@@ -0,0 +1,25 @@
+#version 330 core
+
+in vec2 texCoords;
+
+out vec4 color;
+
+uniform sampler2D image;
+uniform vec2 resolution;
+// (1, 0) for the horizontal pass, (0, 1) for the vertical pass.
+uniform vec2 direction;
+// How far apart the samples are, in pixels.
+uniform float radius;
+
+// 9-tap Gaussian, using linear filtering to sample two texels at once.
+const float offsets[3] = float[](0.0, 1.3846153846, 3.2307692308);
+const float weights[3] = float[](0.2270270270, 0.3162162162, 0.0702702703);
+
+void main() {
+    vec2 step = direction * radius / resolution;
+    color = texture(image, texCoords) * weights[0];
+    for (int i = 1; i < 3; i++) {
+        color += texture(image, texCoords + step * offsets[i]) * weights[i];
+        color += texture(image, texCoords - step * offsets[i]) * weights[i];
+    }
+}
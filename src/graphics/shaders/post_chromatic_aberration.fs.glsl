@@ -0,0 +1,19 @@
+#version 330 core
+
+in vec2 texCoords;
+
+out vec4 color;
+
+uniform sampler2D image;
+uniform vec2 resolution;
+// How far the red and blue channels are pulled apart at the edges, in pixels.
+uniform float offset;
+
+void main() {
+    vec2 direction = (texCoords - 0.5) * 2.0;
+    vec2 shift = direction * offset / resolution;
+    float red = texture(image, texCoords + shift).r;
+    vec4 green = texture(image, texCoords);
+    float blue = texture(image, texCoords - shift).b;
+    color = vec4(red, green.g, blue, green.a);
+}
@@ -0,0 +1,29 @@
+#version 330 core
+
+in vec2 texCoords;
+
+out vec4 color;
+
+uniform sampler2D image;
+// A lookup table with `lut_size` slices of `lut_size` by `lut_size` pixels laid out side by
+// side, with blue increasing from slice to slice.
+uniform sampler2D lut;
+uniform float lut_size;
+uniform float intensity;
+
+vec3 lookup(vec3 rgb) {
+    float blue = rgb.b * (lut_size - 1.0);
+    float slice = floor(blue);
+    float next_slice = min(slice + 1.0, lut_size - 1.0);
+
+    // Sample texel centers so neighbouring slices don't bleed into each other.
+    vec2 uv = (rgb.rg * (lut_size - 1.0) + 0.5) / vec2(lut_size * lut_size, lut_size);
+    vec3 a = texture(lut, uv + vec2(slice / lut_size, 0.0)).rgb;
+    vec3 b = texture(lut, uv + vec2(next_slice / lut_size, 0.0)).rgb;
+    return mix(a, b, blue - slice);
+}
+
+void main() {
+    vec4 texel = texture(image, texCoords);
+    color = vec4(mix(texel.rgb, lookup(clamp(texel.rgb, 0.0, 1.0)), intensity), texel.a);
+}
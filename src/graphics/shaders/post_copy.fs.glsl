@@ -0,0 +1,12 @@
+#version 330 core
+
+in vec2 texCoords;
+
+out vec4 color;
+
+// The output of the previous pass.
+uniform sampler2D image;
+
+void main() {
+    color = texture(image, texCoords);
+}
@@ -0,0 +1,28 @@
+#version 330 core
+
+in vec2 texCoords;
+
+out vec4 color;
+
+uniform sampler2D image;
+uniform vec2 resolution;
+// How much the screen bulges outwards.
+uniform float curvature;
+// How dark the gaps between scanlines are.
+uniform float scanline_intensity;
+// Scanlines per pixel of screen height.
+uniform float scanline_density;
+
+void main() {
+    vec2 uv = texCoords * 2.0 - 1.0;
+    uv *= 1.0 + curvature * dot(uv.yx, uv.yx);
+    uv = uv * 0.5 + 0.5;
+    if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+        color = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+
+    vec4 texel = texture(image, uv);
+    float scanline = sin(uv.y * resolution.y * scanline_density * 6.28318531) * 0.5 + 0.5;
+    color = vec4(texel.rgb * mix(1.0, scanline, scanline_intensity), texel.a);
+}
@@ -0,0 +1,20 @@
+#version 330 core
+
+in vec2 texCoords;
+
+out vec4 color;
+
+uniform sampler2D image;
+uniform vec2 resolution;
+// Where the darkening starts, as a fraction of the distance to the corners.
+uniform float radius;
+uniform float softness;
+uniform float intensity;
+
+void main() {
+    vec4 texel = texture(image, texCoords);
+    vec2 offset = (texCoords - 0.5) * vec2(resolution.x / resolution.y, 1.0);
+    float distance = length(offset) / length(vec2(resolution.x / resolution.y, 1.0) * 0.5);
+    float vignette = 1.0 - smoothstep(radius - softness, radius, distance);
+    color = vec4(texel.rgb * mix(1.0, vignette, intensity), texel.a);
+}
@@ -0,0 +1,26 @@
+#version 330 core
+
+in vec2 texCoords;
+in vec3 spriteColor;
+
+out vec4 color;
+
+uniform sampler2D image;
+// How much of the sprite has dissolved, from 0 to 1.
+uniform float dissolve;
+// The width of the glowing edge, as a fraction of the noise range.
+uniform float edge_width;
+uniform vec3 edge_color;
+
+float noise(vec2 texel) {
+    return fract(sin(dot(texel, vec2(12.9898, 78.233))) * 43758.5453);
+}
+
+void main() {
+    vec4 texel = vec4(spriteColor, 1.0) * texture(image, texCoords);
+    float value = noise(floor(texCoords * vec2(textureSize(image, 0))));
+    if (value < dissolve) {
+        discard;
+    }
+    color = value < dissolve + edge_width && dissolve > 0.0 ? vec4(edge_color, texel.a) : texel;
+}
@@ -0,0 +1,16 @@
+#version 330 core
+
+in vec2 texCoords;
+in vec3 spriteColor;
+
+out vec4 color;
+
+uniform sampler2D image;
+uniform vec3 flash_color;
+// How far to blend towards `flash_color`, from 0 to 1.
+uniform float flash_amount;
+
+void main() {
+    vec4 texel = vec4(spriteColor, 1.0) * texture(image, texCoords);
+    color = vec4(mix(texel.rgb, flash_color, flash_amount), texel.a);
+}
@@ -0,0 +1,17 @@
+#version 330 core
+
+in vec2 texCoords;
+in vec3 spriteColor;
+
+out vec4 color;
+
+uniform sampler2D image;
+// How gray to make the sprite, from 0 to 1.
+uniform float amount;
+
+#include "color.glsl"
+
+void main() {
+    vec4 texel = vec4(spriteColor, 1.0) * texture(image, texCoords);
+    color = vec4(mix(texel.rgb, vec3(luminance(texel.rgb)), amount), texel.a);
+}
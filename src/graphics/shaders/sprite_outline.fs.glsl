@@ -0,0 +1,26 @@
+#version 330 core
+
+in vec2 texCoords;
+in vec3 spriteColor;
+
+out vec4 color;
+
+uniform sampler2D image;
+uniform vec4 outline_color;
+// The outline thickness, in texels.
+uniform float outline_width;
+
+void main() {
+    vec4 texel = vec4(spriteColor, 1.0) * texture(image, texCoords);
+    vec2 step = outline_width / vec2(textureSize(image, 0));
+
+    float neighbours = max(
+        max(texture(image, texCoords + vec2(step.x, 0.0)).a, texture(image, texCoords - vec2(step.x, 0.0)).a),
+        max(texture(image, texCoords + vec2(0.0, step.y)).a, texture(image, texCoords - vec2(0.0, step.y)).a));
+
+    if (texel.a < 0.5 && neighbours >= 0.5) {
+        color = outline_color;
+    } else {
+        color = texel;
+    }
+}
@@ -2,6 +2,9 @@ use cgmath::{self, Matrix4};
 use cgmath::prelude::*;
 use glium::{self, Surface};
 
+use graphics::camera::Camera2D;
+use graphics::shader::{ShaderError, ShaderPreprocessor, ShaderSource};
+
 
 const VERTEX_SHADER_SRC: &'static str = include_str!("shaders/shape.vs.glsl");
 const FRAGMENT_SHADER_SRC: &'static str = include_str!("shaders/shape.fs.glsl");
@@ -24,21 +27,20 @@ pub struct ShapeRenderer {
 
 impl ShapeRenderer {
     // TODO: Create a builder for ShapeRenderer.
-    pub fn new<F: glium::backend::Facade>(display: &F, projection: Matrix4<f32>) -> Self {
-        // NOTE: By default, assume shaders output sRGB colors.
-        let program_creation_input = glium::program::ProgramCreationInput::SourceCode {
-            vertex_shader: VERTEX_SHADER_SRC,
-            fragment_shader: FRAGMENT_SHADER_SRC,
-            geometry_shader: None,
-            tessellation_control_shader: None,
-            tessellation_evaluation_shader: None,
-            transform_feedback_varyings: None,
-            outputs_srgb: true,
-            uses_point_size: false,
-        };
-        let shader = glium::Program::new(display, program_creation_input).unwrap();
+    pub fn new<F: glium::backend::Facade>(display: &F, projection: Matrix4<f32>) -> Result<Self, ShaderError> {
+        Self::with_preprocessor(display, &ShaderPreprocessor::new(), projection)
+    }
 
-        Self::with_shader(display, shader, projection)
+    /// Build the default shaders with `preprocessor`, e.g. to target a different GLSL version.
+    pub fn with_preprocessor<F: glium::backend::Facade>(display: &F, preprocessor: &ShaderPreprocessor,
+                                                        projection: Matrix4<f32>) -> Result<Self, ShaderError> {
+        let shader = preprocessor.program(
+            display,
+            ShaderSource::new("shape.vs.glsl", VERTEX_SHADER_SRC),
+            ShaderSource::new("shape.fs.glsl", FRAGMENT_SHADER_SRC),
+        )?;
+
+        Ok(Self::with_shader(display, shader, projection))
     }
 
     pub fn with_shader<F: glium::backend::Facade>(display: &F, shader: glium::Program,
@@ -117,4 +119,9 @@ impl ShapeRenderer {
     pub fn get_projection_matrix(&self) -> Matrix4<f32> {
         self.projection_matrix
     }
+
+    /// Draw from the camera's point of view.
+    pub fn set_camera(&mut self, camera: &Camera2D) {
+        self.projection_matrix = camera.combined();
+    }
 }
@@ -1,4 +1,5 @@
-use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::mem;
 use std::rc::Rc;
 use std::thread;
 
@@ -9,7 +10,12 @@ use glium::uniforms::{Sampler, SamplerBehavior};
 pub use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter, SamplerWrapFunction};
 use maybe_owned::MaybeOwned;
 
+use graphics::camera::Camera2D;
+use graphics::material::Material;
+use graphics::shader::{ShaderError, ShaderPreprocessor, ShaderSource};
 use graphics::texture::{TextureRegion, TextureRegionHolder};
+use graphics::uniforms::{Uniform, UniformSet};
+use time::Time;
 
 
 const VERTEX_SHADER_SRC: &'static str = include_str!("shaders/sprite.vs.glsl");
@@ -21,6 +27,9 @@ const BATCH_SIZE: usize = 1024;
 const BATCH_VERTEX_SIZE: usize = QUAD_VERTEX_SIZE * BATCH_SIZE;
 const BATCH_INDEX_SIZE: usize = QUAD_INDEX_SIZE * BATCH_SIZE;
 
+/// The name draw calls without a material are reported under.
+pub const DEFAULT_MATERIAL_NAME: &'static str = "default";
+
 
 #[derive(Clone, Copy)]
 pub struct VertexData {
@@ -68,7 +77,9 @@ pub struct SpriteBatch<'a, 'b, S>
     renderer: &'a mut SpriteRenderer,
     target: &'b mut S,
     draw_params: SpriteDrawParams,
+    uniforms: UniformSet,
     draw_calls: u32,
+    material_draw_calls: HashMap<String, u32>,
     finished: bool,
 }
 
@@ -79,32 +90,79 @@ impl<'a, 'b, S> SpriteBatch<'a, 'b, S>
     // state that will be used for the entire batch.
     fn new(renderer: &'a mut SpriteRenderer, draw_params: SpriteDrawParams, target: &'b mut S) -> Self {
         renderer.sprite_queue.clear();
+        let uniforms = renderer.uniforms.clone();
 
         SpriteBatch {
             renderer: renderer,
             target: target,
             draw_params: draw_params,
+            uniforms: uniforms,
             draw_calls: 0,
+            material_draw_calls: HashMap::new(),
             finished: false,
         }
     }
 
+    /// Queue a sprite, drawn with its own material if it has one.
     pub fn draw(&mut self, sprite: &Sprite) -> Result<(), DrawError> {
+        let material = sprite.material().cloned();
+        self.queue(sprite, material)
+    }
+
+    /// Queue a sprite, drawn with `material` instead of its own.
+    pub fn draw_with_material(&mut self, sprite: &Sprite, material: &Rc<Material>) -> Result<(), DrawError> {
+        self.queue(sprite, Some(material.clone()))
+    }
+
+    fn queue(&mut self, sprite: &Sprite, material: Option<Rc<Material>>) -> Result<(), DrawError> {
         if self.renderer.sprite_queue.len() == BATCH_SIZE {
             self.flush()?;
         }
 
         // Queue the sprite data.
         let vertices = sprite.get_vertex_data();
-        self.renderer.sprite_queue.push(vertices, sprite.rc_texture().clone());
+        self.renderer.sprite_queue.push(vertices, sprite.rc_texture().clone(), material);
 
         Ok(())
     }
 
-    pub fn finish(mut self) -> Result<u32, DrawError> {
+    /// Set a user uniform for the sprites drawn after this. Sprites already queued are drawn
+    /// with the old value first.
+    pub fn set_uniform<U: Into<Uniform>>(&mut self, name: &str, value: U) -> Result<(), DrawError> {
+        let value = value.into();
+        if self.uniforms.get(name) != Some(&value) {
+            self.flush()?;
+            self.uniforms.set(name, value);
+        }
+        Ok(())
+    }
+
+    /// Replace all user uniforms for the sprites drawn after this.
+    pub fn set_uniforms(&mut self, uniforms: UniformSet) -> Result<(), DrawError> {
+        if self.uniforms != uniforms {
+            self.flush()?;
+            self.uniforms = uniforms;
+        }
+        Ok(())
+    }
+
+    pub fn uniforms(&self) -> &UniformSet {
+        &self.uniforms
+    }
+
+    /// Draw all remaining sprites. Returns the total number of draw calls.
+    pub fn finish(self) -> Result<u32, DrawError> {
+        self.finish_with_stats().map(|stats| stats.draw_calls)
+    }
+
+    /// Draw all remaining sprites. Returns the number of draw calls, in total and per material.
+    pub fn finish_with_stats(mut self) -> Result<BatchStats, DrawError> {
         self.flush()?;
         self.finished = true;
-        Ok(self.draw_calls)
+        Ok(BatchStats {
+            draw_calls: self.draw_calls,
+            material_draw_calls: mem::replace(&mut self.material_draw_calls, HashMap::new()),
+        })
     }
 
     fn flush(&mut self) -> Result<(), DrawError> {
@@ -112,17 +170,10 @@ impl<'a, 'b, S> SpriteBatch<'a, 'b, S>
             return Ok(());
         }
 
-        // Build draw parameters for the entire batch.
-        let params = {
-            let blend = if self.draw_params.alpha_blending {
-                glium::Blend::alpha_blending()
-            } else {
-                Default::default()
-            };
-            glium::DrawParameters {
-                blend: blend,
-                .. Default::default()
-            }
+        let time = self.renderer.time;
+        let resolution = {
+            let (width, height) = self.target.get_dimensions();
+            [width as f32, height as f32]
         };
 
         // TODO: If sorting sprites, do so before writing vertex data.
@@ -133,76 +184,83 @@ impl<'a, 'b, S> SpriteBatch<'a, 'b, S>
             vertex_buffer.write(&self.renderer.sprite_queue.vertices);
         }
 
-        // TODO: Flush sprites based on their textures.
-        // TODO: Do a fold, using accumulator as previous texture ID and range? Use it to queue up flushes?
-        // TODO: Return ranges of sprites that all have same texture, use that to flush out each
-        // range.
-
-        let mut render_texture = self.renderer.sprite_queue.textures[0].clone();
-        let mut offset = 0;
-        for (i, texture) in self.renderer.sprite_queue.textures.iter().enumerate().skip(1) {
-            if texture.get_id() != render_texture.get_id() {
-                {
-                    let sampler: Sampler<glium::Texture2d> = glium::uniforms::Sampler(
-                        render_texture.borrow(),
-                        self.draw_params.sampler_behavior,
-                    );
-                    let uniforms = uniform! {
-                        image: sampler,
-                        projectionView: cgmath::conv::array4x4(self.renderer.projection_matrix),
-                    };
-
-                    // Draw the batch.
-                    let (vertex_start, vertex_end) = (offset * QUAD_VERTEX_SIZE, i * QUAD_VERTEX_SIZE);
-                    let vertex_buffer = self.renderer.vertex_buffer.slice(vertex_start..vertex_end)
-                        .expect("Vertex buffer does not contain enough elements!");
-                    let (index_start, index_end) = (offset * QUAD_INDEX_SIZE, i * QUAD_INDEX_SIZE);
-                    let index_buffer = self.renderer.index_buffer.slice(index_start..index_end)
-                        .expect("Index buffer does not contain enough elements!");
-
-                    self.target.draw(vertex_buffer, index_buffer, &self.renderer.shader, &uniforms, &params)?;
-                }
-
-                self.draw_calls += 1;
-
-                offset = i;
-                render_texture = texture.clone();
+        // Draw each run of sprites that share a texture and material in one call.
+        let len = self.renderer.sprite_queue.len();
+        let mut start = 0;
+        while start < len {
+            let mut end = start + 1;
+            while end < len && self.renderer.sprite_queue.can_batch(start, end) {
+                end += 1;
             }
+            self.draw_range(start, end, time, resolution)?;
+            start = end;
         }
 
-        // Draw any final sprites.
-        {
-            let i = self.renderer.sprite_queue.len();
-
-            let sampler: Sampler<glium::Texture2d> = glium::uniforms::Sampler(
-                render_texture.borrow(),
-                self.draw_params.sampler_behavior,
-            );
-            let uniforms = uniform! {
-                image: sampler,
-                projectionView: cgmath::conv::array4x4(self.renderer.projection_matrix),
-            };
+        // All sprites have been flushed, so clear out the queue.
+        self.renderer.sprite_queue.clear();
 
-            // Draw the batch.
-            let (vertex_start, vertex_end) = (offset * QUAD_VERTEX_SIZE, i * QUAD_VERTEX_SIZE);
-            let vertex_buffer = self.renderer.vertex_buffer.slice(vertex_start..vertex_end)
-                .expect("Vertex buffer does not contain enough elements!");
-            let (index_start, index_end) = (offset * QUAD_INDEX_SIZE, i * QUAD_INDEX_SIZE);
-            let index_buffer = self.renderer.index_buffer.slice(index_start..index_end)
-                .expect("Index buffer does not contain enough elements!");
+        Ok(())
+    }
+}
+
+impl<'a, 'b, S> SpriteBatch<'a, 'b, S>
+    where S: Surface
+{
+    fn draw_range(&mut self, start: usize, end: usize, time: f32, resolution: [f32; 2]) -> Result<(), DrawError> {
+        let texture = &self.renderer.sprite_queue.textures[start];
+        let material = self.renderer.sprite_queue.materials[start].as_ref();
+
+        let blend = match material.and_then(|material| material.blend()) {
+            Some(blend) => blend,
+            None if self.draw_params.alpha_blending => glium::Blend::alpha_blending(),
+            None => Default::default(),
+        };
+        let params = glium::DrawParameters {
+            blend: blend,
+            .. Default::default()
+        };
 
-            self.target.draw(vertex_buffer, index_buffer, &self.renderer.shader, &uniforms, &params)?;
+        let sampler: Sampler<glium::Texture2d> = glium::uniforms::Sampler(
+            &**texture,
+            material.and_then(|material| material.sampler()).unwrap_or(self.draw_params.sampler_behavior),
+        );
+        // Material uniforms override the batch's, which override the engine's.
+        let no_uniforms = UniformSet::new();
+        let material_uniforms = material.map(|material| material.uniforms()).unwrap_or(&no_uniforms);
+        let uniforms = material_uniforms.over(self.uniforms.over(uniform! {
+            image: sampler,
+            projectionView: cgmath::conv::array4x4(self.renderer.projection_matrix),
+            time: time,
+            resolution: resolution,
+        }));
+        let shader = material.map(|material| material.shader()).unwrap_or(&self.renderer.shader);
+
+        // Draw the batch.
+        let (vertex_start, vertex_end) = (start * QUAD_VERTEX_SIZE, end * QUAD_VERTEX_SIZE);
+        let vertex_buffer = self.renderer.vertex_buffer.slice(vertex_start..vertex_end)
+            .expect("Vertex buffer does not contain enough elements!");
+        let (index_start, index_end) = (start * QUAD_INDEX_SIZE, end * QUAD_INDEX_SIZE);
+        let index_buffer = self.renderer.index_buffer.slice(index_start..index_end)
+            .expect("Index buffer does not contain enough elements!");
 
-            self.draw_calls += 1;
-        }
+        self.target.draw(vertex_buffer, index_buffer, shader, &uniforms, &params)?;
 
-        // All sprites have been flushed, so clear out the queue.
-        self.renderer.sprite_queue.clear();
+        self.draw_calls += 1;
+        let name = material.map(|material| material.name()).unwrap_or(DEFAULT_MATERIAL_NAME);
+        *self.material_draw_calls.entry(name.into()).or_insert(0) += 1;
 
         Ok(())
     }
 }
 
+/// Draw call counts from a finished `SpriteBatch`.
+#[derive(Clone, Debug, Default)]
+pub struct BatchStats {
+    pub draw_calls: u32,
+    /// Draw calls by material name. Sprites without a material count as `DEFAULT_MATERIAL_NAME`.
+    pub material_draw_calls: HashMap<String, u32>,
+}
+
 impl<'a, 'b, S> Drop for SpriteBatch<'a, 'b, S>
     where S: Surface
 {
@@ -218,6 +276,7 @@ impl<'a, 'b, S> Drop for SpriteBatch<'a, 'b, S>
 pub struct SpriteQueue {
     vertices: Vec<VertexData>,
     textures: Vec<Rc<glium::Texture2d>>,
+    materials: Vec<Option<Rc<Material>>>,
 }
 
 impl SpriteQueue {
@@ -225,19 +284,32 @@ impl SpriteQueue {
         SpriteQueue {
             vertices: Vec::with_capacity(BATCH_VERTEX_SIZE),
             textures: Vec::with_capacity(BATCH_SIZE),
+            materials: Vec::with_capacity(BATCH_SIZE),
         }
     }
 
-    fn push(&mut self, vertices: [VertexData; 4], texture: Rc<glium::Texture2d>) {
+    fn push(&mut self, vertices: [VertexData; 4], texture: Rc<glium::Texture2d>, material: Option<Rc<Material>>) {
         assert!(self.textures.len() < BATCH_SIZE, "Sprite queue is full!");
 
         self.vertices.extend_from_slice(&vertices);
         self.textures.push(texture);
+        self.materials.push(material);
+    }
+
+    /// Whether the sprites at `a` and `b` can be drawn in the same draw call.
+    fn can_batch(&self, a: usize, b: usize) -> bool {
+        let same_material = match (&self.materials[a], &self.materials[b]) {
+            (&None, &None) => true,
+            (&Some(ref a), &Some(ref b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        };
+        same_material && self.textures[a].get_id() == self.textures[b].get_id()
     }
 
     fn clear(&mut self) {
         self.vertices.clear();
         self.textures.clear();
+        self.materials.clear();
     }
 
     fn len(&self) -> usize {
@@ -251,25 +323,26 @@ pub struct SpriteRenderer {
     vertex_buffer: glium::VertexBuffer<VertexData>,
     index_buffer: glium::IndexBuffer<u16>,
     sprite_queue: SpriteQueue,
+    uniforms: UniformSet,
+    time: f32,
 }
 
 impl SpriteRenderer {
     // TODO: Create a builder for SpriteRenderer.
-    pub fn new<F: glium::backend::Facade>(display: &F, projection: Matrix4<f32>) -> Self {
-        // NOTE: By default, assume shaders output sRGB colors.
-        let program_creation_input = glium::program::ProgramCreationInput::SourceCode {
-            vertex_shader: VERTEX_SHADER_SRC,
-            fragment_shader: FRAGMENT_SHADER_SRC,
-            geometry_shader: None,
-            tessellation_control_shader: None,
-            tessellation_evaluation_shader: None,
-            transform_feedback_varyings: None,
-            outputs_srgb: true,
-            uses_point_size: false,
-        };
-        let shader = glium::Program::new(display, program_creation_input).unwrap();
+    pub fn new<F: glium::backend::Facade>(display: &F, projection: Matrix4<f32>) -> Result<Self, ShaderError> {
+        Self::with_preprocessor(display, &ShaderPreprocessor::new(), projection)
+    }
+
+    /// Build the default shaders with `preprocessor`, e.g. to target a different GLSL version.
+    pub fn with_preprocessor<F: glium::backend::Facade>(display: &F, preprocessor: &ShaderPreprocessor,
+                                                        projection: Matrix4<f32>) -> Result<Self, ShaderError> {
+        let shader = preprocessor.program(
+            display,
+            ShaderSource::new("sprite.vs.glsl", VERTEX_SHADER_SRC),
+            ShaderSource::new("sprite.fs.glsl", FRAGMENT_SHADER_SRC),
+        )?;
 
-        Self::with_shader(display, shader, projection)
+        Ok(Self::with_shader(display, shader, projection))
     }
 
     pub fn with_shader<F: glium::backend::Facade>(display: &F, shader: glium::Program,
@@ -301,6 +374,8 @@ impl SpriteRenderer {
             vertex_buffer: vertex_buffer,
             index_buffer: index_buffer,
             sprite_queue: SpriteQueue::new(),
+            uniforms: UniformSet::new(),
+            time: 0.0,
         }
     }
 
@@ -316,20 +391,26 @@ impl SpriteRenderer {
             .expect("Vertex buffer does not contain enough elements!");
         vertex_buffer.write(&vertices);
 
+        let material = sprite.material();
         let sampler: Sampler<glium::Texture2d> = glium::uniforms::Sampler(
             sprite.texture(),
-            draw_params.sampler_behavior,
+            material.and_then(|material| material.sampler()).unwrap_or(draw_params.sampler_behavior),
         );
 
-        let uniforms = uniform! {
+        let (width, height) = target.get_dimensions();
+        let no_uniforms = UniformSet::new();
+        let material_uniforms = material.map(|material| material.uniforms()).unwrap_or(&no_uniforms);
+        let uniforms = material_uniforms.over(self.uniforms.over(uniform! {
             image: sampler,
             projectionView: cgmath::conv::array4x4(self.projection_matrix),
-        };
-
-        let blend = if draw_params.alpha_blending {
-            glium::Blend::alpha_blending()
-        } else {
-            Default::default()
+            time: self.time,
+            resolution: [width as f32, height as f32],
+        }));
+
+        let blend = match material.and_then(|material| material.blend()) {
+            Some(blend) => blend,
+            None if draw_params.alpha_blending => glium::Blend::alpha_blending(),
+            None => Default::default(),
         };
         let params = glium::DrawParameters {
             blend: blend,
@@ -339,7 +420,8 @@ impl SpriteRenderer {
         let index_buffer = self.index_buffer.slice(0..QUAD_INDEX_SIZE)
             .expect("Index buffer does not contain enough elements!");
 
-        target.draw(vertex_buffer, index_buffer, &self.shader, &uniforms, &params).unwrap();
+        let shader = material.map(|material| material.shader()).unwrap_or(&self.shader);
+        target.draw(vertex_buffer, index_buffer, shader, &uniforms, &params).unwrap();
     }
 
     pub fn set_projection_matrix(&mut self, projection: Matrix4<f32>) {
@@ -349,6 +431,36 @@ impl SpriteRenderer {
     pub fn get_projection_matrix(&self) -> Matrix4<f32> {
         self.projection_matrix
     }
+
+    /// Draw from the camera's point of view.
+    pub fn set_camera(&mut self, camera: &Camera2D) {
+        self.projection_matrix = camera.combined();
+    }
+
+    /// Uniforms passed to a custom shader on every draw, on top of `image`, `projectionView`,
+    /// `time` (seconds the renderer has been advanced with `update`) and `resolution` (the target
+    /// size in pixels). Batches start out with these and can change them with `SpriteBatch::set_uniform`.
+    pub fn uniforms(&self) -> &UniformSet {
+        &self.uniforms
+    }
+
+    pub fn uniforms_mut(&mut self) -> &mut UniformSet {
+        &mut self.uniforms
+    }
+
+    pub fn set_uniform<U: Into<Uniform>>(&mut self, name: &str, value: U) {
+        self.uniforms.set(name, value);
+    }
+
+    /// Advance the `time` uniform by this frame's delta time. Skip it while paused to freeze
+    /// animated shaders.
+    pub fn update(&mut self, time: &Time) {
+        self.advance(time.delta_time());
+    }
+
+    pub fn advance(&mut self, dt: f64) {
+        self.time += dt as f32;
+    }
 }
 
 pub struct Sprite<'a> {
@@ -361,6 +473,7 @@ pub struct Sprite<'a> {
     color: Vector3<f32>,
     flip_x: bool,
     flip_y: bool,
+    material: Option<Rc<Material>>,
 }
 
 impl<'a> Sprite<'a> {
@@ -387,6 +500,7 @@ impl<'a> Sprite<'a> {
             color: cgmath::vec3(1.0, 1.0, 1.0),
             flip_x: false,
             flip_y: false,
+            material: None,
         }
     }
 
@@ -457,6 +571,19 @@ impl<'a> Sprite<'a> {
         self.color
     }
 
+    /// Draw this sprite with a material instead of the renderer's shader.
+    pub fn set_material(&mut self, material: Rc<Material>) {
+        self.material = Some(material);
+    }
+
+    pub fn clear_material(&mut self) {
+        self.material = None;
+    }
+
+    pub fn material(&self) -> Option<&Rc<Material>> {
+        self.material.as_ref()
+    }
+
     fn get_vertex_data(&self) -> [VertexData; 4] {
         // Compute model matrix.
         let model = {
@@ -0,0 +1,242 @@
+use std::rc::Rc;
+
+use cgmath::{self, Matrix3, Matrix4, Vector2, Vector3, Vector4};
+use glium;
+use glium::uniforms::{SamplerBehavior, UniformValue, Uniforms};
+
+
+/// The value of a user-defined shader uniform.
+#[derive(Clone)]
+pub enum Uniform {
+    Float(f32),
+    Int(i32),
+    Bool(bool),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+    Mat3([[f32; 3]; 3]),
+    Mat4([[f32; 4]; 4]),
+    Texture(Rc<glium::Texture2d>, SamplerBehavior),
+}
+
+impl Uniform {
+    pub fn as_uniform_value(&self) -> UniformValue {
+        match *self {
+            Uniform::Float(value) => UniformValue::Float(value),
+            Uniform::Int(value) => UniformValue::SignedInt(value),
+            Uniform::Bool(value) => UniformValue::Bool(value),
+            Uniform::Vec2(value) => UniformValue::Vec2(value),
+            Uniform::Vec3(value) => UniformValue::Vec3(value),
+            Uniform::Vec4(value) => UniformValue::Vec4(value),
+            Uniform::Mat3(value) => UniformValue::Mat3(value),
+            Uniform::Mat4(value) => UniformValue::Mat4(value),
+            Uniform::Texture(ref texture, sampler) => UniformValue::Texture2d(&**texture, Some(sampler)),
+        }
+    }
+}
+
+/// Textures are compared by identity rather than contents.
+impl PartialEq for Uniform {
+    fn eq(&self, other: &Uniform) -> bool {
+        match (self, other) {
+            (&Uniform::Float(a), &Uniform::Float(b)) => a == b,
+            (&Uniform::Int(a), &Uniform::Int(b)) => a == b,
+            (&Uniform::Bool(a), &Uniform::Bool(b)) => a == b,
+            (&Uniform::Vec2(a), &Uniform::Vec2(b)) => a == b,
+            (&Uniform::Vec3(a), &Uniform::Vec3(b)) => a == b,
+            (&Uniform::Vec4(a), &Uniform::Vec4(b)) => a == b,
+            (&Uniform::Mat3(a), &Uniform::Mat3(b)) => a == b,
+            (&Uniform::Mat4(a), &Uniform::Mat4(b)) => a == b,
+            (&Uniform::Texture(ref a, a_sampler), &Uniform::Texture(ref b, b_sampler)) =>
+                Rc::ptr_eq(a, b) && a_sampler == b_sampler,
+            _ => false,
+        }
+    }
+}
+
+impl From<f32> for Uniform {
+    fn from(value: f32) -> Self {
+        Uniform::Float(value)
+    }
+}
+
+impl From<i32> for Uniform {
+    fn from(value: i32) -> Self {
+        Uniform::Int(value)
+    }
+}
+
+impl From<bool> for Uniform {
+    fn from(value: bool) -> Self {
+        Uniform::Bool(value)
+    }
+}
+
+impl From<[f32; 2]> for Uniform {
+    fn from(value: [f32; 2]) -> Self {
+        Uniform::Vec2(value)
+    }
+}
+
+impl From<[f32; 3]> for Uniform {
+    fn from(value: [f32; 3]) -> Self {
+        Uniform::Vec3(value)
+    }
+}
+
+impl From<[f32; 4]> for Uniform {
+    fn from(value: [f32; 4]) -> Self {
+        Uniform::Vec4(value)
+    }
+}
+
+impl From<Vector2<f32>> for Uniform {
+    fn from(value: Vector2<f32>) -> Self {
+        Uniform::Vec2(cgmath::conv::array2(value))
+    }
+}
+
+impl From<Vector3<f32>> for Uniform {
+    fn from(value: Vector3<f32>) -> Self {
+        Uniform::Vec3(cgmath::conv::array3(value))
+    }
+}
+
+impl From<Vector4<f32>> for Uniform {
+    fn from(value: Vector4<f32>) -> Self {
+        Uniform::Vec4(cgmath::conv::array4(value))
+    }
+}
+
+impl From<Matrix3<f32>> for Uniform {
+    fn from(value: Matrix3<f32>) -> Self {
+        Uniform::Mat3(cgmath::conv::array3x3(value))
+    }
+}
+
+impl From<Matrix4<f32>> for Uniform {
+    fn from(value: Matrix4<f32>) -> Self {
+        Uniform::Mat4(cgmath::conv::array4x4(value))
+    }
+}
+
+/// Uses linear filtering without mipmaps, clamped to the edges.
+impl From<Rc<glium::Texture2d>> for Uniform {
+    fn from(texture: Rc<glium::Texture2d>) -> Self {
+        Uniform::Texture(texture, clamped_sampler())
+    }
+}
+
+/// A sampler that suits textures without mipmaps, like render targets and lookup tables.
+pub fn clamped_sampler() -> SamplerBehavior {
+    use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter, SamplerWrapFunction};
+
+    SamplerBehavior {
+        wrap_function: (SamplerWrapFunction::Clamp, SamplerWrapFunction::Clamp, SamplerWrapFunction::Clamp),
+        minify_filter: MinifySamplerFilter::Linear,
+        magnify_filter: MagnifySamplerFilter::Linear,
+        .. Default::default()
+    }
+}
+
+/// A set of named uniforms to pass to a custom shader, alongside the ones the engine sets.
+#[derive(Clone, Default, PartialEq)]
+pub struct UniformSet {
+    values: Vec<(String, Uniform)>,
+}
+
+impl UniformSet {
+    pub fn new() -> Self {
+        UniformSet {
+            values: Vec::new(),
+        }
+    }
+
+    pub fn with<U: Into<Uniform>>(mut self, name: &str, value: U) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    /// Set a uniform, replacing any existing value with the same name.
+    pub fn set<U: Into<Uniform>>(&mut self, name: &str, value: U) {
+        let value = value.into();
+        match self.values.iter_mut().find(|entry| entry.0 == name) {
+            Some(entry) => entry.1 = value,
+            None => self.values.push((name.into(), value)),
+        }
+    }
+
+    pub fn set_texture(&mut self, name: &str, texture: Rc<glium::Texture2d>, sampler: SamplerBehavior) {
+        self.set(name, Uniform::Texture(texture, sampler));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Uniform> {
+        self.values.iter().find(|entry| entry.0 == name).map(|entry| &entry.1)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Uniform> {
+        self.values.iter().position(|entry| entry.0 == name)
+            .map(|index| self.values.remove(index).1)
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn iter(&self) -> ::std::slice::Iter<(String, Uniform)> {
+        self.values.iter()
+    }
+
+    /// Combine these uniforms with `base`, e.g. the result of glium's `uniform!`. Values in this
+    /// set are visited last, so they override `base` values with the same name.
+    pub fn over<U: Uniforms>(&self, base: U) -> CombinedUniforms<U> {
+        CombinedUniforms {
+            base: base,
+            user: self,
+        }
+    }
+}
+
+impl Uniforms for UniformSet {
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut output: F) {
+        for &(ref name, ref value) in &self.values {
+            output(name, value.as_uniform_value());
+        }
+    }
+}
+
+/// Engine uniforms followed by a `UniformSet`. See `UniformSet::over`.
+pub struct CombinedUniforms<'u, U> {
+    base: U,
+    user: &'u UniformSet,
+}
+
+impl<'u, U: Uniforms> Uniforms for CombinedUniforms<'u, U> {
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut output: F) {
+        self.base.visit_values(&mut output);
+        self.user.visit_values(output);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Uniform, UniformSet};
+
+    #[test]
+    fn set_replaces_by_name() {
+        let mut uniforms = UniformSet::new().with("strength", 0.5f32).with("tint", [1.0f32, 0.0, 0.0]);
+        uniforms.set("strength", 1.0f32);
+        assert_eq!(uniforms.len(), 2);
+        assert!(uniforms.get("strength") == Some(&Uniform::Float(1.0)));
+        assert!(uniforms.remove("tint").is_some());
+        assert!(uniforms.get("tint").is_none());
+    }
+}
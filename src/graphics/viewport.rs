@@ -0,0 +1,182 @@
+use cgmath::{self, Vector2};
+use glium;
+
+use graphics::camera::Camera2D;
+
+
+/// How a viewport maps its virtual world size onto the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scaling {
+    /// Stretch the world to cover the whole screen, ignoring its aspect ratio.
+    Stretch,
+    /// Scale the world as large as it fits, keeping its aspect ratio. Leaves black bars.
+    Fit,
+    /// Scale the world to cover the whole screen, keeping its aspect ratio. Crops the world.
+    Fill,
+    /// Like `Fit`, but the world grows in one direction to cover the screen instead of leaving
+    /// black bars.
+    Extend,
+    /// One world unit per pixel, whatever the screen size. The world size is ignored.
+    Screen,
+    /// Like `Fit`, but only scaled by whole numbers so pixel art stays crisp.
+    PixelPerfect,
+}
+
+/// Maps a virtual world size onto the screen with a `Scaling` strategy, and keeps a camera
+/// looking at it.
+///
+/// Call `update` when the screen is resized. `Midgar` does this for the viewport set with
+/// `Graphics::set_viewport`. Draw with `screen_rect` as the GL viewport and the camera's
+/// `combined` matrix.
+#[derive(Clone, Debug)]
+pub struct Viewport {
+    scaling: Scaling,
+    world_size: Vector2<f32>,
+    screen_size: (u32, u32),
+    screen_rect: glium::Rect,
+    camera: Camera2D,
+}
+
+impl Viewport {
+    pub fn new(scaling: Scaling, world_width: f32, world_height: f32) -> Self {
+        Viewport {
+            scaling: scaling,
+            world_size: cgmath::vec2(world_width, world_height),
+            screen_size: (0, 0),
+            screen_rect: glium::Rect { left: 0, bottom: 0, width: 0, height: 0 },
+            camera: Camera2D::new(world_width, world_height),
+        }
+    }
+
+    pub fn scaling(&self) -> Scaling {
+        self.scaling
+    }
+
+    pub fn set_scaling(&mut self, scaling: Scaling) {
+        self.scaling = scaling;
+        let (width, height) = self.screen_size;
+        self.update(width, height);
+    }
+
+    /// The virtual size of the world, in world units.
+    pub fn world_size(&self) -> Vector2<f32> {
+        self.world_size
+    }
+
+    pub fn set_world_size(&mut self, world_width: f32, world_height: f32) {
+        self.world_size = cgmath::vec2(world_width, world_height);
+        let (width, height) = self.screen_size;
+        self.update(width, height);
+    }
+
+    pub fn camera(&self) -> &Camera2D {
+        &self.camera
+    }
+
+    pub fn camera_mut(&mut self) -> &mut Camera2D {
+        &mut self.camera
+    }
+
+    /// The area of the screen to draw into, for `glium::DrawParameters::viewport`.
+    pub fn screen_rect(&self) -> glium::Rect {
+        self.screen_rect
+    }
+
+    /// Recompute the screen rectangle and the camera's viewport size for a new screen size. The
+    /// camera keeps its position.
+    pub fn update(&mut self, screen_width: u32, screen_height: u32) {
+        self.screen_size = (screen_width, screen_height);
+        let screen = cgmath::vec2(screen_width as f32, screen_height as f32);
+        let world = self.world_size;
+        let fit_scale = (screen.x / world.x).min(screen.y / world.y);
+
+        let (rect_size, camera_size) = match self.scaling {
+            Scaling::Stretch => (screen, world),
+            Scaling::Fit => (world * fit_scale, world),
+            // Show less of the world rather than drawing outside the screen.
+            Scaling::Fill => {
+                let scale = (screen.x / world.x).max(screen.y / world.y);
+                (screen, screen / scale)
+            },
+            Scaling::Extend => (screen, screen / fit_scale),
+            Scaling::Screen => (screen, screen),
+            Scaling::PixelPerfect => {
+                let scale = fit_scale.floor().max(1.0);
+                (world * scale, world)
+            },
+        };
+
+        let rect_width = (rect_size.x.round() as u32).min(screen_width);
+        let rect_height = (rect_size.y.round() as u32).min(screen_height);
+        self.screen_rect = glium::Rect {
+            left: (screen_width - rect_width) / 2,
+            bottom: (screen_height - rect_height) / 2,
+            width: rect_width,
+            height: rect_height,
+        };
+        self.camera.set_viewport_size(camera_size.x, camera_size.y);
+    }
+
+    /// Move the camera so the bottom-left corner of the world is at the origin.
+    pub fn center_camera(&mut self) {
+        self.camera.set_position(self.world_size * 0.5);
+    }
+
+    /// Convert a window position, with Y pointing down like `Input::mouse_pos`, to world
+    /// coordinates. Takes the black bars around the screen rectangle into account.
+    pub fn screen_to_world(&self, screen: Vector2<f32>) -> Vector2<f32> {
+        let rect = self.screen_rect;
+        let top = (self.screen_size.1 - rect.bottom - rect.height) as f32;
+        let local = cgmath::vec2(screen.x - rect.left as f32, screen.y - top);
+        self.camera.screen_to_world(self.to_camera_units(local))
+    }
+
+    /// Convert a world point to a window position, with Y pointing down.
+    pub fn world_to_screen(&self, world: Vector2<f32>) -> Vector2<f32> {
+        let rect = self.screen_rect;
+        let top = (self.screen_size.1 - rect.bottom - rect.height) as f32;
+        let local = self.camera.world_to_screen(world);
+        let camera_size = self.camera.viewport_size();
+        cgmath::vec2(local.x * rect.width as f32 / camera_size.x + rect.left as f32,
+                     local.y * rect.height as f32 / camera_size.y + top)
+    }
+
+    /// Scale a position in screen rectangle pixels to the camera's viewport units.
+    fn to_camera_units(&self, pixels: Vector2<f32>) -> Vector2<f32> {
+        let rect = self.screen_rect;
+        let camera_size = self.camera.viewport_size();
+        if rect.width == 0 || rect.height == 0 {
+            return pixels;
+        }
+        cgmath::vec2(pixels.x * camera_size.x / rect.width as f32,
+                     pixels.y * camera_size.y / rect.height as f32)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath;
+    use super::{Scaling, Viewport};
+
+    #[test]
+    fn fit_letterboxes_and_unprojects() {
+        let mut viewport = Viewport::new(Scaling::Fit, 320.0, 180.0);
+        viewport.update(1280, 1024);
+        let rect = viewport.screen_rect();
+        assert_eq!((rect.left, rect.bottom, rect.width, rect.height), (0, 152, 1280, 720));
+
+        // The top-left corner of the letterboxed area is the top-left of the world.
+        let world = viewport.screen_to_world(cgmath::vec2(0.0, 152.0));
+        assert!(world.x.abs() < 1e-3 && (world.y - 180.0).abs() < 1e-3);
+        let screen = viewport.world_to_screen(cgmath::vec2(160.0, 90.0));
+        assert!((screen.x - 640.0).abs() < 1e-3 && (screen.y - 512.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn pixel_perfect_uses_whole_scales() {
+        let mut viewport = Viewport::new(Scaling::PixelPerfect, 320.0, 180.0);
+        viewport.update(1000, 700);
+        let rect = viewport.screen_rect();
+        assert_eq!((rect.width, rect.height), (960, 540));
+    }
+}
@@ -0,0 +1,687 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use input::{Axis, Button, HatState, Input, KeyCode, MouseButton, ScanCode};
+use input::joystick::hat_contains;
+
+
+/// How far an axis has to be pushed before an axis binding counts as held.
+const DEFAULT_AXIS_THRESHOLD: f32 = 0.5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AxisDirection {
+    Negative,
+    Positive,
+}
+
+/// A single input that can trigger a named action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    /// A physical key, which stays in the same place on any keyboard layout.
+    ScanCode(ScanCode),
+    MouseButton(MouseButton),
+    ControllerButton(Button),
+    /// A controller axis pushed past the action map's threshold in one direction.
+    ControllerAxis(Axis, AxisDirection),
+    /// A button on any raw joystick, by index.
+    JoystickButton(u8),
+    /// A raw joystick axis pushed past the action map's threshold in one direction.
+    JoystickAxis(u8, AxisDirection),
+    /// A joystick hat pushed `Up`, `Down`, `Left` or `Right`. Diagonals count for both of their
+    /// directions. A diagonal binding like `LeftUp` needs both directions held.
+    JoystickHat(u8, HatState),
+}
+
+/// A source of values for a named axis, in the range -1..1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AxisBinding {
+    Keys { negative: KeyCode, positive: KeyCode },
+    ScanCodes { negative: ScanCode, positive: ScanCode },
+    ControllerButtons { negative: Button, positive: Button },
+    ControllerAxis(Axis),
+    JoystickAxis(u8),
+}
+
+fn axis_past_threshold(value: f32, direction: AxisDirection, threshold: f32) -> bool {
+    match direction {
+        AxisDirection::Negative => value <= -threshold,
+        AxisDirection::Positive => value >= threshold,
+    }
+}
+
+impl Binding {
+    pub fn is_held(&self, input: &Input, threshold: f32) -> bool {
+        match *self {
+            Binding::Key(key) => input.is_key_held(key),
+            Binding::ScanCode(scancode) => input.is_scancode_held(scancode),
+            Binding::MouseButton(button) => input.is_button_held(button),
+            Binding::ControllerButton(button) =>
+                input.controllers().iter().any(|controller| controller.is_button_held(button)),
+            Binding::ControllerAxis(axis, direction) =>
+                input.controllers().iter()
+                    .any(|controller| axis_past_threshold(controller.axis(axis), direction, threshold)),
+            Binding::JoystickButton(button) =>
+                input.joysticks().iter().any(|joystick| joystick.is_button_held(button)),
+            Binding::JoystickAxis(axis, direction) =>
+                input.joysticks().iter()
+                    .any(|joystick| axis_past_threshold(joystick.axis(axis), direction, threshold)),
+            Binding::JoystickHat(hat, direction) =>
+                input.joysticks().iter().any(|joystick| hat_contains(joystick.hat(hat), direction)),
+        }
+    }
+
+    pub fn was_pressed(&self, input: &Input, threshold: f32) -> bool {
+        match *self {
+            Binding::Key(key) => input.was_key_pressed(key),
+            Binding::ScanCode(scancode) => input.was_scancode_pressed(scancode),
+            Binding::MouseButton(button) => input.was_button_pressed(button),
+            Binding::ControllerButton(button) =>
+                input.controllers().iter().any(|controller| controller.was_button_pressed(button)),
+            Binding::ControllerAxis(axis, direction) =>
+                input.controllers().iter().any(|controller| {
+                    axis_past_threshold(controller.axis(axis), direction, threshold) &&
+                        !axis_past_threshold(controller.previous_axis(axis), direction, threshold)
+                }),
+            Binding::JoystickButton(button) =>
+                input.joysticks().iter().any(|joystick| joystick.was_button_pressed(button)),
+            Binding::JoystickAxis(axis, direction) =>
+                input.joysticks().iter().any(|joystick| {
+                    axis_past_threshold(joystick.axis(axis), direction, threshold) &&
+                        !axis_past_threshold(joystick.previous_axis(axis), direction, threshold)
+                }),
+            Binding::JoystickHat(hat, direction) =>
+                input.joysticks().iter().any(|joystick| {
+                    hat_contains(joystick.hat(hat), direction) && !hat_contains(joystick.previous_hat(hat), direction)
+                }),
+        }
+    }
+
+    pub fn was_released(&self, input: &Input, threshold: f32) -> bool {
+        match *self {
+            Binding::Key(key) => input.was_key_released(key),
+            Binding::ScanCode(scancode) => input.was_scancode_released(scancode),
+            Binding::MouseButton(button) => input.was_button_released(button),
+            Binding::ControllerButton(button) =>
+                input.controllers().iter().any(|controller| controller.was_button_released(button)),
+            Binding::ControllerAxis(axis, direction) =>
+                input.controllers().iter().any(|controller| {
+                    !axis_past_threshold(controller.axis(axis), direction, threshold) &&
+                        axis_past_threshold(controller.previous_axis(axis), direction, threshold)
+                }),
+            Binding::JoystickButton(button) =>
+                input.joysticks().iter().any(|joystick| joystick.was_button_released(button)),
+            Binding::JoystickAxis(axis, direction) =>
+                input.joysticks().iter().any(|joystick| {
+                    !axis_past_threshold(joystick.axis(axis), direction, threshold) &&
+                        axis_past_threshold(joystick.previous_axis(axis), direction, threshold)
+                }),
+            Binding::JoystickHat(hat, direction) =>
+                input.joysticks().iter().any(|joystick| {
+                    !hat_contains(joystick.hat(hat), direction) && hat_contains(joystick.previous_hat(hat), direction)
+                }),
+        }
+    }
+
+    /// The value of this binding as a 0..1 button press.
+    pub fn value(&self, input: &Input, threshold: f32) -> f32 {
+        match *self {
+            Binding::ControllerAxis(axis, direction) => {
+                input.controllers().iter()
+                    .map(|controller| {
+                        let value = controller.axis(axis);
+                        match direction {
+                            AxisDirection::Negative => (-value).max(0.0),
+                            AxisDirection::Positive => value.max(0.0),
+                        }
+                    })
+                    .fold(0.0, f32::max)
+            },
+            Binding::JoystickAxis(axis, direction) => {
+                input.joysticks().iter()
+                    .map(|joystick| {
+                        let value = joystick.axis(axis);
+                        match direction {
+                            AxisDirection::Negative => (-value).max(0.0),
+                            AxisDirection::Positive => value.max(0.0),
+                        }
+                    })
+                    .fold(0.0, f32::max)
+            },
+            _ => if self.is_held(input, threshold) { 1.0 } else { 0.0 },
+        }
+    }
+}
+
+impl AxisBinding {
+    pub fn value(&self, input: &Input) -> f32 {
+        let direction_value = |negative: bool, positive: bool| {
+            match (negative, positive) {
+                (true, false) => -1.0,
+                (false, true) => 1.0,
+                _ => 0.0,
+            }
+        };
+
+        match *self {
+            AxisBinding::Keys { negative, positive } =>
+                direction_value(input.is_key_held(negative), input.is_key_held(positive)),
+            AxisBinding::ScanCodes { negative, positive } =>
+                direction_value(input.is_scancode_held(negative), input.is_scancode_held(positive)),
+            AxisBinding::ControllerButtons { negative, positive } => {
+                let controllers = input.controllers();
+                direction_value(controllers.iter().any(|controller| controller.is_button_held(negative)),
+                                controllers.iter().any(|controller| controller.is_button_held(positive)))
+            },
+            AxisBinding::ControllerAxis(axis) => {
+                // Use whichever controller is pushed the furthest.
+                input.controllers().iter()
+                    .map(|controller| controller.axis(axis))
+                    .fold(0.0, |best, value| if value.abs() > best.abs() { value } else { best })
+            },
+            AxisBinding::JoystickAxis(axis) => {
+                input.joysticks().iter()
+                    .map(|joystick| joystick.axis(axis))
+                    .fold(0.0, |best, value| if value.abs() > best.abs() { value } else { best })
+            },
+        }
+    }
+}
+
+/// Maps named actions (like "jump") and named axes (like "move_x") to any mix of keys, mouse
+/// buttons and controller inputs.
+///
+/// Queries mirror `Input`'s: `was_action_pressed`, `is_action_held`, `was_action_released` and
+/// `action_value`/`axis_value`.
+#[derive(Clone, Debug)]
+pub struct ActionMap {
+    actions: HashMap<String, Vec<Binding>>,
+    axes: HashMap<String, Vec<AxisBinding>>,
+    axis_threshold: f32,
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        ActionMap {
+            actions: HashMap::new(),
+            axes: HashMap::new(),
+            axis_threshold: DEFAULT_AXIS_THRESHOLD,
+        }
+    }
+
+    pub fn with_axis_threshold(mut self, threshold: f32) -> Self {
+        self.axis_threshold = threshold;
+        self
+    }
+
+    pub fn axis_threshold(&self) -> f32 {
+        self.axis_threshold
+    }
+
+    // Bindings.
+
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        let bindings = self.actions.entry(action.into()).or_insert_with(Vec::new);
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn unbind(&mut self, action: &str, binding: Binding) {
+        if let Some(bindings) = self.actions.get_mut(action) {
+            bindings.retain(|&b| b != binding);
+        }
+    }
+
+    /// Replace `old` with `new`, keeping its position in the binding list.
+    pub fn rebind(&mut self, action: &str, old: Binding, new: Binding) {
+        let bindings = self.actions.entry(action.into()).or_insert_with(Vec::new);
+        bindings.retain(|&b| b != new);
+        match bindings.iter().position(|&b| b == old) {
+            Some(index) => bindings[index] = new,
+            None => bindings.push(new),
+        }
+    }
+
+    pub fn clear_action(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map(|bindings| bindings.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn actions(&self) -> Vec<&str> {
+        self.actions.keys().map(|action| action.as_str()).collect()
+    }
+
+    pub fn bind_axis(&mut self, axis: &str, binding: AxisBinding) {
+        let bindings = self.axes.entry(axis.into()).or_insert_with(Vec::new);
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn unbind_axis(&mut self, axis: &str, binding: AxisBinding) {
+        if let Some(bindings) = self.axes.get_mut(axis) {
+            bindings.retain(|&b| b != binding);
+        }
+    }
+
+    pub fn clear_axis(&mut self, axis: &str) {
+        self.axes.remove(axis);
+    }
+
+    pub fn axis_bindings(&self, axis: &str) -> &[AxisBinding] {
+        self.axes.get(axis).map(|bindings| bindings.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn axes(&self) -> Vec<&str> {
+        self.axes.keys().map(|axis| axis.as_str()).collect()
+    }
+
+    // Queries.
+
+    pub fn is_action_held(&self, input: &Input, action: &str) -> bool {
+        self.bindings(action).iter()
+            .any(|binding| binding.is_held(input, self.axis_threshold))
+    }
+
+    pub fn was_action_pressed(&self, input: &Input, action: &str) -> bool {
+        self.bindings(action).iter()
+            .any(|binding| binding.was_pressed(input, self.axis_threshold))
+    }
+
+    pub fn was_action_released(&self, input: &Input, action: &str) -> bool {
+        // Only report a release once every binding has let go.
+        self.bindings(action).iter().any(|binding| binding.was_released(input, self.axis_threshold)) &&
+            !self.is_action_held(input, action)
+    }
+
+    /// The strongest value among an action's bindings, from 0 to 1.
+    pub fn action_value(&self, input: &Input, action: &str) -> f32 {
+        self.bindings(action).iter()
+            .map(|binding| binding.value(input, self.axis_threshold))
+            .fold(0.0, f32::max)
+    }
+
+    /// The value of a named axis from -1 to 1, taken from whichever binding is pushed furthest.
+    pub fn axis_value(&self, input: &Input, axis: &str) -> f32 {
+        self.axis_bindings(axis).iter()
+            .map(|binding| binding.value(input))
+            .fold(0.0, |best, value| if value.abs() > best.abs() { value } else { best })
+    }
+
+    /// Find the first binding that was pressed this frame. Useful for "press a key to rebind"
+    /// menus.
+    pub fn capture_binding(&self, input: &Input) -> Option<Binding> {
+        if let Some(&key) = input.pressed_keys.iter().next() {
+            return Some(Binding::Key(key));
+        }
+        if let Some(&button) = input.pressed_buttons.iter().next() {
+            return Some(Binding::MouseButton(button));
+        }
+        for controller in input.controllers() {
+            if let Some(&button) = controller.pressed_buttons.iter().next() {
+                return Some(Binding::ControllerButton(button));
+            }
+            for &axis in controller.axis_positions.keys() {
+                for &direction in &[AxisDirection::Negative, AxisDirection::Positive] {
+                    let binding = Binding::ControllerAxis(axis, direction);
+                    if binding.was_pressed(input, self.axis_threshold) {
+                        return Some(binding);
+                    }
+                }
+            }
+        }
+        for joystick in input.joysticks() {
+            let pressed = (0..joystick.num_buttons()).map(|button| button as u8)
+                .find(|&button| joystick.was_button_pressed(button));
+            if let Some(button) = pressed {
+                return Some(Binding::JoystickButton(button));
+            }
+            for axis in 0..joystick.num_axes() as u8 {
+                for &direction in &[AxisDirection::Negative, AxisDirection::Positive] {
+                    let binding = Binding::JoystickAxis(axis, direction);
+                    if binding.was_pressed(input, self.axis_threshold) {
+                        return Some(binding);
+                    }
+                }
+            }
+            for hat in 0..joystick.num_hats() as u8 {
+                for &direction in &[HatState::Up, HatState::Down, HatState::Left, HatState::Right] {
+                    let binding = Binding::JoystickHat(hat, direction);
+                    if binding.was_pressed(input, self.axis_threshold) {
+                        return Some(binding);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // Serialization.
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        contents.parse()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        File::create(path)?.write_all(self.to_string().as_bytes())
+    }
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        ActionMap::new()
+    }
+}
+
+/// Bindings are serialized to a line-based text format, one binding per line:
+///
+/// ```text
+/// action "jump" key "Space"
+/// action "jump" controller_button "a"
+/// axis "move_x" keys "Left" "Right"
+/// axis "move_y" scancodes "S" "W"
+/// axis "move_x" controller_axis "leftx"
+/// action "fire" joystick_hat "0" "up"
+/// ```
+impl fmt::Display for ActionMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut lines = Vec::new();
+
+        let mut actions: Vec<_> = self.actions.iter().collect();
+        actions.sort_by(|a, b| a.0.cmp(b.0));
+        for (action, bindings) in actions {
+            for binding in bindings {
+                let fields = match *binding {
+                    Binding::Key(key) => vec!["key".into(), key.name()],
+                    Binding::ScanCode(scancode) => vec!["scancode".into(), scancode.name().into()],
+                    Binding::MouseButton(button) => vec!["mouse_button".into(), mouse_button_name(button).into()],
+                    Binding::ControllerButton(button) => vec!["controller_button".into(), button.string()],
+                    Binding::ControllerAxis(axis, direction) =>
+                        vec!["controller_axis".into(), axis.string(), direction_name(direction).into()],
+                    Binding::JoystickButton(button) => vec!["joystick_button".into(), button.to_string()],
+                    Binding::JoystickAxis(axis, direction) =>
+                        vec!["joystick_axis".into(), axis.to_string(), direction_name(direction).into()],
+                    Binding::JoystickHat(hat, direction) =>
+                        vec!["joystick_hat".into(), hat.to_string(), hat_name(direction).into()],
+                };
+                lines.push(format_line("action", action, &fields));
+            }
+        }
+
+        let mut axes: Vec<_> = self.axes.iter().collect();
+        axes.sort_by(|a, b| a.0.cmp(b.0));
+        for (axis, bindings) in axes {
+            for binding in bindings {
+                let fields = match *binding {
+                    AxisBinding::Keys { negative, positive } =>
+                        vec!["keys".into(), negative.name(), positive.name()],
+                    AxisBinding::ScanCodes { negative, positive } =>
+                        vec!["scancodes".into(), negative.name().into(), positive.name().into()],
+                    AxisBinding::ControllerButtons { negative, positive } =>
+                        vec!["controller_buttons".into(), negative.string(), positive.string()],
+                    AxisBinding::ControllerAxis(axis) => vec!["controller_axis".into(), axis.string()],
+                    AxisBinding::JoystickAxis(axis) => vec!["joystick_axis".into(), axis.to_string()],
+                };
+                lines.push(format_line("axis", axis, &fields));
+            }
+        }
+
+        for line in lines {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ActionMap {
+    type Err = io::Error;
+
+    fn from_str(contents: &str) -> io::Result<Self> {
+        let mut map = ActionMap::new();
+
+        for (line_number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: &str| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line_number + 1, message))
+            };
+
+            let tokens = tokenize(line).ok_or_else(|| error("unterminated quote"))?;
+            let tokens: Vec<&str> = tokens.iter().map(|token| token.as_str()).collect();
+            match tokens.as_slice() {
+                ["action", action, "key", key] => {
+                    let key = KeyCode::from_name(key).ok_or_else(|| error("unknown key"))?;
+                    map.bind(action, Binding::Key(key));
+                },
+                ["action", action, "scancode", scancode] => {
+                    let scancode = ScanCode::from_name(scancode).ok_or_else(|| error("unknown scancode"))?;
+                    map.bind(action, Binding::ScanCode(scancode));
+                },
+                ["action", action, "mouse_button", button] => {
+                    let button = mouse_button_from_name(button).ok_or_else(|| error("unknown mouse button"))?;
+                    map.bind(action, Binding::MouseButton(button));
+                },
+                ["action", action, "controller_button", button] => {
+                    let button = Button::from_string(button).ok_or_else(|| error("unknown controller button"))?;
+                    map.bind(action, Binding::ControllerButton(button));
+                },
+                ["action", action, "controller_axis", axis, direction] => {
+                    let axis = Axis::from_string(axis).ok_or_else(|| error("unknown controller axis"))?;
+                    let direction = direction_from_name(direction).ok_or_else(|| error("unknown axis direction"))?;
+                    map.bind(action, Binding::ControllerAxis(axis, direction));
+                },
+                ["action", action, "joystick_button", button] => {
+                    let button = button.parse().map_err(|_| error("invalid joystick button"))?;
+                    map.bind(action, Binding::JoystickButton(button));
+                },
+                ["action", action, "joystick_axis", axis, direction] => {
+                    let axis = axis.parse().map_err(|_| error("invalid joystick axis"))?;
+                    let direction = direction_from_name(direction).ok_or_else(|| error("unknown axis direction"))?;
+                    map.bind(action, Binding::JoystickAxis(axis, direction));
+                },
+                ["action", action, "joystick_hat", hat, direction] => {
+                    let hat = hat.parse().map_err(|_| error("invalid joystick hat"))?;
+                    let direction = hat_from_name(direction).ok_or_else(|| error("unknown hat direction"))?;
+                    map.bind(action, Binding::JoystickHat(hat, direction));
+                },
+                ["axis", axis, "keys", negative, positive] => {
+                    let negative = KeyCode::from_name(negative).ok_or_else(|| error("unknown key"))?;
+                    let positive = KeyCode::from_name(positive).ok_or_else(|| error("unknown key"))?;
+                    map.bind_axis(axis, AxisBinding::Keys { negative: negative, positive: positive });
+                },
+                ["axis", axis, "scancodes", negative, positive] => {
+                    let negative = ScanCode::from_name(negative).ok_or_else(|| error("unknown scancode"))?;
+                    let positive = ScanCode::from_name(positive).ok_or_else(|| error("unknown scancode"))?;
+                    map.bind_axis(axis, AxisBinding::ScanCodes { negative: negative, positive: positive });
+                },
+                ["axis", axis, "controller_buttons", negative, positive] => {
+                    let negative = Button::from_string(negative).ok_or_else(|| error("unknown controller button"))?;
+                    let positive = Button::from_string(positive).ok_or_else(|| error("unknown controller button"))?;
+                    map.bind_axis(axis, AxisBinding::ControllerButtons { negative: negative, positive: positive });
+                },
+                ["axis", axis, "controller_axis", controller_axis] => {
+                    let controller_axis = Axis::from_string(controller_axis).ok_or_else(|| error("unknown controller axis"))?;
+                    map.bind_axis(axis, AxisBinding::ControllerAxis(controller_axis));
+                },
+                ["axis", axis, "joystick_axis", joystick_axis] => {
+                    let joystick_axis = joystick_axis.parse().map_err(|_| error("invalid joystick axis"))?;
+                    map.bind_axis(axis, AxisBinding::JoystickAxis(joystick_axis));
+                },
+                _ => return Err(error("unrecognized binding")),
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+fn mouse_button_name(button: MouseButton) -> &'static str {
+    match button {
+        MouseButton::Left => "left",
+        MouseButton::Middle => "middle",
+        MouseButton::Right => "right",
+        MouseButton::X1 => "x1",
+        MouseButton::X2 => "x2",
+        MouseButton::Unknown => "unknown",
+    }
+}
+
+fn mouse_button_from_name(name: &str) -> Option<MouseButton> {
+    match name {
+        "left" => Some(MouseButton::Left),
+        "middle" => Some(MouseButton::Middle),
+        "right" => Some(MouseButton::Right),
+        "x1" => Some(MouseButton::X1),
+        "x2" => Some(MouseButton::X2),
+        "unknown" => Some(MouseButton::Unknown),
+        _ => None,
+    }
+}
+
+fn direction_name(direction: AxisDirection) -> &'static str {
+    match direction {
+        AxisDirection::Negative => "-",
+        AxisDirection::Positive => "+",
+    }
+}
+
+fn direction_from_name(name: &str) -> Option<AxisDirection> {
+    match name {
+        "-" => Some(AxisDirection::Negative),
+        "+" => Some(AxisDirection::Positive),
+        _ => None,
+    }
+}
+
+fn hat_name(direction: HatState) -> &'static str {
+    match direction {
+        HatState::Centered => "centered",
+        HatState::Up => "up",
+        HatState::Right => "right",
+        HatState::Down => "down",
+        HatState::Left => "left",
+        HatState::RightUp => "rightup",
+        HatState::RightDown => "rightdown",
+        HatState::LeftUp => "leftup",
+        HatState::LeftDown => "leftdown",
+    }
+}
+
+fn hat_from_name(name: &str) -> Option<HatState> {
+    match name {
+        "up" => Some(HatState::Up),
+        "right" => Some(HatState::Right),
+        "down" => Some(HatState::Down),
+        "left" => Some(HatState::Left),
+        "rightup" => Some(HatState::RightUp),
+        "rightdown" => Some(HatState::RightDown),
+        "leftup" => Some(HatState::LeftUp),
+        "leftdown" => Some(HatState::LeftDown),
+        _ => None,
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn format_line(kind: &str, name: &str, fields: &[String]) -> String {
+    let mut line = format!("{} {} {}", kind, quote(name), fields[0]);
+    for field in &fields[1..] {
+        line.push(' ');
+        line.push_str(&quote(field));
+    }
+    line
+}
+
+/// Split a line into whitespace-separated tokens. Tokens may be double-quoted, with backslash
+/// escapes, so key names containing spaces or quotes survive a round trip.
+fn tokenize(line: &str) -> Option<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    loop {
+        while chars.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+            chars.next();
+        }
+        let first = match chars.next() {
+            Some(c) => c,
+            None => break,
+        };
+
+        let mut token = String::new();
+        if first == '"' {
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => token.push(chars.next()?),
+                    Some(c) => token.push(c),
+                    None => return None,
+                }
+            }
+        } else {
+            token.push(first);
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+        }
+        tokens.push(token);
+    }
+
+    Some(tokens)
+}
+
+#[cfg(test)]
+mod test {
+    use input::{Axis, Button, HatState, KeyCode, MouseButton};
+    use super::{ActionMap, AxisBinding, AxisDirection, Binding};
+
+    #[test]
+    fn bindings_round_trip() {
+        let mut map = ActionMap::new();
+        map.bind("jump", Binding::Key(KeyCode::Space));
+        map.bind("jump", Binding::ControllerButton(Button::A));
+        map.bind("fire", Binding::MouseButton(MouseButton::Left));
+        map.bind("fire", Binding::MouseButton(MouseButton::Unknown));
+        map.bind("fire", Binding::ControllerAxis(Axis::TriggerRight, AxisDirection::Positive));
+        map.bind("sprint", Binding::Key(KeyCode::LShift));
+        map.bind_axis("move_x", AxisBinding::Keys { negative: KeyCode::Left, positive: KeyCode::Right });
+        map.bind_axis("move_x", AxisBinding::ControllerAxis(Axis::LeftX));
+        map.bind("fire", Binding::JoystickButton(3));
+        map.bind("sprint", Binding::JoystickHat(0, HatState::Up));
+        map.bind("sprint", Binding::JoystickHat(0, HatState::LeftUp));
+        map.bind_axis("move_x", AxisBinding::JoystickAxis(1));
+
+        let loaded: ActionMap = map.to_string().parse().unwrap();
+        assert_eq!(loaded.bindings("jump"), map.bindings("jump"));
+        assert_eq!(loaded.bindings("fire"), map.bindings("fire"));
+        assert_eq!(loaded.bindings("sprint"), map.bindings("sprint"));
+        assert_eq!(loaded.axis_bindings("move_x"), map.axis_bindings("move_x"));
+    }
+
+    #[test]
+    fn rebind_keeps_order() {
+        let mut map = ActionMap::new();
+        map.bind("jump", Binding::Key(KeyCode::Space));
+        map.bind("jump", Binding::Key(KeyCode::W));
+        map.rebind("jump", Binding::Key(KeyCode::Space), Binding::Key(KeyCode::Up));
+        assert_eq!(map.bindings("jump"), &[Binding::Key(KeyCode::Up), Binding::Key(KeyCode::W)]);
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+
+use input::Axis;
+
+
+const AXIS_MAX: f32 = 32767.0;
+
+/// Convert a raw SDL axis value to the range -1..1.
+pub fn normalize_axis(value: i16) -> f32 {
+    (value as f32 / AXIS_MAX).max(-1.0).min(1.0)
+}
+
+/// Shapes an axis value after the dead zone has been removed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ResponseCurve {
+    Linear,
+    /// Finer control near the center, full speed at the edge.
+    Quadratic,
+    Cubic,
+    Power(f32),
+}
+
+impl ResponseCurve {
+    /// Apply the curve to a magnitude from 0 to 1.
+    pub fn apply(&self, magnitude: f32) -> f32 {
+        match *self {
+            ResponseCurve::Linear => magnitude,
+            ResponseCurve::Quadratic => magnitude * magnitude,
+            ResponseCurve::Cubic => magnitude * magnitude * magnitude,
+            ResponseCurve::Power(exponent) => magnitude.powf(exponent),
+        }
+    }
+}
+
+impl Default for ResponseCurve {
+    fn default() -> Self {
+        ResponseCurve::Linear
+    }
+}
+
+/// A dead zone on a single axis.
+///
+/// Values below `inner` read as 0, values past `outer` saturate to 1, and everything in between
+/// is rescaled to cover the full range before the response curve is applied.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DeadZone {
+    pub inner: f32,
+    pub outer: f32,
+    pub curve: ResponseCurve,
+}
+
+impl DeadZone {
+    pub fn new(inner: f32, outer: f32) -> Self {
+        DeadZone {
+            inner: inner,
+            outer: outer,
+            curve: ResponseCurve::Linear,
+        }
+    }
+
+    /// No dead zone at all.
+    pub fn none() -> Self {
+        DeadZone::new(0.0, 1.0)
+    }
+
+    pub fn with_curve(mut self, curve: ResponseCurve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    /// Rescale a magnitude from 0 to 1.
+    pub fn apply_magnitude(&self, magnitude: f32) -> f32 {
+        if magnitude <= self.inner {
+            return 0.0;
+        }
+        let range = self.outer - self.inner;
+        let scaled = if range > 0.0 {
+            ((magnitude - self.inner) / range).min(1.0)
+        } else {
+            1.0
+        };
+        self.curve.apply(scaled)
+    }
+
+    /// Apply the dead zone to a value from -1 to 1, keeping its sign.
+    pub fn apply(&self, value: f32) -> f32 {
+        let magnitude = self.apply_magnitude(value.abs());
+        if value < 0.0 { -magnitude } else { magnitude }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stick {
+    Left,
+    Right,
+}
+
+impl Stick {
+    pub fn axes(&self) -> (Axis, Axis) {
+        match *self {
+            Stick::Left => (Axis::LeftX, Axis::LeftY),
+            Stick::Right => (Axis::RightX, Axis::RightY),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StickDeadZoneKind {
+    /// Each axis gets its own dead zone. Snaps to the axes, which suits d-pad style movement.
+    Axial,
+    /// The dead zone is a circle around the center, which keeps diagonals smooth.
+    Radial,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StickDeadZone {
+    pub kind: StickDeadZoneKind,
+    pub dead_zone: DeadZone,
+}
+
+impl StickDeadZone {
+    pub fn axial(dead_zone: DeadZone) -> Self {
+        StickDeadZone {
+            kind: StickDeadZoneKind::Axial,
+            dead_zone: dead_zone,
+        }
+    }
+
+    pub fn radial(dead_zone: DeadZone) -> Self {
+        StickDeadZone {
+            kind: StickDeadZoneKind::Radial,
+            dead_zone: dead_zone,
+        }
+    }
+
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        match self.kind {
+            StickDeadZoneKind::Axial => (self.dead_zone.apply(x), self.dead_zone.apply(y)),
+            StickDeadZoneKind::Radial => {
+                let magnitude = (x * x + y * y).sqrt();
+                if magnitude <= 0.0 {
+                    return (0.0, 0.0);
+                }
+                let scaled = self.dead_zone.apply_magnitude(magnitude.min(1.0));
+                (x / magnitude * scaled, y / magnitude * scaled)
+            },
+        }
+    }
+}
+
+/// Turns an analog trigger into a button. `release` should be below `press` so a trigger resting
+/// near the threshold doesn't flicker.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TriggerThreshold {
+    pub press: f32,
+    pub release: f32,
+}
+
+impl TriggerThreshold {
+    pub fn new(press: f32, release: f32) -> Self {
+        TriggerThreshold {
+            press: press,
+            release: release.min(press),
+        }
+    }
+
+    /// Whether the trigger is held, given its value and whether it was held before.
+    pub fn is_held(&self, value: f32, was_held: bool) -> bool {
+        if was_held {
+            value > self.release
+        } else {
+            value >= self.press
+        }
+    }
+}
+
+impl Default for TriggerThreshold {
+    fn default() -> Self {
+        TriggerThreshold::new(0.5, 0.4)
+    }
+}
+
+/// Dead zone settings for a controller.
+#[derive(Clone, Debug)]
+pub struct DeadZones {
+    axes: HashMap<Axis, DeadZone>,
+    left_stick: StickDeadZone,
+    right_stick: StickDeadZone,
+    trigger_threshold: TriggerThreshold,
+}
+
+// Roughly the dead zones XInput recommends.
+const DEFAULT_STICK_INNER: f32 = 0.24;
+const DEFAULT_TRIGGER_INNER: f32 = 0.12;
+const DEFAULT_OUTER: f32 = 0.95;
+
+impl DeadZones {
+    pub fn new() -> Self {
+        let stick = DeadZone::new(DEFAULT_STICK_INNER, DEFAULT_OUTER);
+        let trigger = DeadZone::new(DEFAULT_TRIGGER_INNER, DEFAULT_OUTER);
+
+        let mut axes = HashMap::new();
+        for &axis in &[Axis::LeftX, Axis::LeftY, Axis::RightX, Axis::RightY] {
+            axes.insert(axis, stick);
+        }
+        axes.insert(Axis::TriggerLeft, trigger);
+        axes.insert(Axis::TriggerRight, trigger);
+
+        DeadZones {
+            axes: axes,
+            left_stick: StickDeadZone::radial(stick),
+            right_stick: StickDeadZone::radial(stick),
+            trigger_threshold: TriggerThreshold::default(),
+        }
+    }
+
+    pub fn axis(&self, axis: Axis) -> DeadZone {
+        self.axes.get(&axis).cloned().unwrap_or_else(DeadZone::none)
+    }
+
+    pub fn set_axis(&mut self, axis: Axis, dead_zone: DeadZone) {
+        self.axes.insert(axis, dead_zone);
+    }
+
+    pub fn stick(&self, stick: Stick) -> StickDeadZone {
+        match stick {
+            Stick::Left => self.left_stick,
+            Stick::Right => self.right_stick,
+        }
+    }
+
+    pub fn set_stick(&mut self, stick: Stick, dead_zone: StickDeadZone) {
+        match stick {
+            Stick::Left => self.left_stick = dead_zone,
+            Stick::Right => self.right_stick = dead_zone,
+        }
+    }
+
+    pub fn trigger_threshold(&self) -> TriggerThreshold {
+        self.trigger_threshold
+    }
+
+    pub fn set_trigger_threshold(&mut self, threshold: TriggerThreshold) {
+        self.trigger_threshold = threshold;
+    }
+}
+
+impl Default for DeadZones {
+    fn default() -> Self {
+        DeadZones::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DeadZone, StickDeadZone, TriggerThreshold};
+
+    #[test]
+    fn dead_zone_rescales_between_inner_and_outer() {
+        let dead_zone = DeadZone::new(0.2, 0.8);
+        assert_eq!(dead_zone.apply(0.1), 0.0);
+        assert_eq!(dead_zone.apply(-0.5), -0.5);
+        assert_eq!(dead_zone.apply(0.9), 1.0);
+    }
+
+    #[test]
+    fn radial_dead_zone_keeps_direction() {
+        let stick = StickDeadZone::radial(DeadZone::new(0.2, 1.0));
+        assert_eq!(stick.apply(0.1, 0.1), (0.0, 0.0));
+        let (x, y) = stick.apply(0.6, 0.0);
+        assert!((x - 0.5).abs() < 1e-6 && y == 0.0);
+    }
+
+    #[test]
+    fn trigger_threshold_has_hysteresis() {
+        let threshold = TriggerThreshold::new(0.5, 0.3);
+        assert!(!threshold.is_held(0.4, false));
+        assert!(threshold.is_held(0.4, true));
+        assert!(!threshold.is_held(0.2, true));
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+
+use input::{Axis, Binding, Button, HatState, Input, KeyCode, MouseButton, ScanCode, TouchEvent};
+
+
+/// A single change in input state, in the order it happened.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputEvent {
+    KeyDown { keycode: Option<KeyCode>, scancode: Option<ScanCode> },
+    KeyUp { keycode: Option<KeyCode>, scancode: Option<ScanCode> },
+    KeyRepeat { keycode: Option<KeyCode>, scancode: Option<ScanCode> },
+    Text(String),
+    MouseButtonDown { button: MouseButton, clicks: u8, x: i32, y: i32 },
+    MouseButtonUp { button: MouseButton, x: i32, y: i32 },
+    MouseMotion { x: i32, y: i32 },
+    MouseWheel { x: f32, y: f32 },
+    Touch(TouchEvent),
+    ControllerButtonDown { controller: u32, button: Button },
+    ControllerButtonUp { controller: u32, button: Button },
+    ControllerAxis { controller: u32, axis: Axis, value: i16 },
+    ControllerAdded { controller: u32 },
+    ControllerRemoved { controller: u32 },
+    JoystickAdded { joystick: u32 },
+    JoystickRemoved { joystick: u32 },
+    JoystickButtonDown { joystick: u32, button: u8 },
+    JoystickButtonUp { joystick: u32, button: u8 },
+    JoystickAxis { joystick: u32, axis: u8, value: i16 },
+    JoystickHat { joystick: u32, hat: u8, state: HatState },
+    JoystickBall { joystick: u32, ball: u8, xrel: i16, yrel: i16 },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimedEvent {
+    /// SDL's event timestamp, in milliseconds since SDL was initialized.
+    pub timestamp: u32,
+    /// The frame the event was delivered on.
+    pub frame: u64,
+    pub event: InputEvent,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Neutral,
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    /// Build a direction from -1, 0 or 1 on each axis. Positive `y` is up.
+    pub fn from_axes(x: i32, y: i32) -> Self {
+        match (x.signum(), y.signum()) {
+            (0, 1) => Direction::Up,
+            (0, -1) => Direction::Down,
+            (-1, 0) => Direction::Left,
+            (1, 0) => Direction::Right,
+            (-1, 1) => Direction::UpLeft,
+            (1, 1) => Direction::UpRight,
+            (-1, -1) => Direction::DownLeft,
+            (1, -1) => Direction::DownRight,
+            _ => Direction::Neutral,
+        }
+    }
+
+    /// Swap left and right, for characters facing the other way.
+    pub fn mirrored(&self) -> Self {
+        match *self {
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::UpLeft => Direction::UpRight,
+            Direction::UpRight => Direction::UpLeft,
+            Direction::DownLeft => Direction::DownRight,
+            Direction::DownRight => Direction::DownLeft,
+            direction => direction,
+        }
+    }
+}
+
+/// Something a combo step can require.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ComboInput {
+    Direction(Direction),
+    Key(KeyCode),
+    MouseButton(MouseButton),
+    ControllerButton(Button),
+}
+
+impl ComboInput {
+    fn mirrored(&self) -> Self {
+        match *self {
+            ComboInput::Direction(direction) => ComboInput::Direction(direction.mirrored()),
+            input => input,
+        }
+    }
+}
+
+/// A sequence of steps, where each step is one or more inputs pressed together.
+///
+/// Combos are written facing right, so "forward" is `Direction::Right`. Use `mirrored` for
+/// characters facing left.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Combo {
+    steps: Vec<Vec<ComboInput>>,
+    /// The most frames allowed between one step and the next.
+    pub step_window: u64,
+    /// The most frames allowed between the inputs of a single step.
+    pub simultaneous_window: u64,
+}
+
+const DEFAULT_STEP_WINDOW: u64 = 10;
+const DEFAULT_SIMULTANEOUS_WINDOW: u64 = 3;
+
+impl Combo {
+    pub fn new() -> Self {
+        Combo {
+            steps: Vec::new(),
+            step_window: DEFAULT_STEP_WINDOW,
+            simultaneous_window: DEFAULT_SIMULTANEOUS_WINDOW,
+        }
+    }
+
+    pub fn then(mut self, input: ComboInput) -> Self {
+        self.steps.push(vec![input]);
+        self
+    }
+
+    pub fn then_together(mut self, inputs: &[ComboInput]) -> Self {
+        self.steps.push(inputs.to_vec());
+        self
+    }
+
+    pub fn with_step_window(mut self, frames: u64) -> Self {
+        self.step_window = frames;
+        self
+    }
+
+    pub fn with_simultaneous_window(mut self, frames: u64) -> Self {
+        self.simultaneous_window = frames;
+        self
+    }
+
+    pub fn mirrored(&self) -> Self {
+        Combo {
+            steps: self.steps.iter()
+                .map(|step| step.iter().map(ComboInput::mirrored).collect())
+                .collect(),
+            step_window: self.step_window,
+            simultaneous_window: self.simultaneous_window,
+        }
+    }
+
+    pub fn steps(&self) -> &[Vec<ComboInput>] {
+        &self.steps
+    }
+
+    /// Whether the combo's last step completed on `frame`, given buffered `(frame, input)`
+    /// pairs in the order they happened. Unrelated inputs in between are ignored.
+    pub fn matches(&self, buffer: &[(u64, ComboInput)], frame: u64) -> bool {
+        let mut step_index = match self.steps.len() {
+            0 => return false,
+            len => len - 1,
+        };
+        let mut needed = self.steps[step_index].clone();
+        let mut step_end: Option<u64> = None;
+        let mut next_step_start = frame;
+
+        for &(entry_frame, input) in buffer.iter().rev() {
+            match step_end {
+                // The last step has to finish on this frame, so the combo only fires once.
+                None if step_index == self.steps.len() - 1 && entry_frame != frame => return false,
+                None if next_step_start.saturating_sub(entry_frame) > self.step_window => return false,
+                Some(end) if end.saturating_sub(entry_frame) > self.simultaneous_window => return false,
+                _ => {},
+            }
+
+            if let Some(position) = needed.iter().position(|&needed_input| needed_input == input) {
+                needed.remove(position);
+                if step_end.is_none() {
+                    step_end = Some(entry_frame);
+                }
+                if needed.is_empty() {
+                    if step_index == 0 {
+                        return true;
+                    }
+                    step_index -= 1;
+                    needed = self.steps[step_index].clone();
+                    step_end = None;
+                    next_step_start = entry_frame;
+                }
+            }
+        }
+        false
+    }
+}
+
+/// The bindings that make up the up/down/left/right directions for combos.
+#[derive(Clone, Debug)]
+pub struct DirectionBindings {
+    pub up: Vec<Binding>,
+    pub down: Vec<Binding>,
+    pub left: Vec<Binding>,
+    pub right: Vec<Binding>,
+    pub axis_threshold: f32,
+}
+
+impl DirectionBindings {
+    pub fn direction(&self, input: &Input) -> Direction {
+        let held = |bindings: &[Binding]| bindings.iter().any(|binding| binding.is_held(input, self.axis_threshold));
+        let x = held(&self.right) as i32 - held(&self.left) as i32;
+        let y = held(&self.up) as i32 - held(&self.down) as i32;
+        Direction::from_axes(x, y)
+    }
+}
+
+impl Default for DirectionBindings {
+    /// Arrow keys, the d-pad and the left stick.
+    fn default() -> Self {
+        use input::AxisDirection::{Negative, Positive};
+
+        DirectionBindings {
+            up: vec![Binding::Key(KeyCode::Up), Binding::ControllerButton(Button::DPadUp),
+                     Binding::ControllerAxis(Axis::LeftY, Negative)],
+            down: vec![Binding::Key(KeyCode::Down), Binding::ControllerButton(Button::DPadDown),
+                       Binding::ControllerAxis(Axis::LeftY, Positive)],
+            left: vec![Binding::Key(KeyCode::Left), Binding::ControllerButton(Button::DPadLeft),
+                       Binding::ControllerAxis(Axis::LeftX, Negative)],
+            right: vec![Binding::Key(KeyCode::Right), Binding::ControllerButton(Button::DPadRight),
+                        Binding::ControllerAxis(Axis::LeftX, Positive)],
+            axis_threshold: 0.5,
+        }
+    }
+}
+
+/// Buffers recent inputs and detects motion inputs and combos, like a fighting game's input
+/// buffer.
+pub struct ComboMatcher {
+    combos: HashMap<String, Combo>,
+    directions: DirectionBindings,
+    direction: Direction,
+    buffer: Vec<(u64, ComboInput)>,
+    buffer_frames: u64,
+}
+
+impl ComboMatcher {
+    pub fn new() -> Self {
+        ComboMatcher {
+            combos: HashMap::new(),
+            directions: DirectionBindings::default(),
+            direction: Direction::Neutral,
+            buffer: Vec::new(),
+            buffer_frames: 60,
+        }
+    }
+
+    pub fn with_directions(mut self, directions: DirectionBindings) -> Self {
+        self.directions = directions;
+        self
+    }
+
+    /// How many frames of input to keep around.
+    pub fn with_buffer_frames(mut self, frames: u64) -> Self {
+        self.buffer_frames = frames;
+        self
+    }
+
+    pub fn add_combo(&mut self, name: &str, combo: Combo) {
+        self.combos.insert(name.into(), combo);
+    }
+
+    pub fn remove_combo(&mut self, name: &str) {
+        self.combos.remove(name);
+    }
+
+    pub fn clear_buffer(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// Buffer this frame's input and return the names of the combos that completed on it.
+    pub fn update(&mut self, input: &Input) -> Vec<String> {
+        let frame = input.frame();
+        for timed_event in input.events() {
+            let combo_input = match timed_event.event {
+                InputEvent::KeyDown { keycode: Some(keycode), .. } => ComboInput::Key(keycode),
+                InputEvent::MouseButtonDown { button, .. } => ComboInput::MouseButton(button),
+                InputEvent::ControllerButtonDown { button, .. } => ComboInput::ControllerButton(button),
+                _ => continue,
+            };
+            self.push(frame, combo_input);
+        }
+
+        let direction = self.directions.direction(input);
+        if direction != self.direction {
+            self.direction = direction;
+            self.push(frame, ComboInput::Direction(direction));
+        }
+
+        self.completed(frame)
+    }
+
+    /// Buffer an input by hand.
+    pub fn push(&mut self, frame: u64, input: ComboInput) {
+        self.buffer.push((frame, input));
+
+        let buffer_frames = self.buffer_frames;
+        self.buffer.retain(|&(entry_frame, _)| frame.saturating_sub(entry_frame) <= buffer_frames);
+    }
+
+    /// The names of the combos whose last step completed on `frame`.
+    pub fn completed(&self, frame: u64) -> Vec<String> {
+        let mut names: Vec<String> = self.combos.iter()
+            .filter(|&(_, combo)| combo.matches(&self.buffer, frame))
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+impl Default for ComboMatcher {
+    fn default() -> Self {
+        ComboMatcher::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use input::Button;
+    use super::{Combo, ComboInput, ComboMatcher, Direction};
+
+    fn quarter_circle_punch() -> Combo {
+        Combo::new()
+            .then(ComboInput::Direction(Direction::Down))
+            .then(ComboInput::Direction(Direction::DownRight))
+            .then_together(&[ComboInput::Direction(Direction::Right), ComboInput::ControllerButton(Button::X)])
+    }
+
+    #[test]
+    fn detects_motion_input() {
+        let mut matcher = ComboMatcher::new();
+        matcher.add_combo("fireball", quarter_circle_punch());
+
+        matcher.push(10, ComboInput::Direction(Direction::Down));
+        matcher.push(13, ComboInput::Direction(Direction::DownRight));
+        matcher.push(16, ComboInput::Direction(Direction::Right));
+        assert!(matcher.completed(16).is_empty());
+        matcher.push(17, ComboInput::ControllerButton(Button::X));
+        assert_eq!(matcher.completed(17), vec!["fireball".to_string()]);
+    }
+
+    #[test]
+    fn slow_inputs_and_mirroring() {
+        let mut matcher = ComboMatcher::new();
+        matcher.add_combo("fireball", quarter_circle_punch().mirrored());
+
+        matcher.push(0, ComboInput::Direction(Direction::Down));
+        matcher.push(30, ComboInput::Direction(Direction::DownLeft));
+        matcher.push(31, ComboInput::Direction(Direction::Left));
+        matcher.push(31, ComboInput::ControllerButton(Button::X));
+        assert!(matcher.completed(31).is_empty());
+
+        matcher.push(32, ComboInput::Direction(Direction::Down));
+        matcher.push(34, ComboInput::Direction(Direction::DownLeft));
+        matcher.push(36, ComboInput::Direction(Direction::Left));
+        matcher.push(36, ComboInput::ControllerButton(Button::X));
+        assert_eq!(matcher.completed(36).len(), 1);
+    }
+}
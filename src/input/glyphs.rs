@@ -0,0 +1,216 @@
+use input::{Axis, Button, InputEvent};
+use input::deadzone::normalize_axis;
+
+
+// USB vendor IDs of the first-party controller makers.
+const MICROSOFT_VENDOR_ID: u16 = 0x045e;
+const SONY_VENDOR_ID: u16 = 0x054c;
+const NINTENDO_VENDOR_ID: u16 = 0x057e;
+
+/// How far a stick or trigger has to move before it counts as using the controller. Keeps a
+/// drifting stick from stealing prompts away from the keyboard.
+const ACTIVE_AXIS_THRESHOLD: f32 = 0.5;
+
+/// The family a controller belongs to, which decides how its buttons are labelled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ControllerFamily {
+    Xbox,
+    PlayStation,
+    Switch,
+    /// Anything else. Uses Xbox-style labels, since that's the layout SDL's mappings follow.
+    Generic,
+}
+
+impl ControllerFamily {
+    /// Detect the family from the vendor ID in the controller's GUID, falling back to its name.
+    ///
+    /// The SDL version we use doesn't report the controller type itself, so this is the same
+    /// guesswork SDL does internally.
+    pub fn detect(guid: &str, name: &str) -> Self {
+        // XInput devices on Windows don't have a vendor ID, but are always Xbox style pads.
+        if guid.starts_with("78696e707574") {
+            return ControllerFamily::Xbox;
+        }
+
+        match guid_vendor_id(guid) {
+            Some(MICROSOFT_VENDOR_ID) => return ControllerFamily::Xbox,
+            Some(SONY_VENDOR_ID) => return ControllerFamily::PlayStation,
+            Some(NINTENDO_VENDOR_ID) => return ControllerFamily::Switch,
+            _ => {},
+        }
+
+        let name = name.to_lowercase();
+        let name_has = |words: &[&str]| words.iter().any(|word| name.contains(word));
+        if name_has(&["xbox", "xinput", "x-box"]) {
+            ControllerFamily::Xbox
+        } else if name_has(&["playstation", "ps3", "ps4", "ps5", "dualshock", "dualsense"]) {
+            ControllerFamily::PlayStation
+        } else if name_has(&["nintendo", "switch", "joy-con"]) {
+            ControllerFamily::Switch
+        } else {
+            ControllerFamily::Generic
+        }
+    }
+
+    fn icon_prefix(&self) -> &'static str {
+        match *self {
+            ControllerFamily::Xbox => "xbox",
+            ControllerFamily::PlayStation => "ps",
+            ControllerFamily::Switch => "switch",
+            ControllerFamily::Generic => "generic",
+        }
+    }
+
+    /// The label and icon for a button on this family's controllers.
+    ///
+    /// Buttons are named by position, so `Button::A` is always the bottom face button. On
+    /// Switch pads that's the button labelled "B".
+    pub fn button_glyph(&self, button: Button) -> Glyph {
+        let (name, icon) = match (*self, button) {
+            (ControllerFamily::PlayStation, Button::A) => ("Cross", "cross"),
+            (ControllerFamily::PlayStation, Button::B) => ("Circle", "circle"),
+            (ControllerFamily::PlayStation, Button::X) => ("Square", "square"),
+            (ControllerFamily::PlayStation, Button::Y) => ("Triangle", "triangle"),
+            (ControllerFamily::PlayStation, Button::Back) => ("Share", "share"),
+            (ControllerFamily::PlayStation, Button::Guide) => ("PS", "ps"),
+            (ControllerFamily::PlayStation, Button::Start) => ("Options", "options"),
+            (ControllerFamily::PlayStation, Button::LeftStick) => ("L3", "l3"),
+            (ControllerFamily::PlayStation, Button::RightStick) => ("R3", "r3"),
+            (ControllerFamily::PlayStation, Button::LeftShoulder) => ("L1", "l1"),
+            (ControllerFamily::PlayStation, Button::RightShoulder) => ("R1", "r1"),
+
+            (ControllerFamily::Switch, Button::A) => ("B", "b"),
+            (ControllerFamily::Switch, Button::B) => ("A", "a"),
+            (ControllerFamily::Switch, Button::X) => ("Y", "y"),
+            (ControllerFamily::Switch, Button::Y) => ("X", "x"),
+            (ControllerFamily::Switch, Button::Back) => ("-", "minus"),
+            (ControllerFamily::Switch, Button::Guide) => ("Home", "home"),
+            (ControllerFamily::Switch, Button::Start) => ("+", "plus"),
+            (ControllerFamily::Switch, Button::LeftShoulder) => ("L", "l"),
+            (ControllerFamily::Switch, Button::RightShoulder) => ("R", "r"),
+
+            (_, Button::A) => ("A", "a"),
+            (_, Button::B) => ("B", "b"),
+            (_, Button::X) => ("X", "x"),
+            (_, Button::Y) => ("Y", "y"),
+            (_, Button::Back) => ("View", "view"),
+            (_, Button::Guide) => ("Guide", "guide"),
+            (_, Button::Start) => ("Menu", "menu"),
+            (_, Button::LeftShoulder) => ("LB", "lb"),
+            (_, Button::RightShoulder) => ("RB", "rb"),
+            (_, Button::LeftStick) => ("LS", "ls"),
+            (_, Button::RightStick) => ("RS", "rs"),
+            (_, Button::DPadUp) => ("D-Pad Up", "dpad_up"),
+            (_, Button::DPadDown) => ("D-Pad Down", "dpad_down"),
+            (_, Button::DPadLeft) => ("D-Pad Left", "dpad_left"),
+            (_, Button::DPadRight) => ("D-Pad Right", "dpad_right"),
+        };
+        Glyph::new(*self, name, icon)
+    }
+
+    /// The label and icon for an axis on this family's controllers.
+    pub fn axis_glyph(&self, axis: Axis) -> Glyph {
+        let (name, icon) = match (*self, axis) {
+            (ControllerFamily::PlayStation, Axis::TriggerLeft) => ("L2", "l2"),
+            (ControllerFamily::PlayStation, Axis::TriggerRight) => ("R2", "r2"),
+            (ControllerFamily::Switch, Axis::TriggerLeft) => ("ZL", "zl"),
+            (ControllerFamily::Switch, Axis::TriggerRight) => ("ZR", "zr"),
+            (_, Axis::TriggerLeft) => ("LT", "lt"),
+            (_, Axis::TriggerRight) => ("RT", "rt"),
+            (_, Axis::LeftX) | (_, Axis::LeftY) => ("Left Stick", "left_stick"),
+            (_, Axis::RightX) | (_, Axis::RightY) => ("Right Stick", "right_stick"),
+        };
+        Glyph::new(*self, name, icon)
+    }
+}
+
+/// What to show the player for a controller input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Glyph {
+    /// The text printed on the button, e.g. "Cross" or "RB".
+    pub name: &'static str,
+    /// An ID to look up an icon with, e.g. "ps_cross" or "xbox_rb".
+    pub icon: String,
+}
+
+impl Glyph {
+    fn new(family: ControllerFamily, name: &'static str, icon: &str) -> Self {
+        Glyph {
+            name: name,
+            icon: format!("{}_{}", family.icon_prefix(), icon),
+        }
+    }
+}
+
+/// The USB vendor ID stored in an SDL joystick GUID, if it has one.
+fn guid_vendor_id(guid: &str) -> Option<u16> {
+    // Bytes 4 and 5 of the GUID hold the vendor ID, little-endian.
+    if guid.len() < 12 {
+        return None;
+    }
+    let low = u16::from_str_radix(&guid[8..10], 16).ok()?;
+    let high = u16::from_str_radix(&guid[10..12], 16).ok()?;
+    match low | (high << 8) {
+        0 => None,
+        vendor => Some(vendor),
+    }
+}
+
+/// The kind of device the player last used, so prompts can follow whatever they picked up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InputDevice {
+    KeyboardMouse,
+    Controller(u32),
+    Joystick(u32),
+    Touch,
+}
+
+impl InputDevice {
+    /// The device that produced `event`, if it shows the player is actively using it.
+    pub fn from_event(event: &InputEvent) -> Option<Self> {
+        match *event {
+            InputEvent::KeyDown { .. } | InputEvent::Text(_) | InputEvent::MouseButtonDown { .. } |
+            InputEvent::MouseMotion { .. } | InputEvent::MouseWheel { .. } =>
+                Some(InputDevice::KeyboardMouse),
+            InputEvent::Touch(_) => Some(InputDevice::Touch),
+            InputEvent::ControllerButtonDown { controller, .. } => Some(InputDevice::Controller(controller)),
+            InputEvent::ControllerAxis { controller, value, .. }
+                if normalize_axis(value).abs() >= ACTIVE_AXIS_THRESHOLD =>
+                Some(InputDevice::Controller(controller)),
+            InputEvent::JoystickButtonDown { joystick, .. } | InputEvent::JoystickHat { joystick, .. } |
+            InputEvent::JoystickBall { joystick, .. } => Some(InputDevice::Joystick(joystick)),
+            InputEvent::JoystickAxis { joystick, value, .. }
+                if normalize_axis(value).abs() >= ACTIVE_AXIS_THRESHOLD =>
+                Some(InputDevice::Joystick(joystick)),
+            _ => None,
+        }
+    }
+
+    /// Whether this is a controller or joystick rather than keyboard, mouse or touch.
+    pub fn is_gamepad(&self) -> bool {
+        match *self {
+            InputDevice::Controller(_) | InputDevice::Joystick(_) => true,
+            InputDevice::KeyboardMouse | InputDevice::Touch => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use input::Button;
+    use super::ControllerFamily;
+
+    #[test]
+    fn detects_family() {
+        assert_eq!(ControllerFamily::detect("030000004c050000c405000000010000", ""),
+                   ControllerFamily::PlayStation);
+        assert_eq!(ControllerFamily::detect("030000005e0400008e02000014010000", ""), ControllerFamily::Xbox);
+        assert_eq!(ControllerFamily::detect("03000000000000000000000000000000", "Pro Controller (Nintendo Switch)"),
+                   ControllerFamily::Switch);
+        assert_eq!(ControllerFamily::detect("", "Generic USB Pad"), ControllerFamily::Generic);
+
+        let glyph = ControllerFamily::PlayStation.button_glyph(Button::A);
+        assert_eq!(glyph.name, "Cross");
+        assert_eq!(glyph.icon, "ps_cross");
+    }
+}
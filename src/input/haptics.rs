@@ -0,0 +1,401 @@
+use std::cell::RefCell;
+use std::os::raw::c_int;
+use std::rc::Rc;
+
+use sdl2::HapticSubsystem;
+use sdl2::haptic::Haptic;
+use sdl2::sys::{haptic as sys_haptic, joystick as sys_joystick};
+
+use time::Time;
+
+
+/// Which haptic features a device supports. Requests for unsupported features are ignored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HapticCapabilities {
+    pub rumble: bool,
+    pub trigger_rumble: bool,
+    pub led: bool,
+}
+
+/// A low-level haptic output device.
+pub trait HapticDevice {
+    fn capabilities(&self) -> HapticCapabilities;
+
+    /// Rumble the low- and high-frequency motors, with intensities from 0 to 1.
+    fn rumble(&mut self, low_frequency: f32, high_frequency: f32, duration_ms: u32);
+
+    /// Rumble the motors in the triggers. Does nothing unless `capabilities().trigger_rumble`.
+    fn rumble_triggers(&mut self, _left: f32, _right: f32, _duration_ms: u32) {}
+
+    /// Set the color of the device's LED. Does nothing unless `capabilities().led`.
+    fn set_led(&mut self, _red: u8, _green: u8, _blue: u8) {}
+
+    fn stop(&mut self);
+}
+
+/// A device with no haptic support at all.
+pub struct NullHaptic;
+
+impl HapticDevice for NullHaptic {
+    fn capabilities(&self) -> HapticCapabilities {
+        Default::default()
+    }
+
+    fn rumble(&mut self, _low_frequency: f32, _high_frequency: f32, _duration_ms: u32) {}
+
+    fn stop(&mut self) {}
+}
+
+/// Rumble through SDL's haptic subsystem.
+///
+/// SDL's simple rumble API only has a single intensity, so the stronger of the two motors is
+/// used. Trigger rumble and LEDs aren't available through it, so those requests are no-ops.
+pub struct SdlHaptic {
+    haptic: Haptic,
+    capabilities: HapticCapabilities,
+}
+
+impl SdlHaptic {
+    /// Open the haptic device of the joystick at `joystick_index` and check what it supports.
+    pub fn open(haptic_subsystem: &HapticSubsystem, joystick_index: u32) -> Result<Self, String> {
+        let haptic = haptic_subsystem.open_from_joystick_id(joystick_index)
+            .map_err(|error| error.to_string())?;
+        Ok(SdlHaptic {
+            haptic: haptic,
+            capabilities: HapticCapabilities {
+                rumble: rumble_supported(joystick_index),
+                trigger_rumble: false,
+                led: false,
+            },
+        })
+    }
+}
+
+impl HapticDevice for SdlHaptic {
+    fn capabilities(&self) -> HapticCapabilities {
+        self.capabilities
+    }
+
+    fn rumble(&mut self, low_frequency: f32, high_frequency: f32, duration_ms: u32) {
+        if !self.capabilities.rumble {
+            return;
+        }
+        let strength = low_frequency.max(high_frequency).max(0.0).min(1.0);
+        if strength > 0.0 {
+            self.haptic.rumble_play(strength, duration_ms);
+        } else {
+            self.haptic.rumble_stop();
+        }
+    }
+
+    fn stop(&mut self) {
+        if self.capabilities.rumble {
+            self.haptic.rumble_stop();
+        }
+    }
+}
+
+/// Whether SDL's simple rumble works on the joystick at `joystick_index`.
+///
+/// The sdl2 crate doesn't expose `SDL_HapticRumbleSupported`, so this opens its own handles to
+/// ask. SDL reference counts them, so the device opened by `SdlHaptic` stays open.
+fn rumble_supported(joystick_index: u32) -> bool {
+    unsafe {
+        let joystick = sys_joystick::SDL_JoystickOpen(joystick_index as c_int);
+        if joystick.is_null() {
+            return false;
+        }
+        let haptic = sys_haptic::SDL_HapticOpenFromJoystick(joystick);
+        let supported = !haptic.is_null() && sys_haptic::SDL_HapticRumbleSupported(haptic) == 1;
+        if !haptic.is_null() {
+            sys_haptic::SDL_HapticClose(haptic);
+        }
+        sys_joystick::SDL_JoystickClose(joystick);
+        supported
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HapticCommand {
+    Rumble { low_frequency: f32, high_frequency: f32, duration_ms: u32 },
+    RumbleTriggers { left: f32, right: f32, duration_ms: u32 },
+    SetLed { red: u8, green: u8, blue: u8 },
+    Stop,
+}
+
+/// A fake device that records every command it receives, for tests.
+#[derive(Clone, Default)]
+pub struct MockHaptic {
+    capabilities: HapticCapabilities,
+    commands: Rc<RefCell<Vec<HapticCommand>>>,
+}
+
+impl MockHaptic {
+    pub fn new(capabilities: HapticCapabilities) -> Self {
+        MockHaptic {
+            capabilities: capabilities,
+            commands: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    /// The commands received so far. Clones of a `MockHaptic` share their command log, so keep
+    /// a clone around to inspect a device that was boxed up.
+    pub fn commands(&self) -> Vec<HapticCommand> {
+        self.commands.borrow().clone()
+    }
+
+    pub fn clear(&self) {
+        self.commands.borrow_mut().clear();
+    }
+
+    fn record(&self, command: HapticCommand) {
+        self.commands.borrow_mut().push(command);
+    }
+}
+
+impl HapticDevice for MockHaptic {
+    fn capabilities(&self) -> HapticCapabilities {
+        self.capabilities
+    }
+
+    fn rumble(&mut self, low_frequency: f32, high_frequency: f32, duration_ms: u32) {
+        if self.capabilities.rumble {
+            self.record(HapticCommand::Rumble {
+                low_frequency: low_frequency,
+                high_frequency: high_frequency,
+                duration_ms: duration_ms,
+            });
+        }
+    }
+
+    fn rumble_triggers(&mut self, left: f32, right: f32, duration_ms: u32) {
+        if self.capabilities.trigger_rumble {
+            self.record(HapticCommand::RumbleTriggers { left: left, right: right, duration_ms: duration_ms });
+        }
+    }
+
+    fn set_led(&mut self, red: u8, green: u8, blue: u8) {
+        if self.capabilities.led {
+            self.record(HapticCommand::SetLed { red: red, green: green, blue: blue });
+        }
+    }
+
+    fn stop(&mut self) {
+        self.record(HapticCommand::Stop);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HapticStep {
+    pub low_frequency: f32,
+    pub high_frequency: f32,
+    /// How long the step lasts, in seconds.
+    pub duration: f64,
+}
+
+impl HapticStep {
+    pub fn new(low_frequency: f32, high_frequency: f32, duration: f64) -> Self {
+        HapticStep {
+            low_frequency: low_frequency,
+            high_frequency: high_frequency,
+            duration: duration,
+        }
+    }
+
+    pub fn pause(duration: f64) -> Self {
+        HapticStep::new(0.0, 0.0, duration)
+    }
+}
+
+/// A sequence of rumble steps, like "light hit" or "heavy hit".
+#[derive(Clone, Debug, PartialEq)]
+pub struct HapticPattern {
+    steps: Vec<HapticStep>,
+}
+
+impl HapticPattern {
+    pub fn new(steps: Vec<HapticStep>) -> Self {
+        HapticPattern {
+            steps: steps,
+        }
+    }
+
+    pub fn light_hit() -> Self {
+        HapticPattern::new(vec![HapticStep::new(0.0, 0.4, 0.08)])
+    }
+
+    pub fn heavy_hit() -> Self {
+        HapticPattern::new(vec![
+            HapticStep::new(1.0, 0.8, 0.12),
+            HapticStep::new(0.5, 0.2, 0.2),
+        ])
+    }
+
+    pub fn explosion() -> Self {
+        HapticPattern::new(vec![
+            HapticStep::new(1.0, 1.0, 0.15),
+            HapticStep::new(0.7, 0.3, 0.25),
+            HapticStep::new(0.3, 0.0, 0.4),
+        ])
+    }
+
+    /// `count` short pulses separated by pauses of the same length.
+    pub fn pulse(count: u32, intensity: f32, duration: f64) -> Self {
+        let mut steps = Vec::new();
+        for i in 0..count {
+            if i > 0 {
+                steps.push(HapticStep::pause(duration));
+            }
+            steps.push(HapticStep::new(intensity, intensity, duration));
+        }
+        HapticPattern::new(steps)
+    }
+
+    pub fn steps(&self) -> &[HapticStep] {
+        &self.steps
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.steps.iter().map(|step| step.duration).sum()
+    }
+}
+
+/// Drives a `HapticDevice`, playing patterns over time.
+pub struct Haptics {
+    device: Box<HapticDevice>,
+    pattern: Option<HapticPattern>,
+    step: usize,
+    step_time: f64,
+}
+
+impl Haptics {
+    pub fn new(device: Box<HapticDevice>) -> Self {
+        Haptics {
+            device: device,
+            pattern: None,
+            step: 0,
+            step_time: 0.0,
+        }
+    }
+
+    pub fn capabilities(&self) -> HapticCapabilities {
+        self.device.capabilities()
+    }
+
+    /// Rumble immediately for `duration` seconds, cancelling any pattern that is playing.
+    pub fn rumble(&mut self, low_frequency: f32, high_frequency: f32, duration: f64) {
+        self.pattern = None;
+        self.device.rumble(low_frequency, high_frequency, seconds_to_ms(duration));
+    }
+
+    pub fn rumble_triggers(&mut self, left: f32, right: f32, duration: f64) {
+        self.device.rumble_triggers(left, right, seconds_to_ms(duration));
+    }
+
+    pub fn set_led(&mut self, red: u8, green: u8, blue: u8) {
+        self.device.set_led(red, green, blue);
+    }
+
+    pub fn play(&mut self, pattern: HapticPattern) {
+        self.pattern = Some(pattern);
+        self.step = 0;
+        self.step_time = 0.0;
+        self.start_step();
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.pattern.is_some()
+    }
+
+    pub fn stop(&mut self) {
+        self.pattern = None;
+        self.device.stop();
+    }
+
+    /// Advance the current pattern by this frame's delta time.
+    pub fn update(&mut self, time: &Time) {
+        self.advance(time.delta_time());
+    }
+
+    pub fn advance(&mut self, dt: f64) {
+        if self.pattern.is_none() {
+            return;
+        }
+
+        self.step_time += dt;
+        loop {
+            let step_duration = match self.pattern {
+                Some(ref pattern) => pattern.steps.get(self.step).map(|step| step.duration),
+                None => return,
+            };
+            match step_duration {
+                Some(duration) if self.step_time >= duration => {
+                    self.step_time -= duration;
+                    self.step += 1;
+                    self.start_step();
+                },
+                _ => break,
+            }
+        }
+    }
+
+    fn start_step(&mut self) {
+        let step = self.pattern.as_ref().and_then(|pattern| pattern.steps.get(self.step).cloned());
+        match step {
+            Some(step) => self.device.rumble(step.low_frequency, step.high_frequency,
+                                             seconds_to_ms(step.duration)),
+            None => self.stop(),
+        }
+    }
+}
+
+fn seconds_to_ms(seconds: f64) -> u32 {
+    (seconds.max(0.0) * 1000.0).round() as u32
+}
+
+#[cfg(test)]
+mod test {
+    use super::{HapticCapabilities, HapticCommand, HapticPattern, HapticStep, Haptics, MockHaptic};
+
+    #[test]
+    fn patterns_step_over_time() {
+        let device = MockHaptic::new(HapticCapabilities { rumble: true, .. Default::default() });
+        let mut haptics = Haptics::new(Box::new(device.clone()));
+        haptics.play(HapticPattern::new(vec![
+            HapticStep::new(1.0, 0.5, 0.1),
+            HapticStep::new(0.2, 0.0, 0.1),
+        ]));
+
+        haptics.advance(0.05);
+        assert_eq!(device.commands().len(), 1);
+        haptics.advance(0.1);
+        haptics.advance(0.1);
+        assert!(!haptics.is_playing());
+        assert_eq!(device.commands(), vec![
+            HapticCommand::Rumble { low_frequency: 1.0, high_frequency: 0.5, duration_ms: 100 },
+            HapticCommand::Rumble { low_frequency: 0.2, high_frequency: 0.0, duration_ms: 100 },
+            HapticCommand::Stop,
+        ]);
+    }
+
+    #[test]
+    fn unsupported_features_are_ignored() {
+        let device = MockHaptic::new(HapticCapabilities { rumble: true, .. Default::default() });
+        let mut haptics = Haptics::new(Box::new(device.clone()));
+        haptics.set_led(255, 0, 0);
+        haptics.rumble_triggers(1.0, 1.0, 0.5);
+        assert!(device.commands().is_empty());
+    }
+
+    #[test]
+    fn supported_features_reach_the_device() {
+        let device = MockHaptic::new(HapticCapabilities { rumble: true, trigger_rumble: true, led: true });
+        let mut haptics = Haptics::new(Box::new(device.clone()));
+        haptics.rumble_triggers(0.25, 0.75, 0.2);
+        haptics.set_led(0, 128, 255);
+        assert_eq!(device.commands(), vec![
+            HapticCommand::RumbleTriggers { left: 0.25, right: 0.75, duration_ms: 200 },
+            HapticCommand::SetLed { red: 0, green: 128, blue: 255 },
+        ]);
+    }
+}
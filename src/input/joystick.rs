@@ -0,0 +1,217 @@
+use std::collections::{HashMap, HashSet};
+
+use sdl2;
+pub use sdl2::joystick::HatState;
+
+use input::deadzone::{normalize_axis, DeadZone};
+
+
+/// Whether a hat is pushed in `direction`, which should be `Up`, `Down`, `Left` or `Right`.
+/// Diagonals count for both of their directions.
+pub fn hat_contains(state: HatState, direction: HatState) -> bool {
+    direction != HatState::Centered && state.to_raw() & direction.to_raw() == direction.to_raw()
+}
+
+/// A device SDL doesn't recognize as a game controller, like an arcade stick, flight stick,
+/// wheel or pedals. Axes, buttons, hats and balls are identified by index.
+pub struct Joystick {
+    // The joystick instance ID, which SDL uses to identify the joystick in events.
+    id: u32,
+    guid: String,
+    name: String,
+    // `None` for virtual joysticks created with `Input::inject_joystick_added`.
+    sdl_joystick: Option<sdl2::joystick::Joystick>,
+
+    axes: Vec<i16>,
+    previous_axes: Vec<i16>,
+    dead_zones: HashMap<u8, DeadZone>,
+    num_buttons: u32,
+    held_buttons: HashSet<u8>,
+    pressed_buttons: HashSet<u8>,
+    released_buttons: HashSet<u8>,
+    hats: Vec<HatState>,
+    previous_hats: Vec<HatState>,
+    ball_deltas: Vec<(i32, i32)>,
+}
+
+impl Joystick {
+    pub(super) fn new(sdl_joystick: sdl2::joystick::Joystick) -> Self {
+        let id = sdl_joystick.instance_id() as u32;
+        let guid = sdl_joystick.guid().string();
+        let name = sdl_joystick.name();
+        let num_axes = sdl_joystick.num_axes();
+        let num_buttons = sdl_joystick.num_buttons();
+        let num_hats = sdl_joystick.num_hats();
+        let num_balls = sdl_joystick.num_balls();
+
+        let mut joystick = Joystick::with_counts(id, guid, name, num_axes, num_buttons, num_hats, num_balls);
+        joystick.sdl_joystick = Some(sdl_joystick);
+        joystick.refresh_state();
+        joystick
+    }
+
+    pub(super) fn with_counts(id: u32, guid: String, name: String, num_axes: u32, num_buttons: u32,
+                              num_hats: u32, num_balls: u32) -> Self {
+        Joystick {
+            id: id,
+            guid: guid,
+            name: name,
+            sdl_joystick: None,
+
+            axes: vec![0; num_axes as usize],
+            previous_axes: vec![0; num_axes as usize],
+            dead_zones: HashMap::new(),
+            num_buttons: num_buttons,
+            held_buttons: HashSet::new(),
+            pressed_buttons: HashSet::new(),
+            released_buttons: HashSet::new(),
+            hats: vec![HatState::Centered; num_hats as usize],
+            previous_hats: vec![HatState::Centered; num_hats as usize],
+            ball_deltas: vec![(0, 0); num_balls as usize],
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn guid(&self) -> &str {
+        &self.guid
+    }
+
+    /// Whether this joystick was injected rather than opened through SDL.
+    pub fn is_virtual(&self) -> bool {
+        self.sdl_joystick.is_none()
+    }
+
+    pub fn num_axes(&self) -> u32 {
+        self.axes.len() as u32
+    }
+
+    pub fn num_buttons(&self) -> u32 {
+        self.num_buttons
+    }
+
+    pub fn num_hats(&self) -> u32 {
+        self.hats.len() as u32
+    }
+
+    pub fn num_balls(&self) -> u32 {
+        self.ball_deltas.len() as u32
+    }
+
+    pub fn get_axis_position(&self, axis: u8) -> i16 {
+        self.axes.get(axis as usize).cloned().unwrap_or(0)
+    }
+
+    pub fn get_previous_axis_position(&self, axis: u8) -> i16 {
+        self.previous_axes.get(axis as usize).cloned().unwrap_or(0)
+    }
+
+    /// Get the axis position from -1 to 1, with the axis dead zone applied.
+    pub fn axis(&self, axis: u8) -> f32 {
+        self.dead_zone(axis).apply(normalize_axis(self.get_axis_position(axis)))
+    }
+
+    pub fn previous_axis(&self, axis: u8) -> f32 {
+        self.dead_zone(axis).apply(normalize_axis(self.get_previous_axis_position(axis)))
+    }
+
+    /// Joystick axes have no dead zone unless one is set, since wheels and pedals need their
+    /// full range.
+    pub fn dead_zone(&self, axis: u8) -> DeadZone {
+        self.dead_zones.get(&axis).cloned().unwrap_or_else(DeadZone::none)
+    }
+
+    pub fn set_dead_zone(&mut self, axis: u8, dead_zone: DeadZone) {
+        self.dead_zones.insert(axis, dead_zone);
+    }
+
+    pub fn is_button_held(&self, button: u8) -> bool {
+        self.held_buttons.contains(&button)
+    }
+
+    pub fn was_button_pressed(&self, button: u8) -> bool {
+        self.pressed_buttons.contains(&button)
+    }
+
+    pub fn was_button_released(&self, button: u8) -> bool {
+        self.released_buttons.contains(&button)
+    }
+
+    pub fn hat(&self, hat: u8) -> HatState {
+        self.hats.get(hat as usize).cloned().unwrap_or(HatState::Centered)
+    }
+
+    pub fn previous_hat(&self, hat: u8) -> HatState {
+        self.previous_hats.get(hat as usize).cloned().unwrap_or(HatState::Centered)
+    }
+
+    /// How far a trackball moved this frame.
+    pub fn ball_delta(&self, ball: u8) -> (i32, i32) {
+        self.ball_deltas.get(ball as usize).cloned().unwrap_or((0, 0))
+    }
+
+    pub(super) fn begin_frame(&mut self) {
+        self.pressed_buttons.clear();
+        self.released_buttons.clear();
+        self.previous_axes.clone_from(&self.axes);
+        self.previous_hats.clone_from(&self.hats);
+        for delta in &mut self.ball_deltas {
+            *delta = (0, 0);
+        }
+    }
+
+    pub(super) fn set_axis_position(&mut self, axis: u8, position: i16) {
+        if let Some(value) = self.axes.get_mut(axis as usize) {
+            *value = position;
+        }
+    }
+
+    pub(super) fn press_button(&mut self, button: u8) {
+        self.held_buttons.insert(button);
+        self.pressed_buttons.insert(button);
+    }
+
+    pub(super) fn release_button(&mut self, button: u8) {
+        self.held_buttons.remove(&button);
+        self.released_buttons.insert(button);
+    }
+
+    pub(super) fn set_hat(&mut self, hat: u8, state: HatState) {
+        if let Some(value) = self.hats.get_mut(hat as usize) {
+            *value = state;
+        }
+    }
+
+    pub(super) fn add_ball_motion(&mut self, ball: u8, xrel: i32, yrel: i32) {
+        if let Some(delta) = self.ball_deltas.get_mut(ball as usize) {
+            delta.0 += xrel;
+            delta.1 += yrel;
+        }
+    }
+
+    fn refresh_state(&mut self) {
+        let (axes, buttons, hats) = match self.sdl_joystick {
+            Some(ref sdl_joystick) => (
+                (0..sdl_joystick.num_axes()).map(|i| sdl_joystick.axis(i).unwrap_or(0)).collect::<Vec<_>>(),
+                (0..sdl_joystick.num_buttons()).map(|i| sdl_joystick.button(i).unwrap_or(false)).collect::<Vec<_>>(),
+                (0..sdl_joystick.num_hats()).map(|i| sdl_joystick.hat(i).unwrap_or(HatState::Centered)).collect::<Vec<_>>(),
+            ),
+            None => return,
+        };
+
+        self.axes = axes;
+        self.previous_axes.clone_from(&self.axes);
+        self.held_buttons = buttons.iter().enumerate()
+            .filter(|&(_, &held)| held)
+            .map(|(i, _)| i as u8)
+            .collect();
+        self.hats = hats;
+        self.previous_hats.clone_from(&self.hats);
+    }
+}
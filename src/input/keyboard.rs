@@ -0,0 +1,108 @@
+use sdl2::keyboard::{self, Mod};
+
+use input::{KeyCode, ScanCode};
+
+
+/// The state of the modifier keys. Left and right variants are combined.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    /// The Windows, Command or Super key.
+    pub gui: bool,
+    pub caps_lock: bool,
+    pub num_lock: bool,
+}
+
+impl Modifiers {
+    pub fn from_sdl(keymod: Mod) -> Self {
+        Modifiers {
+            shift: keymod.intersects(keyboard::LSHIFTMOD | keyboard::RSHIFTMOD),
+            ctrl: keymod.intersects(keyboard::LCTRLMOD | keyboard::RCTRLMOD),
+            alt: keymod.intersects(keyboard::LALTMOD | keyboard::RALTMOD),
+            gui: keymod.intersects(keyboard::LGUIMOD | keyboard::RGUIMOD),
+            caps_lock: keymod.contains(keyboard::CAPSMOD),
+            num_lock: keymod.contains(keyboard::NUMMOD),
+        }
+    }
+
+    /// Convert back to SDL's flags, using the left-hand keys.
+    pub fn to_sdl(&self) -> Mod {
+        let mut keymod = keyboard::NOMOD;
+        let flags = [
+            (self.shift, keyboard::LSHIFTMOD), (self.ctrl, keyboard::LCTRLMOD),
+            (self.alt, keyboard::LALTMOD), (self.gui, keyboard::LGUIMOD),
+            (self.caps_lock, keyboard::CAPSMOD), (self.num_lock, keyboard::NUMMOD),
+        ];
+        for &(held, flag) in &flags {
+            if held {
+                keymod.insert(flag);
+            }
+        }
+        keymod
+    }
+
+    /// Whether no modifiers other than the lock keys are held.
+    pub fn is_empty(&self) -> bool {
+        !(self.shift || self.ctrl || self.alt || self.gui)
+    }
+}
+
+/// The key a scancode produces on the current keyboard layout. E.g. the `W` scancode is `Z` on
+/// AZERTY keyboards.
+pub fn keycode_for_scancode(scancode: ScanCode) -> Option<KeyCode> {
+    KeyCode::from_scancode(scancode)
+}
+
+/// The physical key that produces `keycode` on the current keyboard layout.
+pub fn scancode_for_keycode(keycode: KeyCode) -> Option<ScanCode> {
+    ScanCode::from_keycode(keycode)
+}
+
+/// A name for a physical key to show to the player, using the key it produces on their layout.
+pub fn scancode_display_name(scancode: ScanCode) -> String {
+    match keycode_for_scancode(scancode) {
+        Some(keycode) => keycode.name(),
+        None => scancode.name().into(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sdl2::keyboard;
+
+    use input::{ElementState, Input, KeyCode, ScanCode};
+    use super::Modifiers;
+
+    #[test]
+    fn modifiers_combine_left_and_right() {
+        let modifiers = Modifiers::from_sdl(keyboard::RSHIFTMOD | keyboard::LCTRLMOD | keyboard::CAPSMOD);
+        assert_eq!(modifiers, Modifiers { shift: true, ctrl: true, caps_lock: true, .. Default::default() });
+        assert!(!modifiers.is_empty());
+        assert_eq!(Modifiers::from_sdl(modifiers.to_sdl()), modifiers);
+        assert!(Modifiers::from_sdl(keyboard::NUMMOD).is_empty());
+    }
+
+    #[test]
+    fn scancodes_track_held_pressed_and_repeated() {
+        let mut input = Input::headless();
+        input.handle_keyboard_input(ElementState::Pressed, Some(KeyCode::Z), Some(ScanCode::W), keyboard::LSHIFTMOD);
+        assert!(input.was_scancode_pressed(ScanCode::W));
+        assert!(input.is_scancode_held(ScanCode::W));
+        assert!(input.is_key_held(KeyCode::Z));
+        assert!(input.modifiers().shift);
+
+        input.begin_frame();
+        input.handle_key_repeat(Some(KeyCode::Z), Some(ScanCode::W), keyboard::NOMOD);
+        assert!(!input.was_scancode_pressed(ScanCode::W));
+        assert!(input.was_scancode_repeated(ScanCode::W));
+        assert!(!input.modifiers().shift);
+
+        input.begin_frame();
+        assert!(!input.was_scancode_repeated(ScanCode::W));
+        input.handle_keyboard_input(ElementState::Released, Some(KeyCode::Z), Some(ScanCode::W), keyboard::NOMOD);
+        assert!(input.was_scancode_released(ScanCode::W));
+        assert!(!input.is_scancode_held(ScanCode::W));
+    }
+}
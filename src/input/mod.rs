@@ -0,0 +1,1416 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::mem;
+use std::path::Path;
+
+use sdl2;
+use sdl2::keyboard::{Mod, TextInputUtil};
+use sdl2::rect::Rect;
+pub use sdl2::controller::{Axis, Button, GameController};
+pub use sdl2::keyboard::Keycode as KeyCode;
+pub use sdl2::keyboard::Scancode as ScanCode;
+pub use sdl2::mouse::MouseButton;
+
+pub use self::action::{ActionMap, AxisBinding, AxisDirection, Binding};
+pub use self::deadzone::{DeadZone, DeadZones, ResponseCurve, Stick, StickDeadZone, TriggerThreshold};
+pub use self::events::{Combo, ComboInput, ComboMatcher, Direction, InputEvent, TimedEvent};
+pub use self::glyphs::{ControllerFamily, Glyph, InputDevice};
+pub use self::haptics::{HapticCapabilities, HapticDevice, HapticPattern, HapticStep, Haptics};
+pub use self::joystick::{HatState, Joystick};
+pub use self::keyboard::Modifiers;
+pub use self::processor::{InputMultiplexer, InputProcessor};
+pub use self::slots::{JoinMode, PlayerSlot, PlayerSlots, SlotEvent};
+pub use self::touch::{Gesture, GestureConfig, GestureDetector, Touch, TouchEvent, TouchPhase};
+
+use self::deadzone::normalize_axis;
+use self::haptics::{NullHaptic, SdlHaptic};
+use time::Time;
+
+pub mod action;
+pub mod deadzone;
+pub mod events;
+pub mod glyphs;
+pub mod haptics;
+pub mod joystick;
+pub mod keyboard;
+pub mod processor;
+pub mod slots;
+pub mod touch;
+
+
+pub const ALL_AXES: [Axis; 6] = [
+    Axis::LeftX, Axis::LeftY, Axis::RightX, Axis::RightY, Axis::TriggerLeft, Axis::TriggerRight,
+];
+
+pub const ALL_BUTTONS: [Button; 15] = [
+    Button::A, Button::B, Button::X, Button::Y,
+    Button::Back, Button::Guide, Button::Start,
+    Button::LeftStick, Button::RightStick, Button::LeftShoulder, Button::RightShoulder,
+    Button::DPadUp, Button::DPadDown, Button::DPadLeft, Button::DPadRight,
+];
+
+/// Keys that auto-repeat while text input is active, so holding backspace or an arrow key
+/// behaves like it does in any other text field.
+pub const EDITING_KEYS: [KeyCode; 13] = [
+    KeyCode::Backspace, KeyCode::Delete, KeyCode::Return, KeyCode::KpEnter, KeyCode::Tab,
+    KeyCode::Left, KeyCode::Right, KeyCode::Up, KeyCode::Down,
+    KeyCode::Home, KeyCode::End, KeyCode::PageUp, KeyCode::PageDown,
+];
+
+/// Text that an IME is still composing and that hasn't been committed yet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Composition {
+    pub text: String,
+    /// The cursor position within `text`, in characters.
+    pub cursor: usize,
+    /// The number of characters selected after the cursor.
+    pub selection_length: usize,
+}
+
+/// Virtual controller IDs start high so they don't collide with SDL's instance IDs.
+const FIRST_VIRTUAL_CONTROLLER_ID: u32 = 1 << 20;
+
+/// How many frames of events `Input` keeps in its history by default.
+const DEFAULT_HISTORY_FRAMES: u64 = 60;
+
+#[derive(Clone, Copy, Debug)]
+pub enum ElementState {
+    Pressed,
+    Released,
+}
+
+//#[derive(Debug)]
+pub struct Controller {
+    // The joystick instance ID, which SDL uses to identify the controller in events.
+    id: u32,
+    guid: String,
+    name: String,
+    // `None` for virtual controllers created with `Input::inject_controller_added`.
+    sdl_controller: Option<GameController>,
+    axis_positions: HashMap<Axis, i16>,
+    previous_axis_positions: HashMap<Axis, i16>,
+    held_buttons: HashSet<Button>,
+    pressed_buttons: HashSet<Button>,
+    released_buttons: HashSet<Button>,
+    dead_zones: DeadZones,
+    held_triggers: HashSet<Axis>,
+    pressed_triggers: HashSet<Axis>,
+    released_triggers: HashSet<Axis>,
+    haptics: Haptics,
+}
+
+impl Controller {
+    fn new(sdl_controller: GameController, guid: String, haptics: Haptics) -> Self {
+        let id = sdl_controller.instance_id() as u32;
+        let name = sdl_controller.name();
+        Controller::with_parts(id, guid, name, Some(sdl_controller), haptics)
+    }
+
+    fn with_parts(id: u32, guid: String, name: String, sdl_controller: Option<GameController>,
+                  haptics: Haptics) -> Self {
+        Controller {
+            id: id,
+            guid: guid,
+            name: name,
+            sdl_controller: sdl_controller,
+            axis_positions: HashMap::new(),
+            previous_axis_positions: HashMap::new(),
+            held_buttons: HashSet::new(),
+            pressed_buttons: HashSet::new(),
+            released_buttons: HashSet::new(),
+            dead_zones: DeadZones::new(),
+            held_triggers: HashSet::new(),
+            pressed_triggers: HashSet::new(),
+            released_triggers: HashSet::new(),
+            haptics: haptics,
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn name(&self) -> String {
+        match self.sdl_controller {
+            Some(ref sdl_controller) => sdl_controller.name(),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Whether this controller was injected rather than opened through SDL.
+    pub fn is_virtual(&self) -> bool {
+        self.sdl_controller.is_none()
+    }
+
+    /// The GUID of the underlying joystick, as used in `gamecontrollerdb.txt`.
+    pub fn guid(&self) -> &str {
+        &self.guid
+    }
+
+    /// The SDL mapping string currently used for this controller. Empty for virtual controllers.
+    pub fn mapping(&self) -> String {
+        self.sdl_controller.as_ref()
+            .map(|sdl_controller| sdl_controller.mapping())
+            .unwrap_or_default()
+    }
+
+    /// Which family this controller belongs to, for showing the right button labels.
+    pub fn family(&self) -> ControllerFamily {
+        ControllerFamily::detect(&self.guid, &self.name())
+    }
+
+    pub fn button_glyph(&self, button: Button) -> Glyph {
+        self.family().button_glyph(button)
+    }
+
+    pub fn axis_glyph(&self, axis: Axis) -> Glyph {
+        self.family().axis_glyph(axis)
+    }
+
+    pub fn get_axis_position(&self, axis: Axis) -> i16 {
+        self.axis_positions.get(&axis).cloned().unwrap_or(0)
+    }
+
+    /// Get the axis position as it was at the end of the previous frame.
+    pub fn get_previous_axis_position(&self, axis: Axis) -> i16 {
+        self.previous_axis_positions.get(&axis).cloned().unwrap_or(0)
+    }
+
+    /// Get the axis position from -1 to 1 (0 to 1 for triggers), with the axis dead zone and
+    /// response curve applied.
+    pub fn axis(&self, axis: Axis) -> f32 {
+        self.dead_zones.axis(axis).apply(normalize_axis(self.get_axis_position(axis)))
+    }
+
+    pub fn previous_axis(&self, axis: Axis) -> f32 {
+        self.dead_zones.axis(axis).apply(normalize_axis(self.get_previous_axis_position(axis)))
+    }
+
+    /// Get both axes of a stick as a vector, with the stick dead zone applied.
+    pub fn stick(&self, stick: Stick) -> (f32, f32) {
+        let (x_axis, y_axis) = stick.axes();
+        let x = normalize_axis(self.get_axis_position(x_axis));
+        let y = normalize_axis(self.get_axis_position(y_axis));
+        self.dead_zones.stick(stick).apply(x, y)
+    }
+
+    pub fn dead_zones(&self) -> &DeadZones {
+        &self.dead_zones
+    }
+
+    pub fn dead_zones_mut(&mut self) -> &mut DeadZones {
+        &mut self.dead_zones
+    }
+
+    /// Whether a trigger is pulled past the trigger threshold.
+    pub fn is_trigger_held(&self, trigger: Axis) -> bool {
+        self.held_triggers.contains(&trigger)
+    }
+
+    pub fn was_trigger_pressed(&self, trigger: Axis) -> bool {
+        self.pressed_triggers.contains(&trigger)
+    }
+
+    pub fn was_trigger_released(&self, trigger: Axis) -> bool {
+        self.released_triggers.contains(&trigger)
+    }
+
+    pub fn is_button_held(&self, button: Button) -> bool {
+        self.held_buttons.contains(&button)
+    }
+
+    pub fn was_button_pressed(&self, button: Button) -> bool {
+        self.pressed_buttons.contains(&button)
+    }
+
+    pub fn was_button_released(&self, button: Button) -> bool {
+        self.released_buttons.contains(&button)
+    }
+
+    pub fn haptic_capabilities(&self) -> HapticCapabilities {
+        self.haptics.capabilities()
+    }
+
+    /// Rumble the low- and high-frequency motors for `duration` seconds. Intensities range from
+    /// 0 to 1. Does nothing if the controller can't rumble.
+    pub fn rumble(&mut self, low_frequency: f32, high_frequency: f32, duration: f64) {
+        self.haptics.rumble(low_frequency, high_frequency, duration);
+    }
+
+    /// Rumble the motors in the triggers. Does nothing on controllers without them.
+    pub fn rumble_triggers(&mut self, left: f32, right: f32, duration: f64) {
+        self.haptics.rumble_triggers(left, right, duration);
+    }
+
+    /// Set the color of the controller's LED. Does nothing on controllers without one.
+    pub fn set_led(&mut self, red: u8, green: u8, blue: u8) {
+        self.haptics.set_led(red, green, blue);
+    }
+
+    /// Play a haptic pattern, replacing any pattern that is already playing.
+    pub fn play_haptic_pattern(&mut self, pattern: HapticPattern) {
+        self.haptics.play(pattern);
+    }
+
+    pub fn stop_haptics(&mut self) {
+        self.haptics.stop();
+    }
+
+    pub fn haptics(&self) -> &Haptics {
+        &self.haptics
+    }
+
+    pub fn haptics_mut(&mut self) -> &mut Haptics {
+        &mut self.haptics
+    }
+
+    fn begin_frame(&mut self) {
+        self.pressed_buttons.clear();
+        self.released_buttons.clear();
+        self.pressed_triggers.clear();
+        self.released_triggers.clear();
+        self.previous_axis_positions.clone_from(&self.axis_positions);
+    }
+
+    fn set_axis_position(&mut self, axis: Axis, position: i16) {
+        self.axis_positions.insert(axis, position);
+
+        if axis == Axis::TriggerLeft || axis == Axis::TriggerRight {
+            let was_held = self.is_trigger_held(axis);
+            let held = self.dead_zones.trigger_threshold().is_held(self.axis(axis), was_held);
+            if held && !was_held {
+                self.held_triggers.insert(axis);
+                self.pressed_triggers.insert(axis);
+            } else if !held && was_held {
+                self.held_triggers.remove(&axis);
+                self.released_triggers.insert(axis);
+            }
+        }
+    }
+
+    fn press_button(&mut self, button: Button) {
+        self.held_buttons.insert(button);
+        self.pressed_buttons.insert(button);
+    }
+
+    fn release_button(&mut self, button: Button) {
+        self.held_buttons.remove(&button);
+        self.released_buttons.insert(button);
+    }
+
+    /// Re-read every axis and button from SDL, e.g. after the controller was remapped. Buttons
+    /// that changed state are reported as pressed or released this frame.
+    fn refresh_state(&mut self) {
+        let (positions, held_buttons): (Vec<_>, Vec<_>) = match self.sdl_controller {
+            Some(ref sdl_controller) => (
+                ALL_AXES.iter().map(|&axis| (axis, sdl_controller.axis(axis))).collect(),
+                ALL_BUTTONS.iter().map(|&button| (button, sdl_controller.button(button))).collect(),
+            ),
+            None => return,
+        };
+
+        for (axis, position) in positions {
+            self.set_axis_position(axis, position);
+        }
+
+        for (button, held) in held_buttons {
+            if held && !self.is_button_held(button) {
+                self.press_button(button);
+            } else if !held && self.is_button_held(button) {
+                self.release_button(button);
+            }
+        }
+    }
+}
+
+// Implement a useful structure that holds current input state.
+pub struct Input {
+    held_keys: HashSet<KeyCode>,
+    pressed_keys: HashSet<KeyCode>,
+    released_keys: HashSet<KeyCode>,
+    repeated_keys: HashSet<KeyCode>,
+    typed_keys: HashSet<KeyCode>,
+    held_scancodes: HashSet<ScanCode>,
+    pressed_scancodes: HashSet<ScanCode>,
+    released_scancodes: HashSet<ScanCode>,
+    repeated_scancodes: HashSet<ScanCode>,
+    modifiers: Modifiers,
+
+    text_input: Option<TextInputUtil>,
+    text_input_active: bool,
+    text: String,
+    composition: Option<Composition>,
+
+    held_buttons: HashSet<MouseButton>,
+    pressed_buttons: HashSet<MouseButton>,
+    released_buttons: HashSet<MouseButton>,
+    click_counts: HashMap<MouseButton, u8>,
+    mouse_pos: (i32, i32),
+    mouse_delta: (i32, i32),
+    mouse_moved: bool,
+    mouse_in_window: bool,
+    scroll: (f32, f32),
+
+    touches: Vec<Touch>,
+    touch_events: Vec<TouchEvent>,
+
+    frame: u64,
+    event_timestamp: u32,
+    events: Vec<TimedEvent>,
+    history: VecDeque<TimedEvent>,
+    history_frames: u64,
+    last_device: InputDevice,
+    last_device_changed: bool,
+
+    controllers: Vec<Controller>,
+    joysticks: Vec<Joystick>,
+    next_virtual_controller_id: u32,
+    controller_subsystem: Option<sdl2::GameControllerSubsystem>,
+    joystick_subsystem: Option<sdl2::JoystickSubsystem>,
+    haptic_subsystem: Option<sdl2::HapticSubsystem>,
+    controller_mapping_errors: Vec<String>,
+}
+
+impl Input {
+    // FIXME: This shouldn't be accessible outside the crate.
+    pub fn new(sdl_context: &sdl2::Sdl) -> Self {
+        let mut input = Input::headless();
+
+        // Initialize controller subsystem.
+        input.controller_subsystem = Some(sdl_context.game_controller().unwrap());
+        input.joystick_subsystem = Some(sdl_context.joystick().unwrap());
+        // Not every platform supports haptics, so rumble just becomes a no-op without it.
+        input.haptic_subsystem = sdl_context.haptic().ok();
+        // SDL starts out with text input enabled, but games usually only want it while a text
+        // field has focus.
+        input.text_input = sdl_context.video().ok().map(|video| video.text_input());
+        if let Some(ref text_input) = input.text_input {
+            text_input.stop();
+        }
+
+        // Open any currently connected devices.
+        input.open_new_controllers();
+
+        input
+    }
+
+    /// Create an `Input` that isn't connected to SDL, for tests and tools. Feed it events with
+    /// the `inject_*` methods.
+    pub fn headless() -> Self {
+        Input {
+            held_keys: HashSet::new(),
+            pressed_keys: HashSet::new(),
+            released_keys: HashSet::new(),
+            repeated_keys: HashSet::new(),
+            typed_keys: HashSet::new(),
+            held_scancodes: HashSet::new(),
+            pressed_scancodes: HashSet::new(),
+            released_scancodes: HashSet::new(),
+            repeated_scancodes: HashSet::new(),
+            modifiers: Modifiers::default(),
+
+            text_input: None,
+            text_input_active: false,
+            text: String::new(),
+            composition: None,
+
+            held_buttons: HashSet::new(),
+            pressed_buttons: HashSet::new(),
+            released_buttons: HashSet::new(),
+            click_counts: HashMap::new(),
+            mouse_pos: (0, 0),
+            mouse_delta: (0, 0),
+            mouse_moved: false,
+            mouse_in_window: false,
+            scroll: (0.0, 0.0),
+
+            touches: Vec::new(),
+            touch_events: Vec::new(),
+
+            frame: 0,
+            event_timestamp: 0,
+            events: Vec::new(),
+            history: VecDeque::new(),
+            history_frames: DEFAULT_HISTORY_FRAMES,
+            last_device: InputDevice::KeyboardMouse,
+            last_device_changed: false,
+
+            controllers: Vec::new(),
+            joysticks: Vec::new(),
+            next_virtual_controller_id: FIRST_VIRTUAL_CONTROLLER_ID,
+            controller_subsystem: None,
+            joystick_subsystem: None,
+            haptic_subsystem: None,
+            controller_mapping_errors: Vec::new(),
+        }
+    }
+
+    /// Load controller mappings from a `gamecontrollerdb.txt` style file. Returns the number of
+    /// mappings that were loaded.
+    ///
+    /// Devices that become recognized as controllers are opened right away, and controllers that
+    /// are already open get remapped.
+    pub fn load_controller_mappings<P: AsRef<Path>>(&mut self, path: P) -> Result<u32, String> {
+        let count = self.controller_subsystem.as_ref()
+            .ok_or_else(|| "The controller subsystem isn't available".to_string())?
+            .load_mappings(path)
+            .map_err(|error| error.to_string())?;
+        self.open_new_controllers();
+        Ok(count as u32)
+    }
+
+    /// Load several mapping files, carrying on past any that fail. The failures can be checked
+    /// with `controller_mapping_errors`.
+    pub fn load_controller_mapping_files<P: AsRef<Path>>(&mut self, paths: &[P]) {
+        for path in paths {
+            let path = path.as_ref();
+            if let Err(error) = self.load_controller_mappings(path) {
+                self.controller_mapping_errors.push(format!("{}: {}", path.display(), error));
+            }
+        }
+    }
+
+    /// Mapping files from `load_controller_mapping_files` that couldn't be loaded, e.g. the ones
+    /// in `MidgarAppConfig`, as `path: error` messages.
+    pub fn controller_mapping_errors(&self) -> &[String] {
+        &self.controller_mapping_errors
+    }
+
+    /// Add a single SDL controller mapping string.
+    pub fn add_controller_mapping(&mut self, mapping: &str) -> Result<(), String> {
+        self.controller_subsystem.as_ref()
+            .ok_or_else(|| "The controller subsystem isn't available".to_string())?
+            .add_mapping(mapping)
+            .map_err(|error| error.to_string())?;
+        self.open_new_controllers();
+        Ok(())
+    }
+
+    /// Open any connected devices that SDL recognizes as controllers but that aren't open yet.
+    ///
+    /// Everything else is opened as a raw `Joystick`. A joystick that becomes recognized as a
+    /// controller, e.g. after loading mappings, is replaced by its controller.
+    fn open_new_controllers(&mut self) {
+        let num_joysticks = match self.joystick_subsystem {
+            Some(ref joystick_subsystem) => joystick_subsystem.num_joysticks().unwrap_or(0),
+            None => 0,
+        };
+        for index in 0..num_joysticks {
+            if !self.is_game_controller(index) {
+                if let Some(joystick) = self.open_joystick(index) {
+                    if self.joysticks.iter().all(|j| j.id() != joystick.id()) {
+                        self.joysticks.push(joystick);
+                    }
+                }
+                continue;
+            }
+            if let Some(controller) = self.open_controller(index) {
+                self.joysticks.retain(|joystick| joystick.id() != controller.id);
+                if self.controllers.iter().all(|c| c.id != controller.id) {
+                    self.record(InputEvent::ControllerAdded { controller: controller.id });
+                    self.controllers.push(controller);
+                }
+            }
+        }
+    }
+
+    fn is_game_controller(&self, index: u32) -> bool {
+        self.controller_subsystem.as_ref()
+            .map_or(false, |controller_subsystem| controller_subsystem.is_game_controller(index))
+    }
+
+    fn open_joystick(&self, index: u32) -> Option<Joystick> {
+        self.joystick_subsystem.as_ref()
+            .and_then(|joystick_subsystem| joystick_subsystem.open(index).ok())
+            .map(Joystick::new)
+    }
+
+    fn open_controller(&self, index: u32) -> Option<Controller> {
+        let sdl_controller = match self.controller_subsystem.as_ref().map(|subsystem| subsystem.open(index)) {
+            Some(Ok(sdl_controller)) => sdl_controller,
+            _ => return None,
+        };
+        let guid = self.joystick_subsystem.as_ref()
+            .and_then(|joystick_subsystem| joystick_subsystem.device_guid(index).ok())
+            .map(|guid| guid.string())
+            .unwrap_or_default();
+        let device: Box<HapticDevice> = match self.haptic_subsystem {
+            Some(ref haptic_subsystem) => match SdlHaptic::open(haptic_subsystem, index) {
+                Ok(haptic) => Box::new(haptic),
+                Err(_) => Box::new(NullHaptic),
+            },
+            None => Box::new(NullHaptic),
+        };
+        Some(Controller::new(sdl_controller, guid, Haptics::new(device)))
+    }
+
+    pub fn is_key_held(&self, keycode: KeyCode) -> bool {
+        self.held_keys.contains(&keycode)
+    }
+
+    pub fn was_key_pressed(&self, keycode: KeyCode) -> bool {
+        self.pressed_keys.contains(&keycode)
+    }
+
+    pub fn was_key_released(&self, keycode: KeyCode) -> bool {
+        self.released_keys.contains(&keycode)
+    }
+
+    /// Whether the OS sent a key-repeat event for a held key this frame.
+    pub fn was_key_repeated(&self, keycode: KeyCode) -> bool {
+        self.repeated_keys.contains(&keycode)
+    }
+
+    /// Whether a physical key is held, regardless of keyboard layout.
+    pub fn is_scancode_held(&self, scancode: ScanCode) -> bool {
+        self.held_scancodes.contains(&scancode)
+    }
+
+    pub fn was_scancode_pressed(&self, scancode: ScanCode) -> bool {
+        self.pressed_scancodes.contains(&scancode)
+    }
+
+    pub fn was_scancode_released(&self, scancode: ScanCode) -> bool {
+        self.released_scancodes.contains(&scancode)
+    }
+
+    pub fn was_scancode_repeated(&self, scancode: ScanCode) -> bool {
+        self.repeated_scancodes.contains(&scancode)
+    }
+
+    /// The modifier keys held as of the last keyboard event.
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Whether a key was pressed this frame, or auto-repeated while text input is active. Use
+    /// this for editing keys in text fields.
+    pub fn was_key_typed(&self, keycode: KeyCode) -> bool {
+        self.was_key_pressed(keycode) || self.typed_keys.contains(&keycode)
+    }
+
+    /// Start delivering text input and IME composition events.
+    pub fn start_text_input(&mut self) {
+        if let Some(ref text_input) = self.text_input {
+            text_input.start();
+        }
+        self.text_input_active = true;
+    }
+
+    pub fn stop_text_input(&mut self) {
+        if let Some(ref text_input) = self.text_input {
+            text_input.stop();
+        }
+        self.text_input_active = false;
+        self.composition = None;
+    }
+
+    pub fn is_text_input_active(&self) -> bool {
+        self.text_input_active
+    }
+
+    /// Tell the IME where the text being edited is on screen, in window coordinates, so it can
+    /// place its candidate list next to it.
+    pub fn set_text_input_rect(&mut self, x: i32, y: i32, width: u32, height: u32) {
+        if let Some(ref text_input) = self.text_input {
+            text_input.set_rect(Rect::new(x, y, width, height));
+        }
+    }
+
+    /// The UTF-8 text committed this frame.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The text the IME is currently composing, if any.
+    pub fn composition(&self) -> Option<&Composition> {
+        self.composition.as_ref()
+    }
+
+    pub fn is_button_held(&self, button: MouseButton) -> bool {
+        self.held_buttons.contains(&button)
+    }
+
+    pub fn was_button_pressed(&self, button: MouseButton) -> bool {
+        self.pressed_buttons.contains(&button)
+    }
+
+    pub fn was_button_released(&self, button: MouseButton) -> bool {
+        self.released_buttons.contains(&button)
+    }
+
+    /// How many times in a row `button` was clicked, if it was pressed this frame. Returns 0 if
+    /// it wasn't pressed.
+    pub fn click_count(&self, button: MouseButton) -> u8 {
+        if self.was_button_pressed(button) {
+            self.click_counts.get(&button).cloned().unwrap_or(1)
+        } else {
+            0
+        }
+    }
+
+    pub fn was_button_double_clicked(&self, button: MouseButton) -> bool {
+        self.click_count(button) == 2
+    }
+
+    pub fn was_button_triple_clicked(&self, button: MouseButton) -> bool {
+        self.click_count(button) == 3
+    }
+
+    pub fn mouse_pos(&self) -> (i32, i32) {
+        self.mouse_pos
+    }
+
+    /// How far the mouse moved since the last frame.
+    pub fn mouse_delta(&self) -> (i32, i32) {
+        self.mouse_delta
+    }
+
+    pub fn was_mouse_moved(&self) -> bool {
+        self.mouse_moved
+    }
+
+    /// Whether the mouse is over the window. This is false until the mouse first moves or enters
+    /// the window, even if it starts out over it.
+    pub fn is_mouse_in_window(&self) -> bool {
+        self.mouse_in_window
+    }
+
+    /// The horizontal and vertical scroll amounts for this frame. Positive values scroll right
+    /// and away from the user, regardless of the platform's "natural" scrolling setting.
+    ///
+    /// These are currently whole wheel clicks, even for trackpads.
+    pub fn scroll(&self) -> (f32, f32) {
+        self.scroll
+    }
+
+    /// The fingers currently touching any touch device.
+    pub fn touches(&self) -> &[Touch] {
+        self.touches.as_slice()
+    }
+
+    pub fn touch(&self, id: i64) -> Option<&Touch> {
+        self.touches.iter().find(|touch| touch.id == id)
+    }
+
+    /// Every touch that began, moved or ended this frame, in order.
+    pub fn touch_events(&self) -> &[TouchEvent] {
+        self.touch_events.as_slice()
+    }
+
+    /// The number of frames that have started since `Input` was created.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Every input event delivered this frame, in order.
+    pub fn events(&self) -> &[TimedEvent] {
+        self.events.as_slice()
+    }
+
+    /// Events from recent frames before this one, oldest first.
+    pub fn history(&self) -> &VecDeque<TimedEvent> {
+        &self.history
+    }
+
+    /// Set how many previous frames of events to keep in the history.
+    pub fn set_history_frames(&mut self, frames: u64) {
+        self.history_frames = frames;
+    }
+
+    /// The device the player used most recently. Use it to pick between keyboard and controller
+    /// prompts.
+    pub fn last_device(&self) -> InputDevice {
+        self.last_device
+    }
+
+    /// Whether the player switched to a different device this frame.
+    pub fn was_last_device_changed(&self) -> bool {
+        self.last_device_changed
+    }
+
+    pub fn controllers(&self) -> &[Controller] {
+        self.controllers.as_slice()
+    }
+
+    pub fn controller(&self, id: u32) -> Option<&Controller> {
+        self.controllers.iter().find(|controller| controller.id == id)
+    }
+
+    pub fn controllers_mut(&mut self) -> &mut [Controller] {
+        self.controllers.as_mut_slice()
+    }
+
+    pub fn controller_mut(&mut self, id: u32) -> Option<&mut Controller> {
+        self.controllers.iter_mut().find(|controller| controller.id == id)
+    }
+
+    /// Devices that aren't game controllers, like flight sticks and wheels.
+    pub fn joysticks(&self) -> &[Joystick] {
+        self.joysticks.as_slice()
+    }
+
+    pub fn joystick(&self, id: u32) -> Option<&Joystick> {
+        self.joysticks.iter().find(|joystick| joystick.id() == id)
+    }
+
+    pub fn joysticks_mut(&mut self) -> &mut [Joystick] {
+        self.joysticks.as_mut_slice()
+    }
+
+    pub fn joystick_mut(&mut self, id: u32) -> Option<&mut Joystick> {
+        self.joysticks.iter_mut().find(|joystick| joystick.id() == id)
+    }
+
+    /// Press a key as if it came from the keyboard. The scancode is given explicitly, since the
+    /// keyboard layout can't be queried without SDL.
+    pub fn inject_key_down(&mut self, keycode: KeyCode, scancode: ScanCode) {
+        let keymod = self.modifiers.to_sdl();
+        self.handle_keyboard_input(ElementState::Pressed, Some(keycode), Some(scancode), keymod);
+        self.modifiers = self.held_modifiers();
+    }
+
+    pub fn inject_key_up(&mut self, keycode: KeyCode, scancode: ScanCode) {
+        let keymod = self.modifiers.to_sdl();
+        self.handle_keyboard_input(ElementState::Released, Some(keycode), Some(scancode), keymod);
+        self.modifiers = self.held_modifiers();
+    }
+
+    pub fn inject_key_repeat(&mut self, keycode: KeyCode, scancode: ScanCode) {
+        let keymod = self.modifiers.to_sdl();
+        self.handle_key_repeat(Some(keycode), Some(scancode), keymod);
+    }
+
+    /// Type committed text. Like real text input, this is ignored unless text input is active.
+    pub fn inject_text(&mut self, text: &str) {
+        self.handle_text_input(text);
+    }
+
+    pub fn inject_mouse_down(&mut self, button: MouseButton, x: i32, y: i32, clicks: u8) {
+        self.handle_mouse_input(ElementState::Pressed, button, clicks, x, y);
+    }
+
+    pub fn inject_mouse_up(&mut self, button: MouseButton, x: i32, y: i32) {
+        self.handle_mouse_input(ElementState::Released, button, 1, x, y);
+    }
+
+    pub fn inject_mouse_motion(&mut self, x: i32, y: i32) {
+        let (xrel, yrel) = (x - self.mouse_pos.0, y - self.mouse_pos.1);
+        self.handle_mouse_motion(x, y, xrel, yrel);
+    }
+
+    pub fn inject_mouse_wheel(&mut self, x: f32, y: f32) {
+        self.handle_mouse_wheel(x, y, false);
+    }
+
+    pub fn inject_touch(&mut self, phase: TouchPhase, touch: Touch) {
+        self.handle_touch(phase, touch);
+    }
+
+    /// Connect a virtual controller and return its ID. It has no haptics.
+    pub fn inject_controller_added(&mut self, name: &str, guid: &str) -> u32 {
+        let id = self.next_virtual_controller_id;
+        self.next_virtual_controller_id += 1;
+        self.controllers.push(Controller::with_parts(id, guid.into(), name.into(), None,
+                                                     Haptics::new(Box::new(NullHaptic))));
+        self.record(InputEvent::ControllerAdded { controller: id });
+        id
+    }
+
+    pub fn inject_controller_removed(&mut self, id: u32) {
+        self.handle_controller_removed(id as i32);
+    }
+
+    pub fn inject_controller_button_down(&mut self, id: u32, button: Button) {
+        self.handle_controller_button(id as i32, ElementState::Pressed, button);
+    }
+
+    pub fn inject_controller_button_up(&mut self, id: u32, button: Button) {
+        self.handle_controller_button(id as i32, ElementState::Released, button);
+    }
+
+    pub fn inject_controller_axis(&mut self, id: u32, axis: Axis, value: i16) {
+        self.handle_controller_axis(id as i32, axis, value);
+    }
+
+    /// Connect a virtual joystick and return its ID.
+    pub fn inject_joystick_added(&mut self, name: &str, guid: &str, num_axes: u32, num_buttons: u32,
+                                 num_hats: u32) -> u32 {
+        let id = self.next_virtual_controller_id;
+        self.next_virtual_controller_id += 1;
+        self.joysticks.push(Joystick::with_counts(id, guid.into(), name.into(), num_axes, num_buttons,
+                                                  num_hats, 0));
+        self.record(InputEvent::JoystickAdded { joystick: id });
+        id
+    }
+
+    pub fn inject_joystick_removed(&mut self, id: u32) {
+        self.handle_joystick_removed(id as i32);
+    }
+
+    pub fn inject_joystick_button_down(&mut self, id: u32, button: u8) {
+        self.handle_joystick_button(id as i32, ElementState::Pressed, button);
+    }
+
+    pub fn inject_joystick_button_up(&mut self, id: u32, button: u8) {
+        self.handle_joystick_button(id as i32, ElementState::Released, button);
+    }
+
+    pub fn inject_joystick_axis(&mut self, id: u32, axis: u8, value: i16) {
+        self.handle_joystick_axis(id as i32, axis, value);
+    }
+
+    pub fn inject_joystick_hat(&mut self, id: u32, hat: u8, state: HatState) {
+        self.handle_joystick_hat(id as i32, hat, state);
+    }
+
+    /// Work out the modifier state from the held keys, for injected key events.
+    fn held_modifiers(&self) -> Modifiers {
+        let held = |left, right| self.is_key_held(left) || self.is_key_held(right);
+        Modifiers {
+            shift: held(KeyCode::LShift, KeyCode::RShift),
+            ctrl: held(KeyCode::LCtrl, KeyCode::RCtrl),
+            alt: held(KeyCode::LAlt, KeyCode::RAlt),
+            gui: held(KeyCode::LGui, KeyCode::RGui),
+            .. self.modifiers
+        }
+    }
+
+    // FIXME: This shouldn't be accessible outside the crate.
+    pub fn begin_frame(&mut self) {
+        let events = mem::replace(&mut self.events, Vec::new());
+        self.history.extend(events);
+        self.frame += 1;
+        while let Some(oldest_frame) = self.history.front().map(|event| event.frame) {
+            if self.frame - oldest_frame > self.history_frames {
+                self.history.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        self.pressed_keys.clear();
+        self.released_keys.clear();
+        self.repeated_keys.clear();
+        self.typed_keys.clear();
+        self.pressed_scancodes.clear();
+        self.released_scancodes.clear();
+        self.repeated_scancodes.clear();
+        self.text.clear();
+
+        self.pressed_buttons.clear();
+        self.released_buttons.clear();
+        self.mouse_delta = (0, 0);
+        self.mouse_moved = false;
+        self.scroll = (0.0, 0.0);
+
+        self.touch_events.clear();
+        self.last_device_changed = false;
+
+        for controller in &mut self.controllers {
+            controller.begin_frame();
+        }
+        for joystick in &mut self.joysticks {
+            joystick.begin_frame();
+        }
+    }
+
+    /// Set the SDL timestamp for the events handled next.
+    // FIXME: This shouldn't be accessible outside the crate.
+    pub fn set_event_timestamp(&mut self, timestamp: u32) {
+        self.event_timestamp = timestamp;
+    }
+
+    fn record(&mut self, event: InputEvent) {
+        if let Some(device) = InputDevice::from_event(&event) {
+            if device != self.last_device {
+                self.last_device = device;
+                self.last_device_changed = true;
+            }
+        }
+        self.events.push(TimedEvent {
+            timestamp: self.event_timestamp,
+            frame: self.frame,
+            event: event,
+        });
+    }
+
+    // FIXME: This shouldn't be accessible outside the crate.
+    pub fn update_haptics(&mut self, time: &Time) {
+        for controller in &mut self.controllers {
+            controller.haptics.update(time);
+        }
+    }
+
+    // FIXME: This shouldn't be accessible outside the crate.
+    pub fn handle_keyboard_input(&mut self, state: ElementState, keycode: Option<KeyCode>,
+                                 scancode: Option<ScanCode>, keymod: Mod) {
+        self.modifiers = Modifiers::from_sdl(keymod);
+        self.record(match state {
+            ElementState::Pressed => InputEvent::KeyDown { keycode: keycode, scancode: scancode },
+            ElementState::Released => InputEvent::KeyUp { keycode: keycode, scancode: scancode },
+        });
+        if let Some(keycode) = keycode {
+            match state {
+                ElementState::Pressed => self.press_key(keycode),
+                ElementState::Released => self.release_key(keycode),
+            }
+        }
+        if let Some(scancode) = scancode {
+            match state {
+                ElementState::Pressed => self.press_scancode(scancode),
+                ElementState::Released => self.release_scancode(scancode),
+            }
+        }
+    }
+
+    // FIXME: This shouldn't be accessible outside the crate.
+    pub fn handle_key_repeat(&mut self, keycode: Option<KeyCode>, scancode: Option<ScanCode>, keymod: Mod) {
+        self.modifiers = Modifiers::from_sdl(keymod);
+        self.record(InputEvent::KeyRepeat { keycode: keycode, scancode: scancode });
+        if let Some(keycode) = keycode {
+            self.repeated_keys.insert(keycode);
+            if self.text_input_active && EDITING_KEYS.contains(&keycode) {
+                self.typed_keys.insert(keycode);
+            }
+        }
+        if let Some(scancode) = scancode {
+            self.repeated_scancodes.insert(scancode);
+        }
+    }
+
+    // FIXME: This shouldn't be accessible outside the crate.
+    pub fn handle_text_input(&mut self, text: &str) {
+        if self.text_input_active {
+            self.text.push_str(text);
+            self.composition = None;
+            self.record(InputEvent::Text(text.into()));
+        }
+    }
+
+    // FIXME: This shouldn't be accessible outside the crate.
+    pub fn handle_text_editing(&mut self, text: &str, start: i32, length: i32) {
+        if !self.text_input_active {
+            return;
+        }
+        if text.is_empty() {
+            self.composition = None;
+        } else {
+            self.composition = Some(Composition {
+                text: text.into(),
+                cursor: start.max(0) as usize,
+                selection_length: length.max(0) as usize,
+            });
+        }
+    }
+
+    // FIXME: This shouldn't be accessible outside the crate.
+    pub fn handle_mouse_input(&mut self, state: ElementState, button: MouseButton, clicks: u8,
+                              x: i32, y: i32) {
+        self.mouse_pos = (x, y);
+        match state {
+            ElementState::Pressed => {
+                self.press_button(button);
+                self.click_counts.insert(button, clicks);
+                self.record(InputEvent::MouseButtonDown { button: button, clicks: clicks, x: x, y: y });
+            },
+            ElementState::Released => {
+                self.release_button(button);
+                self.record(InputEvent::MouseButtonUp { button: button, x: x, y: y });
+            },
+        }
+    }
+
+    // FIXME: This shouldn't be accessible outside the crate.
+    pub fn handle_mouse_motion(&mut self, x: i32, y: i32, xrel: i32, yrel: i32) {
+        self.mouse_pos = (x, y);
+        self.mouse_delta.0 += xrel;
+        self.mouse_delta.1 += yrel;
+        self.mouse_moved = true;
+        self.mouse_in_window = true;
+        self.record(InputEvent::MouseMotion { x: x, y: y });
+    }
+
+    // FIXME: This shouldn't be accessible outside the crate.
+    pub fn handle_mouse_wheel(&mut self, x: f32, y: f32, flipped: bool) {
+        let (x, y) = if flipped { (-x, -y) } else { (x, y) };
+        self.scroll.0 += x;
+        self.scroll.1 += y;
+        self.record(InputEvent::MouseWheel { x: x, y: y });
+    }
+
+    // FIXME: This shouldn't be accessible outside the crate.
+    pub fn handle_mouse_focus(&mut self, in_window: bool) {
+        self.mouse_in_window = in_window;
+    }
+
+    // FIXME: This shouldn't be accessible outside the crate.
+    pub fn handle_touch(&mut self, phase: TouchPhase, touch: Touch) {
+        let index = self.touches.iter().position(|t| t.id == touch.id && t.device_id == touch.device_id);
+        match (phase, index) {
+            (TouchPhase::Ended, Some(index)) => {
+                self.touches.remove(index);
+            },
+            (TouchPhase::Ended, None) => {},
+            (_, Some(index)) => self.touches[index] = touch,
+            (_, None) => self.touches.push(touch),
+        }
+        let event = TouchEvent {
+            phase: phase,
+            touch: touch,
+        };
+        self.touch_events.push(event);
+        self.record(InputEvent::Touch(event));
+    }
+
+    // NOTE: `index` is a device index, not an instance ID like in the other controller events.
+    pub fn handle_controller_added(&mut self, index: i32) {
+        if index >= 0 {
+            // SDL can report a controller that was already opened, e.g. one that was connected
+            // at startup.
+            if let Some(controller) = self.open_controller(index as u32) {
+                self.joysticks.retain(|joystick| joystick.id() != controller.id);
+                if self.controllers.iter().all(|c| c.id != controller.id) {
+                    self.controllers.push(controller);
+                }
+            }
+        } else {
+            // TODO: Log error?
+        }
+    }
+
+    pub fn handle_controller_removed(&mut self, id: i32) {
+        let index = self.controllers.iter().enumerate()
+            .find(|&(_, controller)| controller.id == id as u32)
+            .map(|(i, _)| i);
+        if let Some(index) = index {
+            self.controllers.remove(index);
+            self.record(InputEvent::ControllerRemoved { controller: id as u32 });
+        } else {
+            // TODO: Log error?
+        }
+    }
+
+    pub fn handle_controller_remapped(&mut self, id: i32) {
+        let controller = self.controllers.iter_mut()
+            .find(|controller| controller.id == id as u32);
+        if let Some(controller) = controller {
+            controller.refresh_state();
+        } else {
+            // TODO: Log error?
+        }
+    }
+
+    pub fn handle_controller_axis(&mut self, id: i32, axis: Axis, value: i16) {
+        let controller = self.controllers.iter_mut()
+            .find(|controller| controller.id == id as u32);
+        if let Some(controller) = controller {
+            controller.set_axis_position(axis, value);
+        } else {
+            // TODO: Log error?
+            return;
+        }
+        self.record(InputEvent::ControllerAxis { controller: id as u32, axis: axis, value: value });
+    }
+
+    pub fn handle_controller_button(&mut self, id: i32, state: ElementState, button: Button) {
+        let controller = self.controllers.iter_mut()
+            .find(|controller| controller.id == id as u32);
+        if let Some(controller) = controller {
+            match state {
+                ElementState::Pressed => controller.press_button(button),
+                ElementState::Released => controller.release_button(button),
+            }
+        } else {
+            // TODO: Log error?
+            return;
+        }
+        self.record(match state {
+            ElementState::Pressed => InputEvent::ControllerButtonDown { controller: id as u32, button: button },
+            ElementState::Released => InputEvent::ControllerButtonUp { controller: id as u32, button: button },
+        });
+    }
+
+    // NOTE: `index` is a device index, not an instance ID like in the other joystick events.
+    pub fn handle_joystick_added(&mut self, index: i32) {
+        // Game controllers also show up as joysticks, but get their own events.
+        if index < 0 || self.is_game_controller(index as u32) {
+            return;
+        }
+        if let Some(joystick) = self.open_joystick(index as u32) {
+            if self.joysticks.iter().all(|j| j.id() != joystick.id()) {
+                self.record(InputEvent::JoystickAdded { joystick: joystick.id() });
+                self.joysticks.push(joystick);
+            }
+        }
+    }
+
+    pub fn handle_joystick_removed(&mut self, id: i32) {
+        let index = self.joysticks.iter().position(|joystick| joystick.id() == id as u32);
+        if let Some(index) = index {
+            self.joysticks.remove(index);
+            self.record(InputEvent::JoystickRemoved { joystick: id as u32 });
+        }
+    }
+
+    pub fn handle_joystick_axis(&mut self, id: i32, axis: u8, value: i16) {
+        if let Some(joystick) = self.joystick_mut(id as u32) {
+            joystick.set_axis_position(axis, value);
+        } else {
+            return;
+        }
+        self.record(InputEvent::JoystickAxis { joystick: id as u32, axis: axis, value: value });
+    }
+
+    pub fn handle_joystick_button(&mut self, id: i32, state: ElementState, button: u8) {
+        if let Some(joystick) = self.joystick_mut(id as u32) {
+            match state {
+                ElementState::Pressed => joystick.press_button(button),
+                ElementState::Released => joystick.release_button(button),
+            }
+        } else {
+            return;
+        }
+        self.record(match state {
+            ElementState::Pressed => InputEvent::JoystickButtonDown { joystick: id as u32, button: button },
+            ElementState::Released => InputEvent::JoystickButtonUp { joystick: id as u32, button: button },
+        });
+    }
+
+    pub fn handle_joystick_hat(&mut self, id: i32, hat: u8, state: HatState) {
+        if let Some(joystick) = self.joystick_mut(id as u32) {
+            joystick.set_hat(hat, state);
+        } else {
+            return;
+        }
+        self.record(InputEvent::JoystickHat { joystick: id as u32, hat: hat, state: state });
+    }
+
+    pub fn handle_joystick_ball(&mut self, id: i32, ball: u8, xrel: i16, yrel: i16) {
+        if let Some(joystick) = self.joystick_mut(id as u32) {
+            joystick.add_ball_motion(ball, xrel as i32, yrel as i32);
+        } else {
+            return;
+        }
+        self.record(InputEvent::JoystickBall { joystick: id as u32, ball: ball, xrel: xrel, yrel: yrel });
+    }
+
+    fn press_key(&mut self, keycode: KeyCode) {
+        self.held_keys.insert(keycode);
+        self.pressed_keys.insert(keycode);
+    }
+
+    fn release_key(&mut self, keycode: KeyCode) {
+        self.held_keys.remove(&keycode);
+        self.released_keys.insert(keycode);
+    }
+
+    fn press_scancode(&mut self, scancode: ScanCode) {
+        self.held_scancodes.insert(scancode);
+        self.pressed_scancodes.insert(scancode);
+    }
+
+    fn release_scancode(&mut self, scancode: ScanCode) {
+        self.held_scancodes.remove(&scancode);
+        self.released_scancodes.insert(scancode);
+    }
+
+    fn press_button(&mut self, button: MouseButton) {
+        self.held_buttons.insert(button);
+        self.pressed_buttons.insert(button);
+    }
+
+    fn release_button(&mut self, button: MouseButton) {
+        self.held_buttons.remove(&button);
+        self.released_buttons.insert(button);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ActionMap, AxisDirection, Binding, Button, Composition, HatState, Input, InputDevice, InputEvent,
+                KeyCode, MouseButton, ScanCode};
+
+    #[test]
+    fn injected_keys_track_frames() {
+        let mut input = Input::headless();
+        input.inject_key_down(KeyCode::Space, ScanCode::Space);
+        assert!(input.was_key_pressed(KeyCode::Space));
+        assert!(input.is_key_held(KeyCode::Space));
+
+        input.begin_frame();
+        assert!(!input.was_key_pressed(KeyCode::Space));
+        assert!(input.is_key_held(KeyCode::Space));
+        assert_eq!(input.history().len(), 1);
+
+        input.inject_key_up(KeyCode::Space, ScanCode::Space);
+        assert!(input.was_key_released(KeyCode::Space));
+        assert!(!input.is_key_held(KeyCode::Space));
+    }
+
+    #[test]
+    fn injected_keys_track_scancodes() {
+        let mut input = Input::headless();
+        // An AZERTY layout, where the key labelled A sits where Q is on QWERTY.
+        input.inject_key_down(KeyCode::A, ScanCode::Q);
+        assert!(input.was_scancode_pressed(ScanCode::Q));
+        assert!(input.is_scancode_held(ScanCode::Q));
+        assert!(!input.is_scancode_held(ScanCode::A));
+
+        input.begin_frame();
+        input.inject_key_repeat(KeyCode::A, ScanCode::Q);
+        assert!(!input.was_scancode_pressed(ScanCode::Q));
+        assert!(input.was_scancode_repeated(ScanCode::Q));
+
+        input.inject_key_up(KeyCode::A, ScanCode::Q);
+        assert!(input.was_scancode_released(ScanCode::Q));
+        assert!(!input.is_scancode_held(ScanCode::Q));
+    }
+
+    #[test]
+    fn text_is_dropped_while_text_input_is_inactive() {
+        let mut input = Input::headless();
+        input.handle_text_input("a");
+        input.handle_text_editing("k", 1, 0);
+        assert_eq!(input.text(), "");
+        assert_eq!(input.composition(), None);
+        assert!(input.events().is_empty());
+
+        input.start_text_input();
+        input.handle_text_input("b");
+        assert_eq!(input.text(), "b");
+
+        input.stop_text_input();
+        input.handle_text_input("c");
+        assert_eq!(input.text(), "b");
+    }
+
+    #[test]
+    fn composition_clears_on_commit_and_stop() {
+        let mut input = Input::headless();
+        input.start_text_input();
+        input.handle_text_editing("ka", 2, 0);
+        assert_eq!(input.composition(), Some(&Composition { text: "ka".into(), cursor: 2, selection_length: 0 }));
+
+        input.handle_text_input("か");
+        assert_eq!(input.composition(), None);
+        assert_eq!(input.text(), "か");
+
+        input.handle_text_editing("ki", 2, 0);
+        input.stop_text_input();
+        assert_eq!(input.composition(), None);
+    }
+
+    #[test]
+    fn repeated_editing_keys_are_typed_only_during_text_input() {
+        let mut input = Input::headless();
+        input.inject_key_down(KeyCode::Backspace, ScanCode::Backspace);
+        input.begin_frame();
+        input.inject_key_repeat(KeyCode::Backspace, ScanCode::Backspace);
+        assert!(!input.was_key_typed(KeyCode::Backspace));
+
+        input.start_text_input();
+        input.begin_frame();
+        input.inject_key_repeat(KeyCode::Backspace, ScanCode::Backspace);
+        input.inject_key_repeat(KeyCode::A, ScanCode::A);
+        assert!(input.was_key_typed(KeyCode::Backspace));
+        assert!(!input.was_key_typed(KeyCode::A));
+
+        input.begin_frame();
+        assert!(!input.was_key_typed(KeyCode::Backspace));
+    }
+
+    #[test]
+    fn flipped_mouse_wheel_is_negated() {
+        let mut input = Input::headless();
+        input.handle_mouse_wheel(1.0, 2.0, false);
+        input.handle_mouse_wheel(2.0, 1.0, true);
+        assert_eq!(input.scroll(), (-1.0, 1.0));
+        assert_eq!(input.events()[1].event, InputEvent::MouseWheel { x: -2.0, y: -1.0 });
+
+        input.begin_frame();
+        assert_eq!(input.scroll(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn mouse_motion_accumulates_until_next_frame() {
+        let mut input = Input::headless();
+        assert!(!input.is_mouse_in_window());
+        input.inject_mouse_motion(10, 5);
+        input.inject_mouse_motion(15, 3);
+        assert_eq!(input.mouse_pos(), (15, 3));
+        assert_eq!(input.mouse_delta(), (15, 3));
+        assert!(input.was_mouse_moved());
+        assert!(input.is_mouse_in_window());
+
+        input.begin_frame();
+        assert_eq!(input.mouse_delta(), (0, 0));
+        assert!(!input.was_mouse_moved());
+        assert_eq!(input.mouse_pos(), (15, 3));
+
+        input.handle_mouse_focus(false);
+        assert!(!input.is_mouse_in_window());
+        input.handle_mouse_focus(true);
+        assert!(input.is_mouse_in_window());
+    }
+
+    #[test]
+    fn clicks_are_counted() {
+        let mut input = Input::headless();
+        input.inject_mouse_down(MouseButton::Left, 0, 0, 3);
+        assert_eq!(input.click_count(MouseButton::Left), 3);
+        assert!(input.was_button_triple_clicked(MouseButton::Left));
+        assert!(!input.was_button_double_clicked(MouseButton::Left));
+
+        input.begin_frame();
+        assert_eq!(input.click_count(MouseButton::Left), 0);
+        assert!(!input.was_button_triple_clicked(MouseButton::Left));
+    }
+
+    #[test]
+    fn injected_controllers_drive_actions() {
+        let mut input = Input::headless();
+        let mut actions = ActionMap::new();
+        actions.bind("jump", Binding::ControllerButton(Button::A));
+        actions.bind("shoot", Binding::MouseButton(MouseButton::Left));
+
+        let id = input.inject_controller_added("Virtual Pad", "0300000000000000000000000000000");
+        assert_eq!(input.events()[0].event, InputEvent::ControllerAdded { controller: id });
+        input.inject_controller_button_down(id, Button::A);
+        input.inject_mouse_down(MouseButton::Left, 10, 20, 2);
+        assert!(actions.was_action_pressed(&input, "jump"));
+        assert!(actions.was_action_pressed(&input, "shoot"));
+        assert!(input.was_button_double_clicked(MouseButton::Left));
+        assert_eq!(input.mouse_pos(), (10, 20));
+
+        assert_eq!(input.last_device(), InputDevice::KeyboardMouse);
+        assert!(input.was_last_device_changed());
+
+        input.begin_frame();
+        input.inject_controller_button_up(id, Button::A);
+        assert!(!input.was_last_device_changed());
+        input.inject_controller_removed(id);
+        assert!(input.controllers().is_empty());
+        assert_eq!(input.events().last().map(|event| &event.event),
+                   Some(&InputEvent::ControllerRemoved { controller: id }));
+        assert!(!actions.is_action_held(&input, "jump"));
+    }
+
+    #[test]
+    fn injected_joysticks_drive_actions() {
+        let mut input = Input::headless();
+        let mut actions = ActionMap::new();
+        actions.bind("up", Binding::JoystickHat(0, HatState::Up));
+        actions.bind("brake", Binding::JoystickAxis(2, AxisDirection::Positive));
+
+        let id = input.inject_joystick_added("Arcade Stick", "0300000000000000000000000000001", 3, 8, 1);
+        input.inject_joystick_hat(id, 0, HatState::LeftUp);
+        input.inject_joystick_axis(id, 2, 32767);
+        assert!(actions.was_action_pressed(&input, "up"));
+        assert!(actions.is_action_held(&input, "brake"));
+        assert_eq!(input.joystick(id).map(|joystick| joystick.name()), Some("Arcade Stick"));
+
+        input.begin_frame();
+        input.inject_joystick_hat(id, 0, HatState::Left);
+        assert!(actions.was_action_released(&input, "up"));
+    }
+}
@@ -0,0 +1,296 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use input::{Axis, Button, HatState, InputEvent, KeyCode, MouseButton, TimedEvent, Touch, TouchPhase};
+
+
+/// Receives input events as they happen, in the style of libGDX's `InputProcessor`.
+///
+/// Each method returns whether the event was consumed. A consumed event isn't passed on to
+/// processors further down an `InputMultiplexer`. Polling `Input` still sees every event.
+pub trait InputProcessor {
+    fn key_down(&mut self, _keycode: KeyCode) -> bool { false }
+    fn key_up(&mut self, _keycode: KeyCode) -> bool { false }
+    fn key_repeated(&mut self, _keycode: KeyCode) -> bool { false }
+    /// Text typed while text input is active.
+    fn key_typed(&mut self, _text: &str) -> bool { false }
+
+    fn mouse_down(&mut self, _x: i32, _y: i32, _button: MouseButton) -> bool { false }
+    fn mouse_up(&mut self, _x: i32, _y: i32, _button: MouseButton) -> bool { false }
+    fn mouse_moved(&mut self, _x: i32, _y: i32) -> bool { false }
+    fn scrolled(&mut self, _x: f32, _y: f32) -> bool { false }
+
+    fn touch_down(&mut self, _touch: &Touch) -> bool { false }
+    fn touch_moved(&mut self, _touch: &Touch) -> bool { false }
+    fn touch_up(&mut self, _touch: &Touch) -> bool { false }
+
+    fn controller_button_down(&mut self, _controller: u32, _button: Button) -> bool { false }
+    fn controller_button_up(&mut self, _controller: u32, _button: Button) -> bool { false }
+    fn controller_axis_moved(&mut self, _controller: u32, _axis: Axis, _value: i16) -> bool { false }
+    fn controller_connected(&mut self, _controller: u32) -> bool { false }
+    fn controller_disconnected(&mut self, _controller: u32) -> bool { false }
+
+    fn joystick_connected(&mut self, _joystick: u32) -> bool { false }
+    fn joystick_disconnected(&mut self, _joystick: u32) -> bool { false }
+    fn joystick_button_down(&mut self, _joystick: u32, _button: u8) -> bool { false }
+    fn joystick_button_up(&mut self, _joystick: u32, _button: u8) -> bool { false }
+    fn joystick_axis_moved(&mut self, _joystick: u32, _axis: u8, _value: i16) -> bool { false }
+    fn joystick_hat_moved(&mut self, _joystick: u32, _hat: u8, _state: HatState) -> bool { false }
+    fn joystick_ball_moved(&mut self, _joystick: u32, _ball: u8, _xrel: i16, _yrel: i16) -> bool { false }
+}
+
+/// Send an event to the matching method of `processor`.
+pub fn dispatch_event<P: InputProcessor + ?Sized>(processor: &mut P, event: &InputEvent) -> bool {
+    match *event {
+        InputEvent::KeyDown { keycode: Some(keycode), .. } => processor.key_down(keycode),
+        InputEvent::KeyUp { keycode: Some(keycode), .. } => processor.key_up(keycode),
+        InputEvent::KeyRepeat { keycode: Some(keycode), .. } => processor.key_repeated(keycode),
+        InputEvent::KeyDown { .. } | InputEvent::KeyUp { .. } | InputEvent::KeyRepeat { .. } => false,
+        InputEvent::Text(ref text) => processor.key_typed(text),
+        InputEvent::MouseButtonDown { button, x, y, .. } => processor.mouse_down(x, y, button),
+        InputEvent::MouseButtonUp { button, x, y } => processor.mouse_up(x, y, button),
+        InputEvent::MouseMotion { x, y } => processor.mouse_moved(x, y),
+        InputEvent::MouseWheel { x, y } => processor.scrolled(x, y),
+        InputEvent::Touch(ref event) => match event.phase {
+            TouchPhase::Began => processor.touch_down(&event.touch),
+            TouchPhase::Moved => processor.touch_moved(&event.touch),
+            TouchPhase::Ended => processor.touch_up(&event.touch),
+        },
+        InputEvent::ControllerButtonDown { controller, button } =>
+            processor.controller_button_down(controller, button),
+        InputEvent::ControllerButtonUp { controller, button } =>
+            processor.controller_button_up(controller, button),
+        InputEvent::ControllerAxis { controller, axis, value } =>
+            processor.controller_axis_moved(controller, axis, value),
+        InputEvent::ControllerAdded { controller } => processor.controller_connected(controller),
+        InputEvent::ControllerRemoved { controller } => processor.controller_disconnected(controller),
+        InputEvent::JoystickAdded { joystick } => processor.joystick_connected(joystick),
+        InputEvent::JoystickRemoved { joystick } => processor.joystick_disconnected(joystick),
+        InputEvent::JoystickButtonDown { joystick, button } => processor.joystick_button_down(joystick, button),
+        InputEvent::JoystickButtonUp { joystick, button } => processor.joystick_button_up(joystick, button),
+        InputEvent::JoystickAxis { joystick, axis, value } => processor.joystick_axis_moved(joystick, axis, value),
+        InputEvent::JoystickHat { joystick, hat, state } => processor.joystick_hat_moved(joystick, hat, state),
+        InputEvent::JoystickBall { joystick, ball, xrel, yrel } =>
+            processor.joystick_ball_moved(joystick, ball, xrel, yrel),
+    }
+}
+
+/// Lets the game keep a handle to a processor it has registered.
+impl<P: InputProcessor + ?Sized> InputProcessor for Rc<RefCell<P>> {
+    fn key_down(&mut self, keycode: KeyCode) -> bool { self.borrow_mut().key_down(keycode) }
+    fn key_up(&mut self, keycode: KeyCode) -> bool { self.borrow_mut().key_up(keycode) }
+    fn key_repeated(&mut self, keycode: KeyCode) -> bool { self.borrow_mut().key_repeated(keycode) }
+    fn key_typed(&mut self, text: &str) -> bool { self.borrow_mut().key_typed(text) }
+
+    fn mouse_down(&mut self, x: i32, y: i32, button: MouseButton) -> bool { self.borrow_mut().mouse_down(x, y, button) }
+    fn mouse_up(&mut self, x: i32, y: i32, button: MouseButton) -> bool { self.borrow_mut().mouse_up(x, y, button) }
+    fn mouse_moved(&mut self, x: i32, y: i32) -> bool { self.borrow_mut().mouse_moved(x, y) }
+    fn scrolled(&mut self, x: f32, y: f32) -> bool { self.borrow_mut().scrolled(x, y) }
+
+    fn touch_down(&mut self, touch: &Touch) -> bool { self.borrow_mut().touch_down(touch) }
+    fn touch_moved(&mut self, touch: &Touch) -> bool { self.borrow_mut().touch_moved(touch) }
+    fn touch_up(&mut self, touch: &Touch) -> bool { self.borrow_mut().touch_up(touch) }
+
+    fn controller_button_down(&mut self, controller: u32, button: Button) -> bool {
+        self.borrow_mut().controller_button_down(controller, button)
+    }
+    fn controller_button_up(&mut self, controller: u32, button: Button) -> bool {
+        self.borrow_mut().controller_button_up(controller, button)
+    }
+    fn controller_axis_moved(&mut self, controller: u32, axis: Axis, value: i16) -> bool {
+        self.borrow_mut().controller_axis_moved(controller, axis, value)
+    }
+    fn controller_connected(&mut self, controller: u32) -> bool {
+        self.borrow_mut().controller_connected(controller)
+    }
+    fn controller_disconnected(&mut self, controller: u32) -> bool {
+        self.borrow_mut().controller_disconnected(controller)
+    }
+
+    fn joystick_connected(&mut self, joystick: u32) -> bool { self.borrow_mut().joystick_connected(joystick) }
+    fn joystick_disconnected(&mut self, joystick: u32) -> bool { self.borrow_mut().joystick_disconnected(joystick) }
+    fn joystick_button_down(&mut self, joystick: u32, button: u8) -> bool {
+        self.borrow_mut().joystick_button_down(joystick, button)
+    }
+    fn joystick_button_up(&mut self, joystick: u32, button: u8) -> bool {
+        self.borrow_mut().joystick_button_up(joystick, button)
+    }
+    fn joystick_axis_moved(&mut self, joystick: u32, axis: u8, value: i16) -> bool {
+        self.borrow_mut().joystick_axis_moved(joystick, axis, value)
+    }
+    fn joystick_hat_moved(&mut self, joystick: u32, hat: u8, state: HatState) -> bool {
+        self.borrow_mut().joystick_hat_moved(joystick, hat, state)
+    }
+    fn joystick_ball_moved(&mut self, joystick: u32, ball: u8, xrel: i16, yrel: i16) -> bool {
+        self.borrow_mut().joystick_ball_moved(joystick, ball, xrel, yrel)
+    }
+}
+
+/// Passes events through a list of processors in priority order, stopping at the first one that
+/// consumes it.
+#[derive(Default)]
+pub struct InputMultiplexer {
+    processors: Vec<Box<InputProcessor>>,
+}
+
+impl InputMultiplexer {
+    pub fn new() -> Self {
+        InputMultiplexer {
+            processors: Vec::new(),
+        }
+    }
+
+    /// Add a processor below all existing ones.
+    pub fn add_processor(&mut self, processor: Box<InputProcessor>) {
+        self.processors.push(processor);
+    }
+
+    /// Insert a processor at `index`, where 0 sees events first.
+    pub fn insert_processor(&mut self, index: usize, processor: Box<InputProcessor>) {
+        self.processors.insert(index, processor);
+    }
+
+    pub fn remove_processor(&mut self, index: usize) -> Box<InputProcessor> {
+        self.processors.remove(index)
+    }
+
+    pub fn clear(&mut self) {
+        self.processors.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.processors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.processors.is_empty()
+    }
+
+    /// Dispatch a single event. Returns whether any processor consumed it.
+    pub fn dispatch(&mut self, event: &InputEvent) -> bool {
+        self.processors.iter_mut().any(|processor| dispatch_event(&mut **processor, event))
+    }
+
+    /// Dispatch a frame's worth of events in order.
+    pub fn process(&mut self, events: &[TimedEvent]) {
+        for timed_event in events {
+            self.dispatch(&timed_event.event);
+        }
+    }
+}
+
+impl InputProcessor for InputMultiplexer {
+    fn key_down(&mut self, keycode: KeyCode) -> bool {
+        self.processors.iter_mut().any(|processor| processor.key_down(keycode))
+    }
+    fn key_up(&mut self, keycode: KeyCode) -> bool {
+        self.processors.iter_mut().any(|processor| processor.key_up(keycode))
+    }
+    fn key_repeated(&mut self, keycode: KeyCode) -> bool {
+        self.processors.iter_mut().any(|processor| processor.key_repeated(keycode))
+    }
+    fn key_typed(&mut self, text: &str) -> bool {
+        self.processors.iter_mut().any(|processor| processor.key_typed(text))
+    }
+
+    fn mouse_down(&mut self, x: i32, y: i32, button: MouseButton) -> bool {
+        self.processors.iter_mut().any(|processor| processor.mouse_down(x, y, button))
+    }
+    fn mouse_up(&mut self, x: i32, y: i32, button: MouseButton) -> bool {
+        self.processors.iter_mut().any(|processor| processor.mouse_up(x, y, button))
+    }
+    fn mouse_moved(&mut self, x: i32, y: i32) -> bool {
+        self.processors.iter_mut().any(|processor| processor.mouse_moved(x, y))
+    }
+    fn scrolled(&mut self, x: f32, y: f32) -> bool {
+        self.processors.iter_mut().any(|processor| processor.scrolled(x, y))
+    }
+
+    fn touch_down(&mut self, touch: &Touch) -> bool {
+        self.processors.iter_mut().any(|processor| processor.touch_down(touch))
+    }
+    fn touch_moved(&mut self, touch: &Touch) -> bool {
+        self.processors.iter_mut().any(|processor| processor.touch_moved(touch))
+    }
+    fn touch_up(&mut self, touch: &Touch) -> bool {
+        self.processors.iter_mut().any(|processor| processor.touch_up(touch))
+    }
+
+    fn controller_button_down(&mut self, controller: u32, button: Button) -> bool {
+        self.processors.iter_mut().any(|processor| processor.controller_button_down(controller, button))
+    }
+    fn controller_button_up(&mut self, controller: u32, button: Button) -> bool {
+        self.processors.iter_mut().any(|processor| processor.controller_button_up(controller, button))
+    }
+    fn controller_axis_moved(&mut self, controller: u32, axis: Axis, value: i16) -> bool {
+        self.processors.iter_mut().any(|processor| processor.controller_axis_moved(controller, axis, value))
+    }
+    fn controller_connected(&mut self, controller: u32) -> bool {
+        self.processors.iter_mut().any(|processor| processor.controller_connected(controller))
+    }
+    fn controller_disconnected(&mut self, controller: u32) -> bool {
+        self.processors.iter_mut().any(|processor| processor.controller_disconnected(controller))
+    }
+
+    fn joystick_connected(&mut self, joystick: u32) -> bool {
+        self.processors.iter_mut().any(|processor| processor.joystick_connected(joystick))
+    }
+    fn joystick_disconnected(&mut self, joystick: u32) -> bool {
+        self.processors.iter_mut().any(|processor| processor.joystick_disconnected(joystick))
+    }
+    fn joystick_button_down(&mut self, joystick: u32, button: u8) -> bool {
+        self.processors.iter_mut().any(|processor| processor.joystick_button_down(joystick, button))
+    }
+    fn joystick_button_up(&mut self, joystick: u32, button: u8) -> bool {
+        self.processors.iter_mut().any(|processor| processor.joystick_button_up(joystick, button))
+    }
+    fn joystick_axis_moved(&mut self, joystick: u32, axis: u8, value: i16) -> bool {
+        self.processors.iter_mut().any(|processor| processor.joystick_axis_moved(joystick, axis, value))
+    }
+    fn joystick_hat_moved(&mut self, joystick: u32, hat: u8, state: HatState) -> bool {
+        self.processors.iter_mut().any(|processor| processor.joystick_hat_moved(joystick, hat, state))
+    }
+    fn joystick_ball_moved(&mut self, joystick: u32, ball: u8, xrel: i16, yrel: i16) -> bool {
+        self.processors.iter_mut().any(|processor| processor.joystick_ball_moved(joystick, ball, xrel, yrel))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use input::{InputEvent, MouseButton};
+    use super::{InputMultiplexer, InputProcessor};
+
+    struct Layer {
+        consumes: bool,
+        clicks: u32,
+    }
+
+    impl InputProcessor for Layer {
+        fn mouse_down(&mut self, _x: i32, _y: i32, _button: MouseButton) -> bool {
+            self.clicks += 1;
+            self.consumes
+        }
+    }
+
+    #[test]
+    fn consumed_events_stop_propagating() {
+        let ui = Rc::new(RefCell::new(Layer { consumes: true, clicks: 0 }));
+        let game = Rc::new(RefCell::new(Layer { consumes: false, clicks: 0 }));
+
+        let mut multiplexer = InputMultiplexer::new();
+        multiplexer.add_processor(Box::new(game.clone()));
+        multiplexer.insert_processor(0, Box::new(ui.clone()));
+
+        let click = InputEvent::MouseButtonDown { button: MouseButton::Left, clicks: 1, x: 10, y: 10 };
+        assert!(multiplexer.dispatch(&click));
+        assert_eq!(ui.borrow().clicks, 1);
+        assert_eq!(game.borrow().clicks, 0);
+
+        ui.borrow_mut().consumes = false;
+        assert!(!multiplexer.dispatch(&click));
+        assert_eq!(game.borrow().clicks, 1);
+    }
+}
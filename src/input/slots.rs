@@ -0,0 +1,197 @@
+use input::{Button, Input};
+
+
+/// How controllers get assigned to free player slots.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JoinMode {
+    /// New controllers take the first free slot as soon as they're connected.
+    Automatic,
+    /// New controllers only take a slot once this button is pressed on them, e.g. "press start
+    /// to join".
+    PressToJoin(Button),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SlotEvent {
+    /// A new controller took a free slot.
+    Joined { slot: usize, controller: u32 },
+    /// A slot's controller was unplugged. The slot stays reserved for it.
+    Disconnected { slot: usize, controller: u32 },
+    /// The controller that was unplugged from a slot was plugged back in. Its instance ID may be
+    /// different from before.
+    Reconnected { slot: usize, controller: u32 },
+    /// A slot was freed with `PlayerSlots::release`.
+    Released { slot: usize },
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlayerSlot {
+    controller: Option<u32>,
+    guid: Option<String>,
+}
+
+impl PlayerSlot {
+    /// The instance ID of the slot's controller, if it is connected.
+    pub fn controller(&self) -> Option<u32> {
+        self.controller
+    }
+
+    /// The GUID of the controller the slot belongs to, even while it is disconnected.
+    pub fn guid(&self) -> Option<&str> {
+        self.guid.as_ref().map(|guid| guid.as_str())
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.controller.is_some()
+    }
+
+    /// Whether the slot belongs to a controller, connected or not.
+    pub fn is_occupied(&self) -> bool {
+        self.guid.is_some()
+    }
+}
+
+/// Keeps each player on the same controller while controllers are plugged and unplugged.
+///
+/// Unlike `Input::controllers`, slots never move around. A slot whose controller disconnects
+/// waits for a controller with the same GUID to come back.
+pub struct PlayerSlots {
+    slots: Vec<PlayerSlot>,
+    join_mode: JoinMode,
+}
+
+impl PlayerSlots {
+    pub fn new(num_slots: usize) -> Self {
+        PlayerSlots {
+            slots: vec![PlayerSlot::default(); num_slots],
+            join_mode: JoinMode::Automatic,
+        }
+    }
+
+    pub fn with_join_mode(mut self, join_mode: JoinMode) -> Self {
+        self.join_mode = join_mode;
+        self
+    }
+
+    pub fn join_mode(&self) -> JoinMode {
+        self.join_mode
+    }
+
+    pub fn set_join_mode(&mut self, join_mode: JoinMode) {
+        self.join_mode = join_mode;
+    }
+
+    pub fn slots(&self) -> &[PlayerSlot] {
+        self.slots.as_slice()
+    }
+
+    pub fn slot(&self, slot: usize) -> Option<&PlayerSlot> {
+        self.slots.get(slot)
+    }
+
+    /// The instance ID of the controller in `slot`, if it is connected.
+    pub fn controller(&self, slot: usize) -> Option<u32> {
+        self.slots.get(slot).and_then(|slot| slot.controller)
+    }
+
+    pub fn slot_for_controller(&self, controller: u32) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.controller == Some(controller))
+    }
+
+    /// Free a slot so another controller can join it.
+    pub fn release(&mut self, slot: usize) -> Option<SlotEvent> {
+        match self.slots.get_mut(slot) {
+            Some(player_slot) if player_slot.is_occupied() => {
+                *player_slot = PlayerSlot::default();
+                Some(SlotEvent::Released { slot: slot })
+            },
+            _ => None,
+        }
+    }
+
+    /// Match slots up with the controllers in `input`. Call this once per frame and react to
+    /// the returned events, e.g. by pausing when a player disconnects.
+    pub fn update(&mut self, input: &Input) -> Vec<SlotEvent> {
+        let join_button = match self.join_mode {
+            JoinMode::Automatic => None,
+            JoinMode::PressToJoin(button) => Some(button),
+        };
+        let controllers: Vec<(u32, &str, bool)> = input.controllers().iter()
+            .map(|controller| {
+                let join_pressed = join_button.map(|button| controller.was_button_pressed(button))
+                    .unwrap_or(true);
+                (controller.id(), controller.guid(), join_pressed)
+            })
+            .collect();
+        self.update_controllers(&controllers)
+    }
+
+    /// Takes `(instance ID, GUID, join pressed)` for every connected controller.
+    fn update_controllers(&mut self, controllers: &[(u32, &str, bool)]) -> Vec<SlotEvent> {
+        let mut events = Vec::new();
+
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if let Some(id) = slot.controller {
+                if controllers.iter().all(|&(controller, _, _)| controller != id) {
+                    slot.controller = None;
+                    events.push(SlotEvent::Disconnected { slot: index, controller: id });
+                }
+            }
+        }
+
+        for &(id, guid, join_pressed) in controllers {
+            if self.slot_for_controller(id).is_some() {
+                continue;
+            }
+
+            let reconnect = self.slots.iter()
+                .position(|slot| !slot.is_connected() && slot.guid() == Some(guid));
+            if let Some(index) = reconnect {
+                self.slots[index].controller = Some(id);
+                events.push(SlotEvent::Reconnected { slot: index, controller: id });
+                continue;
+            }
+
+            if !join_pressed {
+                continue;
+            }
+            if let Some(index) = self.slots.iter().position(|slot| !slot.is_occupied()) {
+                self.slots[index] = PlayerSlot {
+                    controller: Some(id),
+                    guid: Some(guid.into()),
+                };
+                events.push(SlotEvent::Joined { slot: index, controller: id });
+            }
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use input::Button;
+    use super::{JoinMode, PlayerSlots, SlotEvent};
+
+    #[test]
+    fn players_keep_their_slots_across_reconnects() {
+        let mut slots = PlayerSlots::new(2);
+        slots.update_controllers(&[(0, "pad-a", true), (1, "pad-b", true)]);
+        assert_eq!(slots.controller(1), Some(1));
+
+        let events = slots.update_controllers(&[(1, "pad-b", true)]);
+        assert_eq!(events, vec![SlotEvent::Disconnected { slot: 0, controller: 0 }]);
+        assert_eq!(slots.controller(1), Some(1));
+
+        let events = slots.update_controllers(&[(1, "pad-b", true), (2, "pad-a", true)]);
+        assert_eq!(events, vec![SlotEvent::Reconnected { slot: 0, controller: 2 }]);
+    }
+
+    #[test]
+    fn press_to_join() {
+        let mut slots = PlayerSlots::new(4).with_join_mode(JoinMode::PressToJoin(Button::Start));
+        assert!(slots.update_controllers(&[(3, "pad", false)]).is_empty());
+        assert_eq!(slots.update_controllers(&[(3, "pad", true)]),
+                   vec![SlotEvent::Joined { slot: 0, controller: 3 }]);
+    }
+}
@@ -0,0 +1,379 @@
+use std::f32::consts::PI;
+
+use input::Input;
+use time::Time;
+
+
+/// A finger on a touch device. Positions are normalized to 0..1 across the device.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Touch {
+    pub id: i64,
+    pub device_id: i64,
+    pub x: f32,
+    pub y: f32,
+    pub pressure: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TouchPhase {
+    Began,
+    Moved,
+    Ended,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TouchEvent {
+    pub phase: TouchPhase,
+    pub touch: Touch,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gesture {
+    /// One or more quick taps in the same spot. `count` is 2 for a double tap.
+    Tap { x: f32, y: f32, count: u32 },
+    LongPress { x: f32, y: f32 },
+    /// A single finger dragged by `(dx, dy)`.
+    Pan { x: f32, y: f32, dx: f32, dy: f32 },
+    PanStop { x: f32, y: f32 },
+    /// A pan that ended while still moving, with its velocity in units per second.
+    Fling { velocity_x: f32, velocity_y: f32 },
+    /// Two fingers moved apart or together. Divide `distance` by `initial_distance` to get a
+    /// zoom factor.
+    Zoom { initial_distance: f32, distance: f32 },
+    /// Two fingers rotated around each other, by `angle` radians since the pinch started.
+    Rotate { angle: f32 },
+}
+
+/// Thresholds for the gesture detector, in the same units as the touch positions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GestureConfig {
+    /// How far a finger can move and still count as a tap.
+    pub tap_square_size: f32,
+    /// How long between taps for them to count as a double tap, in seconds.
+    pub tap_count_interval: f64,
+    pub long_press_duration: f64,
+    /// A pan turns into a fling if the finger lifts within this many seconds of its last move.
+    pub max_fling_delay: f64,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        GestureConfig {
+            tap_square_size: 0.02,
+            tap_count_interval: 0.4,
+            long_press_duration: 1.1,
+            max_fling_delay: 0.15,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Pointer {
+    id: i64,
+    x: f32,
+    y: f32,
+}
+
+/// Recognizes taps, long presses, pans, flings and pinches from raw touch events, in the style
+/// of libGDX's `GestureDetector`.
+///
+/// Either call `update` once per frame to consume `Input`'s touch events, or feed events in by
+/// hand with `touch_down`, `touch_moved`, `touch_up` and `advance`.
+pub struct GestureDetector {
+    config: GestureConfig,
+    time: f64,
+
+    pointers: Vec<Pointer>,
+    touch_down_time: f64,
+    touch_down_pos: (f32, f32),
+    in_tap_square: bool,
+    long_press_fired: bool,
+    panning: bool,
+
+    last_tap_time: Option<f64>,
+    last_tap_pos: (f32, f32),
+    tap_count: u32,
+
+    // Recent (x, y, time) samples of the panning finger, for fling velocity.
+    samples: Vec<(f32, f32, f64)>,
+
+    pinching: bool,
+    initial_distance: f32,
+    initial_angle: f32,
+}
+
+const MAX_SAMPLES: usize = 10;
+
+impl GestureDetector {
+    pub fn new() -> Self {
+        GestureDetector::with_config(GestureConfig::default())
+    }
+
+    pub fn with_config(config: GestureConfig) -> Self {
+        GestureDetector {
+            config: config,
+            time: 0.0,
+
+            pointers: Vec::new(),
+            touch_down_time: 0.0,
+            touch_down_pos: (0.0, 0.0),
+            in_tap_square: false,
+            long_press_fired: false,
+            panning: false,
+
+            last_tap_time: None,
+            last_tap_pos: (0.0, 0.0),
+            tap_count: 0,
+
+            samples: Vec::new(),
+
+            pinching: false,
+            initial_distance: 0.0,
+            initial_angle: 0.0,
+        }
+    }
+
+    pub fn config(&self) -> &GestureConfig {
+        &self.config
+    }
+
+    pub fn config_mut(&mut self) -> &mut GestureConfig {
+        &mut self.config
+    }
+
+    pub fn is_panning(&self) -> bool {
+        self.panning
+    }
+
+    pub fn is_pinching(&self) -> bool {
+        self.pinching
+    }
+
+    /// Consume this frame's touch events from `input`.
+    pub fn update(&mut self, input: &Input, time: &Time) -> Vec<Gesture> {
+        let mut gestures = self.advance(time.delta_time());
+        for event in input.touch_events() {
+            let touch = event.touch;
+            gestures.extend(match event.phase {
+                TouchPhase::Began => self.touch_down(touch.id, touch.x, touch.y),
+                TouchPhase::Moved => self.touch_moved(touch.id, touch.x, touch.y),
+                TouchPhase::Ended => self.touch_up(touch.id, touch.x, touch.y),
+            });
+        }
+        gestures
+    }
+
+    /// Advance the detector's clock by `dt` seconds. Fires long presses.
+    pub fn advance(&mut self, dt: f64) -> Vec<Gesture> {
+        self.time += dt;
+
+        let mut gestures = Vec::new();
+        if self.pointers.len() == 1 && self.in_tap_square && !self.long_press_fired &&
+            self.time - self.touch_down_time >= self.config.long_press_duration {
+            self.long_press_fired = true;
+            gestures.push(Gesture::LongPress { x: self.touch_down_pos.0, y: self.touch_down_pos.1 });
+        }
+        gestures
+    }
+
+    pub fn touch_down(&mut self, id: i64, x: f32, y: f32) -> Vec<Gesture> {
+        if self.pointers.len() >= 2 || self.pointers.iter().any(|pointer| pointer.id == id) {
+            return Vec::new();
+        }
+        self.pointers.push(Pointer { id: id, x: x, y: y });
+
+        if self.pointers.len() == 1 {
+            self.touch_down_time = self.time;
+            self.touch_down_pos = (x, y);
+            self.in_tap_square = true;
+            self.long_press_fired = false;
+            self.panning = false;
+            self.samples.clear();
+            self.samples.push((x, y, self.time));
+        } else {
+            // A second finger starts a pinch and cancels any tap or pan.
+            self.in_tap_square = false;
+            self.pinching = true;
+            let mut gestures = Vec::new();
+            if self.panning {
+                self.panning = false;
+                gestures.push(Gesture::PanStop { x: self.pointers[0].x, y: self.pointers[0].y });
+            }
+            self.initial_distance = self.pinch_distance();
+            self.initial_angle = self.pinch_angle();
+            return gestures;
+        }
+        Vec::new()
+    }
+
+    pub fn touch_moved(&mut self, id: i64, x: f32, y: f32) -> Vec<Gesture> {
+        let (previous_x, previous_y) = match self.pointers.iter_mut().find(|pointer| pointer.id == id) {
+            Some(pointer) => {
+                let previous = (pointer.x, pointer.y);
+                pointer.x = x;
+                pointer.y = y;
+                previous
+            },
+            None => return Vec::new(),
+        };
+
+        if self.pinching {
+            let mut angle = self.pinch_angle() - self.initial_angle;
+            if angle > PI {
+                angle -= 2.0 * PI;
+            } else if angle < -PI {
+                angle += 2.0 * PI;
+            }
+            return vec![
+                Gesture::Zoom { initial_distance: self.initial_distance, distance: self.pinch_distance() },
+                Gesture::Rotate { angle: angle },
+            ];
+        }
+
+        self.samples.push((x, y, self.time));
+        if self.samples.len() > MAX_SAMPLES {
+            self.samples.remove(0);
+        }
+
+        if self.in_tap_square && !self.is_within_tap_square(x, y, self.touch_down_pos) {
+            self.in_tap_square = false;
+        }
+        if self.in_tap_square {
+            return Vec::new();
+        }
+
+        self.panning = true;
+        vec![Gesture::Pan { x: x, y: y, dx: x - previous_x, dy: y - previous_y }]
+    }
+
+    pub fn touch_up(&mut self, id: i64, x: f32, y: f32) -> Vec<Gesture> {
+        let index = match self.pointers.iter().position(|pointer| pointer.id == id) {
+            Some(index) => index,
+            None => return Vec::new(),
+        };
+        self.pointers.remove(index);
+
+        if self.pinching {
+            // Lifting one finger of a pinch leaves the other one down, but it can't tap.
+            self.pinching = false;
+            self.samples.clear();
+            if let Some(pointer) = self.pointers.first() {
+                self.samples.push((pointer.x, pointer.y, self.time));
+            }
+            return Vec::new();
+        }
+
+        let mut gestures = Vec::new();
+        if self.in_tap_square && !self.long_press_fired {
+            let continues_sequence = match self.last_tap_time {
+                Some(last_tap_time) => self.time - last_tap_time <= self.config.tap_count_interval &&
+                    self.is_within_tap_square(x, y, self.last_tap_pos),
+                None => false,
+            };
+            self.tap_count = if continues_sequence { self.tap_count + 1 } else { 1 };
+            self.last_tap_time = Some(self.time);
+            self.last_tap_pos = (x, y);
+            gestures.push(Gesture::Tap { x: x, y: y, count: self.tap_count });
+        } else if self.panning {
+            self.panning = false;
+            gestures.push(Gesture::PanStop { x: x, y: y });
+
+            let last_move_time = self.samples.last().map(|sample| sample.2).unwrap_or(0.0);
+            if self.time - last_move_time <= self.config.max_fling_delay {
+                let (velocity_x, velocity_y) = self.velocity();
+                if velocity_x != 0.0 || velocity_y != 0.0 {
+                    gestures.push(Gesture::Fling { velocity_x: velocity_x, velocity_y: velocity_y });
+                }
+            }
+        }
+        self.in_tap_square = false;
+        gestures
+    }
+
+    fn is_within_tap_square(&self, x: f32, y: f32, center: (f32, f32)) -> bool {
+        let half_size = self.config.tap_square_size / 2.0;
+        (x - center.0).abs() <= half_size && (y - center.1).abs() <= half_size
+    }
+
+    fn velocity(&self) -> (f32, f32) {
+        match (self.samples.first(), self.samples.last()) {
+            (Some(&(x0, y0, t0)), Some(&(x1, y1, t1))) if t1 > t0 => {
+                let dt = (t1 - t0) as f32;
+                ((x1 - x0) / dt, (y1 - y0) / dt)
+            },
+            _ => (0.0, 0.0),
+        }
+    }
+
+    fn pinch_distance(&self) -> f32 {
+        let (a, b) = (self.pointers[0], self.pointers[1]);
+        ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
+    }
+
+    fn pinch_angle(&self) -> f32 {
+        let (a, b) = (self.pointers[0], self.pointers[1]);
+        (b.y - a.y).atan2(b.x - a.x)
+    }
+}
+
+impl Default for GestureDetector {
+    fn default() -> Self {
+        GestureDetector::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Gesture, GestureDetector};
+
+    #[test]
+    fn taps_and_double_taps() {
+        let mut detector = GestureDetector::new();
+        detector.touch_down(0, 0.5, 0.5);
+        detector.advance(0.1);
+        assert_eq!(detector.touch_up(0, 0.5, 0.5), vec![Gesture::Tap { x: 0.5, y: 0.5, count: 1 }]);
+
+        detector.advance(0.1);
+        detector.touch_down(1, 0.505, 0.5);
+        assert_eq!(detector.touch_up(1, 0.505, 0.5), vec![Gesture::Tap { x: 0.505, y: 0.5, count: 2 }]);
+    }
+
+    #[test]
+    fn long_press_suppresses_tap() {
+        let mut detector = GestureDetector::new();
+        detector.touch_down(0, 0.2, 0.2);
+        assert!(detector.advance(1.0).is_empty());
+        assert_eq!(detector.advance(0.2), vec![Gesture::LongPress { x: 0.2, y: 0.2 }]);
+        assert!(detector.touch_up(0, 0.2, 0.2).is_empty());
+    }
+
+    #[test]
+    fn pan_then_fling() {
+        let mut detector = GestureDetector::new();
+        detector.touch_down(0, 0.1, 0.5);
+        detector.advance(0.05);
+        assert_eq!(detector.touch_moved(0, 0.2, 0.5).len(), 1);
+        detector.advance(0.05);
+        detector.touch_moved(0, 0.3, 0.5);
+
+        let gestures = detector.touch_up(0, 0.3, 0.5);
+        assert_eq!(gestures[0], Gesture::PanStop { x: 0.3, y: 0.5 });
+        match gestures[1] {
+            Gesture::Fling { velocity_x, velocity_y } =>
+                assert!((velocity_x - 2.0).abs() < 1e-4 && velocity_y == 0.0),
+            ref other => panic!("Expected a fling, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn pinch_zooms() {
+        let mut detector = GestureDetector::new();
+        detector.touch_down(0, 0.4, 0.5);
+        detector.touch_down(1, 0.6, 0.5);
+        match detector.touch_moved(1, 0.8, 0.5)[0] {
+            Gesture::Zoom { initial_distance, distance } =>
+                assert!((distance / initial_distance - 2.0).abs() < 1e-4),
+            ref other => panic!("Expected a zoom, got {:?}", other),
+        }
+    }
+}
@@ -5,6 +5,7 @@ extern crate glium_sdl2;
 extern crate image;
 extern crate maybe_owned;
 extern crate moving_average;
+extern crate rand;
 extern crate sdl2;
 
 pub use glium::{Surface, Texture2d};
@@ -12,7 +13,7 @@ use moving_average::MovingAverage;
 
 pub use app::App;
 pub use config::MidgarAppConfig;
-pub use input::{Axis, Button, KeyCode, MouseButton};
+pub use input::{Axis, Button, KeyCode, MouseButton, ScanCode};
 
 use std::time::{
     Duration,
@@ -20,14 +21,16 @@ use std::time::{
 };
 use std::thread;
 
+use audio::Audio;
 use graphics::Graphics;
-use input::{ElementState, Input};
+use input::{ElementState, Input, InputMultiplexer, Touch, TouchPhase};
 use time::Time;
 
 mod app;
+pub mod audio;
 mod config;
 pub mod graphics;
-mod input;
+pub mod input;
 mod time;
 
 
@@ -67,37 +70,90 @@ impl<T: App> MidgarApp<T> {
             self.midgar.delta_times.add(self.midgar.time.delta_time());
 
             self.midgar.input.begin_frame();
+            self.midgar.input.update_haptics(&self.midgar.time);
 
             // Respond to event updates
             for event in self.midgar.event_pump().poll_iter() {
                 use sdl2::event::Event::*;
                 use sdl2::event::WindowEvent;
+                use sdl2::mouse::MouseWheelDirection;
+
+                if let Some(timestamp) = input_event_timestamp(&event) {
+                    self.midgar.input.set_event_timestamp(timestamp);
+                }
                 match event {
                     Quit { .. } => window_closed = true,
 
                     // Window events.
                     Window { win_event, .. } => {
-                        if let WindowEvent::Resized(x, y) = win_event {
-                            resized = Some((x as u32, y as u32));
+                        match win_event {
+                            WindowEvent::Resized(x, y) => resized = Some((x as u32, y as u32)),
+                            WindowEvent::Enter => self.midgar.input.handle_mouse_focus(true),
+                            WindowEvent::Leave => self.midgar.input.handle_mouse_focus(false),
+                            _ => {},
                         }
                     },
 
                     // Keyboard events.
-                    KeyDown { keycode, repeat, .. } => {
+                    KeyDown { keycode, scancode, keymod, repeat, .. } => {
                         if !repeat {
-                            self.midgar.input.handle_keyboard_input(ElementState::Pressed, keycode);
+                            self.midgar.input.handle_keyboard_input(ElementState::Pressed, keycode, scancode, keymod);
+                        } else {
+                            self.midgar.input.handle_key_repeat(keycode, scancode, keymod);
                         }
                     },
-                    KeyUp { keycode, .. } =>
-                        self.midgar.input.handle_keyboard_input(ElementState::Released, keycode),
+                    KeyUp { keycode, scancode, keymod, .. } =>
+                        self.midgar.input.handle_keyboard_input(ElementState::Released, keycode, scancode, keymod),
+
+                    // Text input events.
+                    TextInput { text, .. } =>
+                        self.midgar.input.handle_text_input(&text),
+                    TextEditing { text, start, length, .. } =>
+                        self.midgar.input.handle_text_editing(&text, start, length),
 
                     // Mouse events.
-                    MouseButtonDown { mouse_btn, .. } =>
-                        self.midgar.input.handle_mouse_input(ElementState::Pressed, mouse_btn),
-                    MouseButtonUp { mouse_btn, .. } =>
-                        self.midgar.input.handle_mouse_input(ElementState::Released, mouse_btn),
-                    MouseMotion { x, y, .. } =>
-                        self.midgar.input.handle_mouse_motion(x, y),
+                    MouseButtonDown { mouse_btn, clicks, x, y, .. } =>
+                        self.midgar.input.handle_mouse_input(ElementState::Pressed, mouse_btn, clicks, x, y),
+                    MouseButtonUp { mouse_btn, clicks, x, y, .. } =>
+                        self.midgar.input.handle_mouse_input(ElementState::Released, mouse_btn, clicks, x, y),
+                    MouseMotion { x, y, xrel, yrel, .. } =>
+                        self.midgar.input.handle_mouse_motion(x, y, xrel, yrel),
+                    // TODO: sdl2 0.29 only reports whole wheel clicks, so trackpads scroll in
+                    // steps. Newer sdl2 releases add `precise_x`/`precise_y`; pass those in once
+                    // sdl2 (and glium_sdl2 with it) can be updated.
+                    MouseWheel { x, y, direction, .. } =>
+                        self.midgar.input.handle_mouse_wheel(x as f32, y as f32,
+                                                             direction == MouseWheelDirection::Flipped),
+
+                    // Touch events.
+                    FingerDown { touch_id, finger_id, x, y, pressure, .. } =>
+                        self.midgar.input.handle_touch(TouchPhase::Began, Touch {
+                            id: finger_id, device_id: touch_id, x: x, y: y, pressure: pressure,
+                        }),
+                    FingerMotion { touch_id, finger_id, x, y, pressure, .. } =>
+                        self.midgar.input.handle_touch(TouchPhase::Moved, Touch {
+                            id: finger_id, device_id: touch_id, x: x, y: y, pressure: pressure,
+                        }),
+                    FingerUp { touch_id, finger_id, x, y, pressure, .. } =>
+                        self.midgar.input.handle_touch(TouchPhase::Ended, Touch {
+                            id: finger_id, device_id: touch_id, x: x, y: y, pressure: pressure,
+                        }),
+
+                    // Joystick events. Only devices that aren't game controllers are tracked.
+                    JoyDeviceAdded { which, .. } =>
+                        self.midgar.input.handle_joystick_added(which as i32),
+                    JoyDeviceRemoved { which, .. } =>
+                        self.midgar.input.handle_joystick_removed(which),
+                    JoyAxisMotion { which, axis_idx, value, .. } =>
+                        self.midgar.input.handle_joystick_axis(which, axis_idx, value),
+                    JoyButtonDown { which, button_idx, .. } =>
+                        self.midgar.input.handle_joystick_button(which, ElementState::Pressed, button_idx),
+                    JoyButtonUp { which, button_idx, .. } =>
+                        self.midgar.input.handle_joystick_button(which, ElementState::Released, button_idx),
+                    JoyHatMotion { which, hat_idx, state, .. } =>
+                        self.midgar.input.handle_joystick_hat(which, hat_idx, state),
+                    JoyBallMotion { which, ball_idx, xrel, yrel, .. } =>
+                        self.midgar.input.handle_joystick_ball(which, ball_idx, xrel, yrel),
 
                     // Controller events.
                     ControllerDeviceAdded { which, .. } =>
@@ -127,11 +183,13 @@ impl<T: App> MidgarApp<T> {
                 win_size = cur_win_size;
             }
             if let Some(size) = resized {
+                self.midgar.graphics.update_viewport(size.0, size.1);
                 self.app.resize(size, &self.midgar);
                 resized = None;
             }
 
-            // TODO: Process input events
+            // Let the input processors react to this frame's events, in order.
+            self.midgar.input_processors.process(self.midgar.input.events());
 
             // Call app step func
             self.app.step(&mut self.midgar);
@@ -150,11 +208,30 @@ impl<T: App> MidgarApp<T> {
     }
 }
 
+fn input_event_timestamp(event: &sdl2::event::Event) -> Option<u32> {
+    use sdl2::event::Event::*;
+    match *event {
+        KeyDown { timestamp, .. } | KeyUp { timestamp, .. } |
+        TextInput { timestamp, .. } | TextEditing { timestamp, .. } |
+        MouseButtonDown { timestamp, .. } | MouseButtonUp { timestamp, .. } |
+        MouseMotion { timestamp, .. } | MouseWheel { timestamp, .. } |
+        FingerDown { timestamp, .. } | FingerMotion { timestamp, .. } | FingerUp { timestamp, .. } |
+        JoyDeviceAdded { timestamp, .. } | JoyDeviceRemoved { timestamp, .. } |
+        JoyAxisMotion { timestamp, .. } | JoyButtonDown { timestamp, .. } | JoyButtonUp { timestamp, .. } |
+        JoyHatMotion { timestamp, .. } | JoyBallMotion { timestamp, .. } |
+        ControllerAxisMotion { timestamp, .. } |
+        ControllerButtonDown { timestamp, .. } | ControllerButtonUp { timestamp, .. } => Some(timestamp),
+        _ => None,
+    }
+}
+
 pub struct Midgar {
     sdl_context: sdl2::Sdl,
     time: Time,
     graphics: Graphics,
     input: Input,
+    input_processors: InputMultiplexer,
+    audio: Audio,
 
     frame_times: MovingAverage<f64>,
     delta_times: MovingAverage<f64>,
@@ -165,13 +242,17 @@ impl Midgar {
     fn new(config: &MidgarAppConfig) -> Self {
         let sdl_context = sdl2::init().unwrap();
         let graphics = Graphics::new(config, &sdl_context);
-        let input = Input::new(&sdl_context);
+        let mut input = Input::new(&sdl_context);
+        input.load_controller_mapping_files(config.controller_mappings());
+        let audio = Audio::new(&sdl_context);
 
         Midgar {
             sdl_context: sdl_context,
             time: Time::new(),
             graphics: graphics,
             input: input,
+            input_processors: InputMultiplexer::new(),
+            audio: audio,
 
             frame_times: MovingAverage::new(200),
             delta_times: MovingAverage::new(200),
@@ -195,6 +276,27 @@ impl Midgar {
         &self.input
     }
 
+    pub fn input_mut(&mut self) -> &mut Input {
+        &mut self.input
+    }
+
+    /// The processors that input events are dispatched to each frame, before `App::step`.
+    pub fn input_processors(&self) -> &InputMultiplexer {
+        &self.input_processors
+    }
+
+    pub fn input_processors_mut(&mut self) -> &mut InputMultiplexer {
+        &mut self.input_processors
+    }
+
+    pub fn audio(&self) -> &Audio {
+        &self.audio
+    }
+
+    pub fn audio_mut(&mut self) -> &mut Audio {
+        &mut self.audio
+    }
+
     pub fn frame_time(&self) -> f64 {
         self.frame_times.average()
     }